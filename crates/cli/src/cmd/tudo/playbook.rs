@@ -1,37 +1,120 @@
 //! Playbook command
-use clap::Parser;
-use owo_colors::OwoColorize;
+use clap::{Parser, Subcommand};
+use config::logging::{__tracing as tracing, instrument};
 use serde::Serialize;
-use std::path::PathBuf;
-use config::logging::{__tracing as tracing, info, instrument};
-use executor::playbook::PlaybookExecutor;
-use interpreter::playbook::Playbook;
 
-use crate::cmd::utils::AsyncCmd;
+use crate::{
+    cmd::utils::{AsyncCmd, Cmd},
+    utils::ExitStatus,
+};
 
 mod core;
 pub use self::core::*;
+mod summary;
+pub use self::summary::*;
+mod run;
+pub use self::run::*;
+mod plan;
+pub use self::plan::*;
+mod validate;
+pub use self::validate::*;
+mod graph;
+pub use self::graph::*;
 
-/// CLI arguments for `tudo playbook`.
+/// CLI arguments for `tudo playbook`. `tudo playbook <PLAYBOOK_FILE>` runs the playbook with the default options of
+/// `tudo playbook run`.
 #[derive(Debug, Parser, Serialize)]
-#[clap(next_help_heading = "Playbook options", about = None, long_about = None)]
+#[clap(
+    next_help_heading = "Playbook options",
+    about = None,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct PlaybookArgs {
-    /// Playbook file path.
-    #[serde(skip)]
-    pub playbook_file: PathBuf,
+    #[clap(subcommand)]
+    pub command: Option<PlaybookSubcommands>,
+
+    #[clap(flatten)]
+    pub core: Option<CorePlaybookArgs>,
+}
+
+#[derive(Debug, Subcommand, Serialize)]
+pub enum PlaybookSubcommands {
+    /// Run the playbook
+    Run(RunPlaybookArgs),
+    /// Print what the playbook will execute, without connecting to any RPC provider
+    #[clap(alias = "dry-run")]
+    Plan(PlanPlaybookArgs),
+    /// Check the playbook without executing it, and report every problem with its location
+    Validate(ValidatePlaybookArgs),
+    /// Render the job dependency graph of the workflows as Graphviz DOT or Mermaid
+    Graph(GraphPlaybookArgs),
 }
 
 #[async_trait::async_trait]
 impl AsyncCmd for PlaybookArgs {
-    type Output = ();
+    type Output = ExitStatus;
 
-    /// Parse and run playbook
-    #[instrument(name = "PlaybookCliRun", skip_all)]
+    #[instrument(name = "PlaybookCli", skip_all)]
     async fn run(self) -> eyre::Result<Self::Output> {
-        info!("Running playbook at {:#?}", &self.playbook_file.green());
-        let playbook = Playbook::from_file(self.playbook_file)?;
+        let command = match (self.command, self.core) {
+            (Some(command), _) => command,
+            (None, Some(core)) => PlaybookSubcommands::Run(RunPlaybookArgs {
+                core,
+                report: None,
+                max_parallel: None,
+                resume: None,
+            }),
+            (None, None) => eyre::bail!("a playbook file or a subcommand is required"),
+        };
+        match command {
+            PlaybookSubcommands::Run(cmd) => cmd
+                .run()
+                .await
+                .map(|run_report| ExitStatus::from_report(&run_report)),
+            PlaybookSubcommands::Plan(cmd) => cmd.run().map(|_| ExitStatus::Success),
+            PlaybookSubcommands::Validate(cmd) => cmd.run().map(|diagnostics| {
+                if diagnostics.is_empty() {
+                    ExitStatus::Success
+                } else {
+                    ExitStatus::ValidationError
+                }
+            }),
+            PlaybookSubcommands::Graph(cmd) => cmd.run().map(|_| ExitStatus::Success),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_matches, assert_ok};
+    use std::path::Path;
+
+    #[test]
+    fn can_run_playbook_without_subcommand() {
+        let args = assert_ok!(PlaybookArgs::try_parse_from(["playbook", "playbook.yaml"]));
+        assert_matches!(&args.command, None);
+        assert_eq!(args.core.unwrap().playbook_file, Path::new("playbook.yaml"));
+
+        let args = assert_ok!(PlaybookArgs::try_parse_from([
+            "playbook",
+            "run",
+            "playbook.yaml",
+            "--max-parallel",
+            "2",
+        ]));
+        assert_matches!(&args.command, Some(PlaybookSubcommands::Run(run)) if run.core.playbook_file == Path::new("playbook.yaml"));
+        assert!(args.core.is_none());
+
+        let args = assert_ok!(PlaybookArgs::try_parse_from([
+            "playbook",
+            "validate",
+            "playbook.yaml"
+        ]));
+        assert_matches!(&args.command, Some(PlaybookSubcommands::Validate(_)));
 
-        PlaybookExecutor::run(playbook).await?;
-        Ok(())
+        assert_err!(PlaybookArgs::try_parse_from(["playbook"]));
     }
 }
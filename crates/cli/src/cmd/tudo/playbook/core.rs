@@ -1,5 +1,20 @@
 use clap::Parser;
+use interpreter::playbook::Playbook;
 use serde::Serialize;
+use std::path::PathBuf;
 
+/// Arguments shared by all playbook commands
 #[derive(Debug, Parser, Serialize)]
-pub struct CorePlaybookArgs {}
+pub struct CorePlaybookArgs {
+    /// Playbook file path.
+    #[serde(skip)]
+    pub playbook_file: PathBuf,
+}
+
+impl CorePlaybookArgs {
+    /// Parse the playbook file
+    pub fn load_playbook(&self) -> eyre::Result<Playbook> {
+        let playbook = Playbook::from_file(&self.playbook_file)?;
+        Ok(playbook)
+    }
+}
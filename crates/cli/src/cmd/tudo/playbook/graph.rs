@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use executor::{
+    graph::{GraphFormat, WorkflowGraph},
+    playbook::PlaybookExecutor,
+    report::RunReport,
+};
+use serde::Serialize;
+
+use crate::cmd::utils::Cmd;
+
+use super::CorePlaybookArgs;
+
+/// CLI arguments for `tudo playbook graph`.
+#[derive(Debug, Parser, Serialize)]
+pub struct GraphPlaybookArgs {
+    #[clap(flatten)]
+    pub core: CorePlaybookArgs,
+
+    /// Only render the workflow with this name.
+    #[clap(long)]
+    pub workflow: Option<String>,
+
+    /// Graph format, either `dot` or `mermaid`.
+    #[clap(long, default_value = "dot")]
+    pub format: GraphFormat,
+
+    /// Color jobs by their status in the JSON report written by `tudo playbook run --report`.
+    #[clap(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+}
+
+impl Cmd for GraphPlaybookArgs {
+    type Output = Vec<WorkflowGraph>;
+
+    /// Render the job dependency graph of the playbook workflows
+    fn run(self) -> eyre::Result<Self::Output> {
+        let playbook = self.core.load_playbook()?;
+        let mut workflow_graphs = PlaybookExecutor::graph(&playbook)?;
+
+        if let Some(workflow_name) = &self.workflow {
+            workflow_graphs.retain(|workflow_graph| &workflow_graph.name == workflow_name);
+            if workflow_graphs.is_empty() {
+                eyre::bail!(
+                    "workflow {:?} is not defined in the playbook",
+                    workflow_name
+                );
+            }
+        }
+
+        if let Some(report_path) = &self.report {
+            let run_report: RunReport =
+                serde_json::from_str(&std::fs::read_to_string(report_path)?)?;
+
+            workflow_graphs = workflow_graphs
+                .into_iter()
+                .map(|workflow_graph| {
+                    match run_report
+                        .workflows
+                        .iter()
+                        .find(|workflow_report| workflow_report.name == workflow_graph.name)
+                    {
+                        Some(workflow_report) => workflow_graph.with_report(workflow_report),
+                        None => workflow_graph,
+                    }
+                })
+                .collect();
+        }
+
+        let rendered_graphs: Vec<String> = workflow_graphs
+            .iter()
+            .map(|workflow_graph| workflow_graph.render(self.format))
+            .collect();
+        print!("{}", rendered_graphs.join("\n"));
+
+        Ok(workflow_graphs)
+    }
+}
@@ -0,0 +1,106 @@
+use clap::Parser;
+use executor::{
+    plan::{JobStepPlan, PlaybookPlan},
+    playbook::PlaybookExecutor,
+};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::cmd::utils::Cmd;
+
+use super::CorePlaybookArgs;
+
+/// CLI arguments for `tudo playbook plan`.
+#[derive(Debug, Parser, Serialize)]
+pub struct PlanPlaybookArgs {
+    #[clap(flatten)]
+    pub core: CorePlaybookArgs,
+
+    /// Print the plan as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl Cmd for PlanPlaybookArgs {
+    type Output = PlaybookPlan;
+
+    /// Parse the playbook and print what it will execute, without connecting to any RPC provider
+    fn run(self) -> eyre::Result<Self::Output> {
+        let playbook = self.core.load_playbook()?;
+        let playbook_plan = PlaybookExecutor::plan(&playbook)?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&playbook_plan)?);
+        } else {
+            print_plan(&playbook_plan);
+        }
+        Ok(playbook_plan)
+    }
+}
+
+fn print_plan(playbook_plan: &PlaybookPlan) {
+    for workflow in &playbook_plan.workflows {
+        println!("{} {}", "Workflow".bold(), workflow.name.bold());
+
+        for job in &workflow.jobs {
+            let depends_on = if job.depends_on.is_empty() {
+                String::new()
+            } else {
+                format!(" (depends on {})", job.depends_on.join(", "))
+            };
+            println!(
+                "  [rank {}] {} {}{}",
+                job.rank,
+                "Job".cyan(),
+                job.name,
+                depends_on
+            );
+
+            for step in &job.steps {
+                print_step_plan(step);
+            }
+        }
+        println!();
+    }
+}
+
+fn print_step_plan(step: &JobStepPlan) {
+    println!("    {} {} ({})", "Step".green(), step.name, step.step_type);
+
+    let plan = &step.plan;
+    let fields = [
+        ("title", step.title.clone()),
+        ("rpc provider", plan.rpc_provider.clone()),
+        ("target", plan.target.clone()),
+        ("value", plan.value.clone()),
+        ("function", plan.function_signature.clone()),
+        ("selector", plan.function_selector.clone()),
+        (
+            "calldata",
+            match (&plan.calldata, &plan.function_signature) {
+                (Some(calldata), _) => Some(calldata.clone()),
+                (None, Some(_)) => Some("resolved at run time".to_string()),
+                (None, None) => None,
+            },
+        ),
+        ("deploys at", plan.deployed_address.clone()),
+        (
+            "returns",
+            plan.return_types
+                .as_ref()
+                .map(|return_types| format!("[{}]", return_types.join(", "))),
+        ),
+        (
+            "references",
+            (!plan.references.is_empty()).then(|| plan.references.join(", ")),
+        ),
+        ("save as", step.save_as.clone()),
+    ];
+
+    for (field, value) in fields {
+        if let Some(value) = value {
+            let field = format!("{:<14}", format!("{}:", field));
+            println!("      {}{}", field.dimmed(), value);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use clap::Parser;
+use config::logging::{__tracing as tracing, info, instrument};
+use executor::{playbook::PlaybookExecutor, report::RunReport, state::RunStateStore};
+use interpreter::validation::{PlaybookValidationError, PlaybookValidator};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use std::{num::NonZeroUsize, path::PathBuf, sync::Arc};
+use tokio_util::sync::CancellationToken;
+
+use crate::{cmd::utils::AsyncCmd, utils::cancel_on_shutdown_signal};
+
+use super::{print_diagnostics, print_run_summary, CorePlaybookArgs};
+
+/// CLI arguments for `tudo playbook run`.
+#[derive(Debug, Parser, Serialize)]
+pub struct RunPlaybookArgs {
+    #[clap(flatten)]
+    pub core: CorePlaybookArgs,
+
+    /// Write the run report as JSON to the file.
+    #[clap(long, value_name = "PATH")]
+    #[serde(skip)]
+    pub report: Option<PathBuf>,
+
+    /// Maximum number of jobs running at the same time across all workflows, overrides `max_parallel` of the
+    /// playbook.
+    #[clap(long, value_name = "NUM")]
+    pub max_parallel: Option<NonZeroUsize>,
+
+    /// Resume the run with the id: steps it completed are not executed again and their outputs are restored, sent
+    /// transactions are confirmed instead of being sent again.
+    #[clap(long, value_name = "RUN_ID")]
+    pub resume: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl AsyncCmd for RunPlaybookArgs {
+    type Output = RunReport;
+
+    /// Validate, parse and run playbook, then print the run summary. An invalid playbook is not executed, its
+    /// problems are printed as `tudo playbook validate` does. On Ctrl-C or SIGTERM, the run stops gracefully and the
+    /// partial report is still written and printed. The progress is recorded under `.tudo/runs` of the project root
+    /// of the playbook file, so the run can be resumed.
+    #[instrument(name = "PlaybookCliRun", skip_all)]
+    async fn run(self) -> eyre::Result<Self::Output> {
+        info!(
+            "Running playbook at {:#?}",
+            &self.core.playbook_file.green()
+        );
+        if let Err(error) = PlaybookValidator::check_file(&self.core.playbook_file) {
+            if let PlaybookValidationError::InvalidPlaybook(diagnostics) = &error {
+                print_diagnostics(diagnostics);
+            }
+            return Err(error.into());
+        }
+        let mut playbook = self.core.load_playbook()?;
+        if let Some(max_parallel) = self.max_parallel {
+            playbook.set_max_parallel(Some(max_parallel));
+        }
+
+        let playbook_dir = self
+            .core
+            .playbook_file
+            .canonicalize()?
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        let project_root = config::find_project_root_path(Some(&playbook_dir))?;
+        let run_state = match &self.resume {
+            Some(run_id) => RunStateStore::open(&project_root, run_id, &self.core.playbook_file)?,
+            None => RunStateStore::create(&project_root, &self.core.playbook_file)?,
+        };
+        info!(
+            "Run state is recorded to {:#?}",
+            run_state.file_path().green()
+        );
+
+        let cancellation = CancellationToken::new();
+        cancel_on_shutdown_signal(cancellation.clone());
+        let run_report =
+            PlaybookExecutor::run(playbook, cancellation, Some(Arc::new(run_state))).await?;
+
+        if let Some(report_file) = &self.report {
+            let content = serde_json::to_string_pretty(&run_report)?;
+            std::fs::write(report_file, content)?;
+            info!("Run report is written to {:#?}", report_file.green());
+        }
+
+        print_run_summary(&run_report);
+        Ok(run_report)
+    }
+}
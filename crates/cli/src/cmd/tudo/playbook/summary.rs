@@ -0,0 +1,206 @@
+use executor::report::{RunReport, RunStatus, Timing};
+use owo_colors::OwoColorize;
+
+const HEADERS: [&str; 5] = ["WORKFLOW", "JOB", "STEP", "STATUS", "DURATION"];
+
+/// Row of the summary table
+struct SummaryRow {
+    cells: [String; 5],
+    status: RunStatus,
+}
+
+/// Print the end-of-run summary table of the playbook run, followed by reasons of skipped jobs and errors of failed
+/// workflows, jobs and steps
+pub fn print_run_summary(report: &RunReport) {
+    let mut rows = Vec::new();
+    let mut skipped = Vec::new();
+    let mut errors = Vec::new();
+
+    for workflow in &report.workflows {
+        if workflow.jobs.is_empty() {
+            rows.push(SummaryRow::new(
+                &workflow.name,
+                "-",
+                "-",
+                workflow.status,
+                &workflow.timing,
+            ));
+        }
+        if !workflow.error.is_empty() {
+            errors.push((workflow.name.clone(), workflow.error.join(": ")));
+        }
+
+        for job in &workflow.jobs {
+            if let Some(skipped_reason) = &job.skipped_reason {
+                skipped.push((
+                    format!("{} > {}", workflow.name, job.name),
+                    skipped_reason.clone(),
+                ));
+            }
+            if job.steps.is_empty() {
+                rows.push(SummaryRow::new(
+                    &workflow.name,
+                    &job.name,
+                    "-",
+                    job.status,
+                    &job.timing,
+                ));
+            }
+
+            for step in &job.steps {
+                rows.push(SummaryRow::new(
+                    &workflow.name,
+                    &job.name,
+                    step.title.as_deref().unwrap_or(&step.name),
+                    step.status,
+                    &step.timing,
+                ));
+                if !step.error.is_empty() {
+                    errors.push((
+                        format!("{} > {} > {}", workflow.name, job.name, step.name),
+                        step.error.join(": "),
+                    ));
+                }
+            }
+
+            // job errors which do not come from a failed step
+            if !job.error.is_empty() && job.steps.iter().all(|step| step.error.is_empty()) {
+                errors.push((
+                    format!("{} > {}", workflow.name, job.name),
+                    job.error.join(": "),
+                ));
+            }
+        }
+    }
+
+    let mut widths = HEADERS.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(&row.cells) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    println!();
+    println!("{}", "Run summary".bold());
+    let header = HEADERS
+        .iter()
+        .zip(widths)
+        .map(|(header, width)| format!("{:<width$}", header))
+        .collect::<Vec<_>>()
+        .join("  ");
+    println!("{}", header.trim_end().bold());
+
+    for row in &rows {
+        let cells = row
+            .cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(index, (cell, width))| {
+                let cell = format!("{:<width$}", cell);
+                // only the status column is colored
+                if index == 3 {
+                    colorize(cell, row.status)
+                } else {
+                    cell
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", cells.trim_end());
+    }
+
+    if !skipped.is_empty() {
+        println!();
+        println!("{}", "Skipped".bold());
+        for (source, reason) in &skipped {
+            println!("{} {}", format!("{}:", source).yellow(), reason);
+        }
+    }
+
+    if !errors.is_empty() {
+        println!();
+        println!("{}", "Errors".bold());
+        for (source, error) in &errors {
+            println!("{} {}", format!("{}:", source).red(), error);
+        }
+    }
+
+    let failed_jobs = report
+        .workflows
+        .iter()
+        .flat_map(|workflow| &workflow.jobs)
+        .filter(|job| job.status.is_failure())
+        .count();
+    let total_jobs = report
+        .workflows
+        .iter()
+        .map(|workflow| workflow.jobs.len())
+        .sum::<usize>();
+
+    println!();
+    println!(
+        "Result: {} ({} of {} jobs failed) in {}",
+        colorize(status_text(report.status).to_string(), report.status),
+        failed_jobs,
+        total_jobs,
+        format_duration(&report.timing)
+    );
+    if let Some(run_id) = &report.run_id {
+        if report.is_success() {
+            println!("Run id: {}", run_id);
+        } else {
+            println!("Run id: {} (resume it with `--resume {}`)", run_id, run_id);
+        }
+    }
+}
+
+impl SummaryRow {
+    fn new(workflow: &str, job: &str, step: &str, status: RunStatus, timing: &Timing) -> Self {
+        let duration = match status {
+            RunStatus::Skipped => "-".to_string(),
+            _ => format_duration(timing),
+        };
+
+        Self {
+            cells: [
+                workflow.to_string(),
+                job.to_string(),
+                step.to_string(),
+                status_text(status).to_string(),
+                duration,
+            ],
+            status,
+        }
+    }
+}
+
+fn status_text(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Succeeded => "succeeded",
+        RunStatus::Failed => "failed",
+        RunStatus::Skipped => "skipped",
+        RunStatus::TimedOut => "timed out",
+        RunStatus::Cancelled => "cancelled",
+        RunStatus::Pending => "pending",
+    }
+}
+
+fn colorize(text: String, status: RunStatus) -> String {
+    match status {
+        RunStatus::Succeeded => text.green().to_string(),
+        RunStatus::Failed => text.red().to_string(),
+        RunStatus::Skipped => text.yellow().to_string(),
+        RunStatus::TimedOut => text.magenta().to_string(),
+        RunStatus::Cancelled => text.cyan().to_string(),
+        RunStatus::Pending => text.blue().to_string(),
+    }
+}
+
+fn format_duration(timing: &Timing) -> String {
+    if timing.duration_ms < 1000 {
+        format!("{}ms", timing.duration_ms)
+    } else {
+        format!("{:.2}s", timing.duration_ms as f64 / 1000.0)
+    }
+}
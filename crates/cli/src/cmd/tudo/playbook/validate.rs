@@ -0,0 +1,51 @@
+use clap::Parser;
+use interpreter::{
+    playbook::PlaybookError,
+    validation::{Diagnostic, PlaybookValidator},
+};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use crate::cmd::utils::Cmd;
+
+use super::CorePlaybookArgs;
+
+/// CLI arguments for `tudo playbook validate`.
+#[derive(Debug, Parser, Serialize)]
+pub struct ValidatePlaybookArgs {
+    #[clap(flatten)]
+    pub core: CorePlaybookArgs,
+
+    /// Print the diagnostics as JSON.
+    #[clap(long)]
+    pub json: bool,
+}
+
+impl Cmd for ValidatePlaybookArgs {
+    type Output = Vec<Diagnostic>;
+
+    /// Check the playbook without executing it, and print every problem found
+    fn run(self) -> eyre::Result<Self::Output> {
+        let diagnostics = PlaybookValidator::validate_file(&self.core.playbook_file)
+            .map_err(PlaybookError::from)?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+        } else {
+            print_diagnostics(&diagnostics);
+        }
+        Ok(diagnostics)
+    }
+}
+
+pub(crate) fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        println!("{} {}", "error:".red().bold(), diagnostic);
+    }
+
+    match diagnostics.len() {
+        0 => println!("{}", "Playbook is valid".green()),
+        1 => println!("\n{}", "Found 1 problem".red()),
+        count => println!("\n{}", format!("Found {} problems", count).red()),
+    }
+}
@@ -1,19 +1,29 @@
+use std::process::ExitCode;
+
 use clap::Parser;
 use cli::{
     cli::tudo::{Cli, Subcommands},
     cmd::utils::AsyncCmd,
-    utils,
+    utils::{self, ExitStatus},
 };
 use config::logging::{info, init_tracing_subscriber};
 
 #[tokio::main]
-async fn main() -> eyre::Result<()> {
+async fn main() -> ExitCode {
     init_tracing_subscriber();
     info!("Tracing initialized");
     utils::enable_terminal_colors();
     let cli = Cli::parse();
 
-    match cli.subcommands {
+    let exit_status = match cli.subcommands {
         Subcommands::Playbook(cmd) => cmd.run().await,
+    };
+
+    match exit_status {
+        Ok(exit_status) => exit_status.into(),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitStatus::from_error(&err).into()
+        }
     }
 }
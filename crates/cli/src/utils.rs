@@ -1,3 +1,14 @@
+use std::process::ExitCode;
+
+use executor::{
+    playbook::PlaybookExecutorError,
+    report::{RunReport, RunStatus},
+    workflow::WorkflowExecutorError,
+};
+use interpreter::{playbook::PlaybookError, validation::PlaybookValidationError};
+use owo_colors::OwoColorize;
+use tokio_util::sync::CancellationToken;
+
 /// The version message for the current program, like
 /// `tudo 0.1.0 (f01b232bc 2022-01-22T23:28:39.493201+00:00)`
 pub(crate) const VERSION_MESSAGE: &str = concat!(
@@ -9,6 +20,97 @@ pub(crate) const VERSION_MESSAGE: &str = concat!(
     ")"
 );
 
+/// Exit status of `tudo`. Exit code `2` is used by `clap` when the command line arguments are invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Every workflow, job and step succeeded
+    Success = 0,
+    /// The playbook is valid, but some workflows, jobs or steps failed to execute
+    ExecutionFailure = 1,
+    /// The playbook file could not be read or parsed
+    ParseError = 3,
+    /// The playbook is parsed, but it is not valid to be executed
+    ValidationError = 4,
+    /// The run is interrupted by Ctrl-C or SIGTERM, following the shell convention `128 + SIGINT`
+    Interrupted = 130,
+}
+
+impl ExitStatus {
+    /// Classify the error returned by a command
+    pub fn from_error(error: &eyre::Report) -> Self {
+        if error.downcast_ref::<PlaybookError>().is_some() {
+            return ExitStatus::ParseError;
+        }
+
+        match error.downcast_ref::<PlaybookValidationError>() {
+            Some(PlaybookValidationError::PlaybookError(_)) => return ExitStatus::ParseError,
+            Some(PlaybookValidationError::InvalidPlaybook(_)) => {
+                return ExitStatus::ValidationError
+            }
+            None => {}
+        }
+
+        match error.downcast_ref::<PlaybookExecutorError>() {
+            Some(PlaybookExecutorError::PlaybookVersionNotSupported(_))
+            | Some(PlaybookExecutorError::WorkflowExecutorError(
+                WorkflowExecutorError::WorkflowDAGError(_),
+            )) => ExitStatus::ValidationError,
+            _ => ExitStatus::ExecutionFailure,
+        }
+    }
+
+    /// Exit status of a finished playbook run
+    pub fn from_report(report: &RunReport) -> Self {
+        match report.status {
+            RunStatus::Succeeded => ExitStatus::Success,
+            RunStatus::Cancelled => ExitStatus::Interrupted,
+            _ => ExitStatus::ExecutionFailure,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// Cancel the token on the first Ctrl-C or SIGTERM, so the run stops gracefully and reports what is done. The
+/// process exits at once on the second signal.
+pub fn cancel_on_shutdown_signal(cancellation: CancellationToken) {
+    tokio::spawn(async move {
+        if shutdown_signal().await.is_err() {
+            return;
+        }
+        eprintln!(
+            "{}",
+            "Interrupted, waiting for running steps to stop. Press Ctrl-C again to exit immediately.".yellow()
+        );
+        cancellation.cancel();
+
+        if shutdown_signal().await.is_ok() {
+            std::process::exit(ExitStatus::Interrupted as i32);
+        }
+    });
+}
+
+/// Wait for Ctrl-C, or SIGTERM on Unix
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut sigterm =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result,
+            _ = sigterm.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await
+    }
+}
+
 /// Disables terminal colours if either:
 /// - Running windows and the terminal does not support colour codes.
 /// - Colour has been disabled by some environment variable.
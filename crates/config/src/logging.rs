@@ -5,7 +5,7 @@ use tracing_subscriber::prelude::*;
 pub use tracing as __tracing;
 pub use tracing::{debug, error, info, instrument, trace, warn};
 
-/// Initializes a tracing Subscriber for logging
+/// Initializes a tracing Subscriber for logging. Logs are written to stderr, so command outputs on stdout could be piped
 #[allow(dead_code)]
 pub fn init_tracing_subscriber() {
     tracing_subscriber::Registry::default()
@@ -15,6 +15,6 @@ pub fn init_tracing_subscriber() {
                 .from_env_lossy(),
         )
         .with(ErrorLayer::default())
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init()
 }
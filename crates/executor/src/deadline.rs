@@ -0,0 +1,55 @@
+use std::{future::Future, time::Duration};
+
+use tokio::time::Instant;
+
+/// Deadline of a run which starts now with the timeout, bounded by the deadline of its parent run, e.g. a job in a
+/// workflow
+pub fn deadline_after(
+    timeout: Option<Duration>,
+    parent_deadline: Option<Instant>,
+) -> Option<Instant> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+    match (deadline, parent_deadline) {
+        (Some(deadline), Some(parent_deadline)) => Some(deadline.min(parent_deadline)),
+        (deadline, parent_deadline) => deadline.or(parent_deadline),
+    }
+}
+
+/// Whether the deadline has passed
+pub fn is_expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| deadline <= Instant::now())
+}
+
+/// Run the future until the deadline, return None if the deadline passes first
+pub async fn run_until<F: Future>(deadline: Option<Instant>, future: F) -> Option<F::Output> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_bound_deadline_by_parent_deadline() {
+        let parent_deadline = Instant::now() + Duration::from_secs(10);
+
+        assert_eq!(deadline_after(None, None), None);
+        assert_eq!(
+            deadline_after(None, Some(parent_deadline)),
+            Some(parent_deadline)
+        );
+        assert_eq!(
+            deadline_after(Some(Duration::from_secs(60)), Some(parent_deadline)),
+            Some(parent_deadline)
+        );
+
+        let deadline = deadline_after(Some(Duration::from_secs(1)), Some(parent_deadline)).unwrap();
+        assert!(deadline < parent_deadline);
+        assert!(!is_expired(Some(deadline)));
+        assert!(is_expired(deadline_after(Some(Duration::ZERO), None)));
+    }
+}
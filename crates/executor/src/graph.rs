@@ -0,0 +1,2 @@
+mod workflow_graph;
+pub use workflow_graph::*;
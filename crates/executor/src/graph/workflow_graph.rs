@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, fmt::Write, str::FromStr};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::report::{RunStatus, WorkflowReport};
+
+/// Text format of a rendered [`WorkflowGraph`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = GraphFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(GraphFormatError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum GraphFormatError {
+    #[error("unknown graph format `{0}`, expected `dot` or `mermaid`")]
+    UnknownFormat(String),
+}
+
+/// Job in a [`WorkflowGraph`]
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub rank: u32,
+    pub step_count: usize,
+    /// Status of the job in a finished run, nodes are colored by their status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<RunStatus>,
+}
+
+impl GraphNode {
+    fn label(&self, line_break: &str) -> String {
+        let steps = match self.step_count {
+            1 => "1 step".to_string(),
+            count => format!("{} steps", count),
+        };
+        match self.status {
+            Some(status) => format!(
+                "{}{}{} ({})",
+                self.name,
+                line_break,
+                steps,
+                status_name(status)
+            ),
+            None => format!("{}{}{}", self.name, line_break, steps),
+        }
+    }
+}
+
+/// Dependency graph of the jobs in a workflow
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowGraph {
+    pub name: String,
+    /// Jobs sorted by rank, then by name
+    pub nodes: Vec<GraphNode>,
+    /// Edges from prerequisited jobs to their dependent jobs
+    pub edges: Vec<(String, String)>,
+}
+
+impl WorkflowGraph {
+    /// Set the status of each job from the report of a finished run of the workflow
+    pub fn with_report(mut self, workflow_report: &WorkflowReport) -> Self {
+        for node in self.nodes.iter_mut() {
+            node.status = workflow_report
+                .jobs
+                .iter()
+                .find(|job_report| job_report.name == node.name)
+                .map(|job_report| job_report.status);
+        }
+        self
+    }
+
+    /// Render the graph in the given format
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    /// Nodes grouped by rank
+    fn ranks(&self) -> BTreeMap<u32, Vec<(usize, &GraphNode)>> {
+        let mut ranks: BTreeMap<u32, Vec<(usize, &GraphNode)>> = BTreeMap::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            ranks.entry(node.rank).or_default().push((index, node));
+        }
+        ranks
+    }
+
+    fn node_index(&self, job_name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == job_name)
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        let _ = writeln!(dot, "digraph \"{}\" {{", self.name);
+        let _ = writeln!(dot, "    label=\"{}\";", self.name);
+        let _ = writeln!(dot, "    labelloc=t;");
+        let _ = writeln!(
+            dot,
+            "    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\"];"
+        );
+
+        for (rank, nodes) in self.ranks() {
+            let _ = writeln!(dot, "    subgraph cluster_rank_{} {{", rank);
+            let _ = writeln!(dot, "        label=\"rank {}\";", rank);
+            let _ = writeln!(dot, "        style=dashed;");
+            for (_, node) in nodes {
+                let color = match node.status.map(status_colors) {
+                    Some((fill, stroke)) => {
+                        format!(", fillcolor=\"{}\", color=\"{}\"", fill, stroke)
+                    }
+                    None => String::new(),
+                };
+                let _ = writeln!(
+                    dot,
+                    "        \"{}\" [label=\"{}\"{}];",
+                    node.name,
+                    node.label("\\n"),
+                    color
+                );
+            }
+            let _ = writeln!(dot, "    }}");
+        }
+
+        for (from, to) in &self.edges {
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\";", from, to);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Mermaid node ids are generated from the node index, since job names could contain `-` which has a meaning in
+    /// Mermaid edges
+    fn to_mermaid(&self) -> String {
+        let mut mermaid = String::new();
+
+        let _ = writeln!(mermaid, "---");
+        let _ = writeln!(mermaid, "title: {}", self.name);
+        let _ = writeln!(mermaid, "---");
+        let _ = writeln!(mermaid, "flowchart TD");
+
+        for (rank, nodes) in self.ranks() {
+            let _ = writeln!(mermaid, "    subgraph rank_{}[\"rank {}\"]", rank, rank);
+            for (index, node) in nodes {
+                let _ = writeln!(
+                    mermaid,
+                    "        job_{}[\"{}\"]",
+                    index,
+                    node.label("<br/>")
+                );
+            }
+            let _ = writeln!(mermaid, "    end");
+        }
+
+        for (from, to) in &self.edges {
+            if let (Some(from), Some(to)) = (self.node_index(from), self.node_index(to)) {
+                let _ = writeln!(mermaid, "    job_{} --> job_{}", from, to);
+            }
+        }
+
+        for status in [
+            RunStatus::Succeeded,
+            RunStatus::Failed,
+            RunStatus::TimedOut,
+            RunStatus::Cancelled,
+            RunStatus::Skipped,
+        ] {
+            let node_ids: Vec<String> = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.status == Some(status))
+                .map(|(index, _)| format!("job_{}", index))
+                .collect();
+            if node_ids.is_empty() {
+                continue;
+            }
+
+            let (fill, stroke) = status_colors(status);
+            let _ = writeln!(
+                mermaid,
+                "    classDef {} fill:{},stroke:{}",
+                status_name(status),
+                fill,
+                stroke
+            );
+            let _ = writeln!(
+                mermaid,
+                "    class {} {}",
+                node_ids.join(","),
+                status_name(status)
+            );
+        }
+        mermaid
+    }
+}
+
+fn status_name(status: RunStatus) -> &'static str {
+    match status {
+        RunStatus::Succeeded => "succeeded",
+        RunStatus::Failed => "failed",
+        RunStatus::Skipped => "skipped",
+        RunStatus::TimedOut => "timed_out",
+        RunStatus::Cancelled => "cancelled",
+        RunStatus::Pending => "pending",
+    }
+}
+
+/// Fill and stroke colors of a node with the status
+fn status_colors(status: RunStatus) -> (&'static str, &'static str) {
+    match status {
+        RunStatus::Succeeded => ("#c8e6c9", "#2e7d32"),
+        RunStatus::Failed => ("#ffcdd2", "#c62828"),
+        RunStatus::Skipped => ("#eeeeee", "#9e9e9e"),
+        RunStatus::TimedOut => ("#ffe0b2", "#e65100"),
+        RunStatus::Cancelled => ("#e1bee7", "#6a1b9a"),
+        RunStatus::Pending => ("#bbdefb", "#1565c0"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use interpreter::playbook::Playbook;
+
+    use super::*;
+    use crate::{
+        playbook::PlaybookExecutor,
+        report::{JobReport, Timing},
+    };
+
+    #[test]
+    fn can_render_workflow_graph() {
+        let yaml = r#"
+            version: "1"
+            jobs:
+                job_1:
+                    steps:
+                      - type: BlankStep
+                      - type: BlankStep
+                job_2:
+                    steps:
+                      - type: BlankStep
+                job_3:
+                    steps:
+                      - type: BlankStep
+            workflows:
+                workflow_1:
+                    jobs:
+                    - job_1
+                    - name: job_2
+                      depends_on: [job_1]
+                    - name: job_3
+                      depends_on: [job_1, job_2]
+        "#;
+        let playbook: Playbook = serde_yaml::from_str(yaml).unwrap();
+
+        let workflow_graphs = PlaybookExecutor::graph(&playbook).unwrap();
+        let workflow_graph = &workflow_graphs[0];
+        let ranks: Vec<(&str, u32)> = workflow_graph
+            .nodes
+            .iter()
+            .map(|node| (node.name.as_str(), node.rank))
+            .collect();
+        assert_eq!(ranks, vec![("job_1", 0), ("job_2", 1), ("job_3", 2)]);
+        assert_eq!(workflow_graph.edges.len(), 3);
+
+        let dot = workflow_graph.render(GraphFormat::Dot);
+        assert!(dot.contains("subgraph cluster_rank_0 {"));
+        assert!(dot.contains("\"job_1\" [label=\"job_1\\n2 steps\"];"));
+        assert!(dot.contains("\"job_1\" -> \"job_3\";"));
+
+        let workflow_report = WorkflowReport::new(
+            "workflow_1",
+            Timing::default(),
+            vec![JobReport::new(
+                "job_1",
+                Timing::default(),
+                None,
+                vec![],
+                &Default::default(),
+            )],
+        );
+        let workflow_graph = workflow_graph.clone().with_report(&workflow_report);
+
+        let mermaid = workflow_graph.render(GraphFormat::Mermaid);
+        assert!(mermaid.contains("job_0[\"job_1<br/>2 steps (succeeded)\"]"));
+        assert!(mermaid.contains("job_0 --> job_2"));
+        assert!(mermaid.contains("class job_0 succeeded"));
+        assert!(!mermaid.contains("classDef failed"));
+    }
+}
@@ -1,15 +1,29 @@
 use std::collections::HashMap;
 
-use interpreter::step::StepName;
+use ethers::abi::Token;
+use interpreter::{
+    job::JobName,
+    step::StepName,
+    types::{OutputValues, SavedOutputs, VariableName},
+};
 use shared::StepOutput;
 use thiserror::Error;
 
 #[derive(Clone, Default)]
 pub struct JobContext {
     step_outputs: HashMap<StepName, StepOutput>,
+    outputs: OutputValues,
 }
 
 impl JobContext {
+    /// Create job context whose steps can reference outputs of the given ancestor jobs
+    pub fn with_job_outputs(job_outputs: HashMap<JobName, SavedOutputs>) -> Self {
+        Self {
+            step_outputs: HashMap::new(),
+            outputs: OutputValues::with_job_outputs(job_outputs),
+        }
+    }
+
     /// Add step output to job context
     pub fn add_step_output(
         &mut self,
@@ -23,10 +37,36 @@ impl JobContext {
             Err(JobContextError::StepExisted(step_name.clone()))
         }
     }
+
+    /// Get output of an executed step
+    pub fn step_output(&self, step_name: &StepName) -> Option<&StepOutput> {
+        self.step_outputs.get(step_name)
+    }
+
+    /// Save step output value, so later steps of the job can reference it by its variable name
+    pub fn save_output(
+        &mut self,
+        variable_name: &VariableName,
+        value: Token,
+    ) -> Result<(), JobContextError> {
+        if !self.outputs.contains_key(variable_name) {
+            self.outputs.insert(variable_name.clone(), value);
+            Ok(())
+        } else {
+            Err(JobContextError::OutputExisted(variable_name.clone()))
+        }
+    }
+
+    /// Get step output values, including outputs of ancestor jobs
+    pub fn outputs(&self) -> &OutputValues {
+        &self.outputs
+    }
 }
 
 #[derive(Debug, Error)]
 pub enum JobContextError {
     #[error("step with name {:#?} already existed", .0)]
     StepExisted(StepName),
+    #[error("output with name {:#?} already existed", .0)]
+    OutputExisted(VariableName),
 }
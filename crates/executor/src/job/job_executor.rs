@@ -1,31 +1,260 @@
+use config::logging::{__tracing as tracing, error, info, instrument};
+use ethers::types::TxHash;
+use interpreter::{
+    job::{JobConfig, JobName},
+    step::StepConfig,
+    types::SavedOutputs,
+    workflow::WorkflowName,
+};
+use shared::{SendTransactionOutput, StepOutput, TransactionSentHook, TransactionSentHookError};
+use std::collections::HashMap;
 use thiserror::Error;
-use config::logging::{__tracing as tracing, info, instrument};
-use interpreter::job::{JobConfig, JobName};
+use tokio::time::Instant;
 
 use crate::{
+    deadline::deadline_after,
     job::JobContext,
+    playbook::PlaybookContext,
+    report::{step_output_to_json, JobReport, RunStatus, StepReport, Timer},
+    state::{StepState, TokenValue},
     step::{ExecuteStepError, StepExecutor},
 };
 
 pub struct JobExecutor {}
 
 impl JobExecutor {
-    #[instrument(name = "JobExecute", skip(job_config))]
+    /// Execute steps of the job, `job_outputs` are the outputs of its ancestor jobs which the steps can reference.
+    /// Steps after a failed step are skipped, the running step is cancelled once the `timeout` of the job or the
+    /// deadline of its workflow passes. Once the run is interrupted, no more step starts and the job is cancelled.
+    /// Steps completed by the resumed run are not executed again, their saved outputs are restored instead.
+    /// Return the job context with outputs of the executed steps, and the job report.
+    #[instrument(name = "JobExecute", skip(job_config, playbook_context, job_outputs))]
     pub async fn execute(
+        workflow_name: &WorkflowName,
         job_name: &JobName,
         job_config: &JobConfig,
-    ) -> Result<JobContext, ExecuteJobError> {
+        playbook_context: &PlaybookContext,
+        job_outputs: HashMap<JobName, SavedOutputs>,
+        workflow_deadline: Option<Instant>,
+    ) -> (JobContext, JobReport) {
         info!("Executing job {:#?}", job_name);
 
-        let mut job_context = JobContext::default();
+        let timer = Timer::start();
+        let deadline = deadline_after(job_config.timeout(), workflow_deadline);
+        let mut job_context = JobContext::with_job_outputs(job_outputs);
+        let mut step_reports = Vec::with_capacity(job_config.steps().len());
+        let mut job_error: Option<ExecuteJobError> = None;
+        let mut is_cancelled = false;
 
         for step in job_config.steps() {
-            StepExecutor::execute(step, &mut job_context).await?;
+            is_cancelled |= playbook_context.cancellation().is_cancelled();
+            if job_error.is_some() || is_cancelled {
+                step_reports.push(
+                    StepReport::skipped(step.name.as_str()).with_title(step.title.as_deref()),
+                );
+                continue;
+            }
+
+            let step_state = playbook_context.run_state().and_then(|run_state| {
+                run_state.step(
+                    workflow_name.as_str(),
+                    job_name.as_str(),
+                    step.name.as_str(),
+                )
+            });
+            if let Some(step_state) = step_state
+                .as_ref()
+                .filter(|step_state| Self::is_step_completed(step, step_state))
+            {
+                info!("Step {:#?} is completed by the resumed run", step.name);
+
+                match Self::restore_step_output(step, step_state, &mut job_context) {
+                    Ok(()) => step_reports.push(
+                        StepReport::resumed(step.name.as_str(), step_state.output.clone())
+                            .with_title(step.title.as_deref()),
+                    ),
+                    Err(err) => {
+                        error!("error restore step {:#?}: {}", step.name, err);
+                        step_reports.push(
+                            StepReport::failed(step.name.as_str(), Default::default(), &err)
+                                .with_title(step.title.as_deref()),
+                        );
+                        job_error = Some(err.into());
+                    }
+                }
+                continue;
+            }
+            // the transaction sent by the resumed run is confirmed, never sent again
+            let sent_tx_hash = step_state.and_then(|step_state| step_state.pending_tx_hash());
+
+            let step_timer = Timer::start();
+            let (result, attempts) = StepExecutor::execute_with_retry(
+                step,
+                &mut job_context,
+                playbook_context,
+                deadline,
+                sent_tx_hash,
+                Self::on_tx_sent(workflow_name, job_name, step, playbook_context),
+            )
+            .await;
+            let step_report = match &result {
+                Ok(()) => StepReport::succeeded(
+                    step.name.as_str(),
+                    step_timer.stop(),
+                    job_context.step_output(&step.name).and_then(|step_output| {
+                        let setup = playbook_context.shared_setup();
+                        let return_types = step.return_types(setup.as_deref());
+                        step_output_to_json(step_output, return_types.as_deref())
+                    }),
+                ),
+                Err(err) => {
+                    error!("error execute step {:#?}: {}", step.name, err);
+                    let mut step_report =
+                        StepReport::failed(step.name.as_str(), step_timer.stop(), err);
+                    if err.is_timeout() {
+                        step_report.status = RunStatus::TimedOut;
+                    } else if err.is_cancelled() {
+                        step_report.status = RunStatus::Cancelled;
+                    }
+                    step_report
+                }
+            }
+            .with_title(step.title.as_deref())
+            .with_attempts(attempts);
+
+            if let Some(run_state) = playbook_context.run_state() {
+                let step_state =
+                    Self::step_state(step, &step_report, &result, sent_tx_hash, &job_context);
+                if let Err(err) = run_state.record_step(
+                    workflow_name.as_str(),
+                    job_name.as_str(),
+                    step.name.as_str(),
+                    step_state,
+                ) {
+                    error!("error record state of step {:#?}: {}", step.name, err);
+                }
+            }
+
+            step_reports.push(step_report);
+            if let Err(err) = result {
+                job_error = Some(err.into());
+            }
         }
 
         info!("Finish executing the job {:#?}", job_name);
 
-        Ok(job_context)
+        let mut job_report = JobReport::new(
+            job_name.as_str(),
+            timer.stop(),
+            job_error
+                .as_ref()
+                .map(|err| err as &(dyn std::error::Error + 'static)),
+            step_reports,
+            job_context.outputs().saved(),
+        );
+        match &job_error {
+            Some(err) if err.is_timeout() => job_report.status = RunStatus::TimedOut,
+            Some(err) if err.is_cancelled() => job_report.status = RunStatus::Cancelled,
+            None if is_cancelled => job_report.status = RunStatus::Cancelled,
+            _ => {}
+        }
+        (job_context, job_report)
+    }
+
+    /// Whether the step is completed by the resumed run, along with the value it saves if any
+    fn is_step_completed(step: &StepConfig, step_state: &StepState) -> bool {
+        step_state.is_completed() && (step.output.is_none() || step_state.saved_value.is_some())
+    }
+
+    /// Restore the value saved by a step completed by the resumed run, so later steps can reference it
+    fn restore_step_output(
+        step: &StepConfig,
+        step_state: &StepState,
+        job_context: &mut JobContext,
+    ) -> Result<(), ExecuteStepError> {
+        if let (Some(output), Some(saved_value)) = (&step.output, &step_state.saved_value) {
+            job_context.save_output(&output.save_as, saved_value.clone().into())?;
+        }
+        Ok(())
+    }
+
+    /// Hook recording the hash of the transaction sent by the step as soon as it is known, so the transaction is
+    /// confirmed instead of being sent again if the run stops before the step finishes. The run state file is written
+    /// off the async runtime threads, and the step fails if it cannot be written.
+    fn on_tx_sent(
+        workflow_name: &WorkflowName,
+        job_name: &JobName,
+        step: &StepConfig,
+        playbook_context: &PlaybookContext,
+    ) -> Option<TransactionSentHook> {
+        let run_state = playbook_context.shared_run_state()?;
+        let workflow_name = workflow_name.to_string();
+        let job_name = job_name.to_string();
+        let step_name = step.name.to_string();
+
+        Some(TransactionSentHook::new(move |tx_hash| {
+            info!("Transaction {:?} of step {:#?} is sent", tx_hash, step_name);
+            let run_state = run_state.clone();
+            let (workflow_name, job_name, step_name) =
+                (workflow_name.clone(), job_name.clone(), step_name.clone());
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    run_state.record_step(
+                        &workflow_name,
+                        &job_name,
+                        &step_name,
+                        StepState::pending(tx_hash),
+                    )
+                })
+                .await??;
+                Ok::<(), TransactionSentHookError>(())
+            }
+        }))
+    }
+
+    /// State of the executed step to record. The hash of a transaction sent by the resumed run is kept until the
+    /// transaction is confirmed. A reverted transaction is recorded as final, so a resumed run executes the step
+    /// again.
+    fn step_state(
+        step: &StepConfig,
+        step_report: &StepReport,
+        result: &Result<(), ExecuteStepError>,
+        sent_tx_hash: Option<TxHash>,
+        job_context: &JobContext,
+    ) -> StepState {
+        match result {
+            Ok(()) => {
+                let step_output = job_context.step_output(&step.name);
+                let tx_hash = match step_output {
+                    Some(StepOutput::SendTransactionOutput(
+                        SendTransactionOutput::TransactionReceipt(Some(receipt)),
+                    )) => Some(receipt.transaction_hash),
+                    Some(StepOutput::DeployContractOutput(output)) => {
+                        Some(output.tx_receipt.transaction_hash)
+                    }
+                    _ => None,
+                };
+
+                StepState {
+                    status: step_report.status,
+                    saved_value: step
+                        .output
+                        .as_ref()
+                        .and_then(|output| job_context.outputs().get(&output.save_as))
+                        .map(TokenValue::from),
+                    output: step_report.output.clone(),
+                    tx_hash,
+                    tx_reverted: false,
+                }
+            }
+            Err(err) => StepState {
+                status: step_report.status,
+                saved_value: None,
+                output: None,
+                tx_hash: err.tx_hash().or(sent_tx_hash),
+                tx_reverted: err.is_reverted(),
+            },
+        }
     }
 }
 
@@ -34,3 +263,187 @@ pub enum ExecuteJobError {
     #[error(transparent)]
     ExecuteStepError(#[from] ExecuteStepError),
 }
+
+impl ExecuteJobError {
+    /// Whether the job is cancelled by a deadline
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            ExecuteJobError::ExecuteStepError(err) => err.is_timeout(),
+        }
+    }
+
+    /// Whether the job is interrupted by the cancellation of the run
+    pub fn is_cancelled(&self) -> bool {
+        match self {
+            ExecuteJobError::ExecuteStepError(err) => err.is_cancelled(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, sync::Arc};
+
+    use ethers::{abi::Token, types::H160};
+    use interpreter::types::VariableName;
+    use shared::StepError;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{playbook::PlaybookContextBuilder, state::RunStateStore};
+
+    #[tokio::test]
+    async fn can_resume_completed_steps() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_root = temp_dir.path().to_path_buf();
+        let playbook_file = project_root.join("playbook.yaml");
+        std::fs::write(&playbook_file, "version: \"1\"").unwrap();
+
+        // no RPC provider listens on the port, so the step fails if it is executed again
+        let job_config: JobConfig = serde_yaml::from_str(
+            r#"
+            steps:
+              - type: CallContract
+                name: get_owner
+                arguments:
+                    chain_rpc_url: "http://localhost:1"
+                    contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                    function_signature: "owner()"
+                    function_arguments: []
+                output:
+                    save_as: OWNER
+              - type: BlankStep
+                name: after_get_owner
+        "#,
+        )
+        .unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let job_name = JobName::from_str("job_1").unwrap();
+        let owner = Token::Address(H160::repeat_byte(1));
+
+        let run_state = RunStateStore::create(&project_root, &playbook_file).unwrap();
+        run_state
+            .record_step(
+                "workflow_1",
+                "job_1",
+                "get_owner",
+                StepState {
+                    status: RunStatus::Succeeded,
+                    saved_value: Some(TokenValue::from(&owner)),
+                    output: None,
+                    tx_hash: None,
+                    tx_reverted: false,
+                },
+            )
+            .unwrap();
+        let playbook_context = PlaybookContextBuilder::default()
+            .run_state(Some(Arc::new(run_state)))
+            .build()
+            .unwrap();
+
+        let (job_context, job_report) = JobExecutor::execute(
+            &workflow_name,
+            &job_name,
+            &job_config,
+            &playbook_context,
+            HashMap::new(),
+            None,
+        )
+        .await;
+        assert_eq!(job_report.status, RunStatus::Succeeded);
+        assert!(job_report.steps[0].resumed);
+        assert!(!job_report.steps[1].resumed);
+        assert_eq!(
+            job_context
+                .outputs()
+                .get(&VariableName::from_str("OWNER").unwrap()),
+            Some(&owner)
+        );
+
+        let run_state = playbook_context.run_state().unwrap();
+        assert!(run_state
+            .step("workflow_1", "job_1", "after_get_owner")
+            .is_some_and(|step_state| step_state.is_completed()));
+    }
+
+    #[tokio::test]
+    async fn can_record_sent_transactions_at_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_root = temp_dir.path().to_path_buf();
+        let playbook_file = project_root.join("playbook.yaml");
+        std::fs::write(&playbook_file, "version: \"1\"").unwrap();
+
+        let step: StepConfig = serde_yaml::from_str(
+            r#"
+            type: SendTransaction
+            name: transfer
+            arguments:
+                chain_rpc_url: "http://localhost:1"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+        "#,
+        )
+        .unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let job_name = JobName::from_str("job_1").unwrap();
+        let run_state = RunStateStore::create(&project_root, &playbook_file).unwrap();
+        let playbook_context = PlaybookContextBuilder::default()
+            .run_state(Some(Arc::new(run_state)))
+            .build()
+            .unwrap();
+
+        let on_tx_sent =
+            JobExecutor::on_tx_sent(&workflow_name, &job_name, &step, &playbook_context).unwrap();
+        on_tx_sent.call(TxHash::repeat_byte(1)).await.unwrap();
+
+        // the pending transaction is in the run state file before the step finishes
+        let run_state = playbook_context.run_state().unwrap();
+        let run_state =
+            RunStateStore::open(&project_root, &run_state.run_id(), &playbook_file).unwrap();
+        let step_state = run_state.step("workflow_1", "job_1", "transfer").unwrap();
+        assert_eq!(step_state.status, RunStatus::Pending);
+        assert_eq!(step_state.tx_hash, Some(TxHash::repeat_byte(1)));
+        assert!(!step_state.is_completed());
+
+        // the step fails if the transaction is not recorded
+        std::fs::remove_dir_all(project_root.join(RunStateStore::DIRECTORY)).unwrap();
+        assert!(on_tx_sent.call(TxHash::repeat_byte(2)).await.is_err());
+    }
+
+    #[test]
+    fn can_record_reverted_transactions_as_final() {
+        let step: StepConfig = serde_yaml::from_str(
+            r#"
+            type: SendTransaction
+            name: transfer
+            arguments:
+                chain_rpc_url: "http://localhost:1"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+        "#,
+        )
+        .unwrap();
+        let err: ExecuteStepError = StepError::TransactionReverted {
+            tx_hash: TxHash::repeat_byte(1),
+        }
+        .into();
+        let step_report = StepReport::failed("transfer", Timer::start().stop(), &err);
+        let result = Err(err);
+
+        // the revert is seen by the run sending the transaction, or by the resumed run confirming it
+        for sent_tx_hash in [None, Some(TxHash::repeat_byte(1))] {
+            let step_state = JobExecutor::step_state(
+                &step,
+                &step_report,
+                &result,
+                sent_tx_hash,
+                &JobContext::default(),
+            );
+            assert_eq!(step_state.status, RunStatus::Failed);
+            assert_eq!(step_state.tx_hash, Some(TxHash::repeat_byte(1)));
+            assert_eq!(step_state.pending_tx_hash(), None);
+        }
+    }
+}
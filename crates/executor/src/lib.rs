@@ -1,5 +1,10 @@
+pub mod deadline;
+pub mod graph;
 pub mod job;
+pub mod plan;
 pub mod playbook;
+pub mod report;
+pub mod state;
 pub mod step;
 pub mod workflow;
 mod workflow_controller;
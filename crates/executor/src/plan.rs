@@ -0,0 +1,2 @@
+mod playbook_plan;
+pub use playbook_plan::*;
@@ -0,0 +1,41 @@
+use interpreter::step::StepPlan;
+use serde::Serialize;
+
+/// Execution plan of a playbook, built without connecting to any RPC provider
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybookPlan {
+    pub workflows: Vec<WorkflowPlan>,
+}
+
+/// Execution plan of a workflow
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkflowPlan {
+    pub name: String,
+    /// Jobs in execution order, jobs with the same rank could be executed in parallel
+    pub jobs: Vec<JobPlan>,
+}
+
+/// Execution plan of a job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobPlan {
+    pub name: String,
+    /// Length of the longest dependency chain before the job
+    pub rank: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Steps in execution order
+    pub steps: Vec<JobStepPlan>,
+}
+
+/// Execution plan of a step in a job
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStepPlan {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub step_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_as: Option<String>,
+    #[serde(flatten)]
+    pub plan: StepPlan,
+}
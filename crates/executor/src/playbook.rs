@@ -2,3 +2,7 @@ mod playbook_executor;
 pub use playbook_executor::*;
 mod playbook_context;
 pub use playbook_context::*;
+mod rpc_provider_pool;
+pub use rpc_provider_pool::*;
+mod signer_pool;
+pub use signer_pool::*;
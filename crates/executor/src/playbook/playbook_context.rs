@@ -3,13 +3,33 @@ use std::sync::Arc;
 use derive_builder::Builder;
 use futures::lock::Mutex;
 use interpreter::playbook::Setup;
+use tokio::{sync::Semaphore, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+use super::{RpcProviderPool, SignerPool};
+use crate::state::RunStateStore;
 
 /// Playbook context, could be shared with the whole playbook by using [`SharedMutexPlaybookContext`]
-#[allow(dead_code)]
-#[derive(Debug, Builder)]
+#[derive(Debug, Clone, Builder)]
 pub struct PlaybookContext {
     #[builder(default)]
     shared_setup: Option<Arc<Setup>>,
+    #[builder(default)]
+    rpc_provider_pool: Arc<RpcProviderPool>,
+    #[builder(default)]
+    signer_pool: Arc<SignerPool>,
+    /// Permits of jobs running at the same time across all workflows, unlimited if not set
+    #[builder(default)]
+    job_permits: Option<Arc<Semaphore>>,
+    /// Instant the playbook times out at, unlimited if not set
+    #[builder(default)]
+    deadline: Option<Instant>,
+    /// Cancelled once the run is interrupted, no job or step starts after that
+    #[builder(default)]
+    cancellation: CancellationToken,
+    /// Store recording the progress of the run, completed steps recorded in it are not executed again
+    #[builder(default)]
+    run_state: Option<Arc<RunStateStore>>,
 }
 
 pub type SharedMutexPlaybookContext = Arc<Mutex<PlaybookContext>>;
@@ -18,4 +38,50 @@ impl PlaybookContext {
     pub fn into_shared_mutex(self) -> SharedMutexPlaybookContext {
         Arc::new(Mutex::new(self))
     }
+
+    /// Get shared setup config
+    pub fn shared_setup(&self) -> Option<Arc<Setup>> {
+        self.shared_setup.clone()
+    }
+
+    /// Get the pool of RPC providers connected during the playbook run
+    pub fn rpc_provider_pool(&self) -> &RpcProviderPool {
+        &self.rpc_provider_pool
+    }
+
+    /// Get the pool of signers used during the playbook run
+    pub fn signer_pool(&self) -> &SignerPool {
+        &self.signer_pool
+    }
+
+    /// Get permits of jobs running at the same time across all workflows
+    pub fn job_permits(&self) -> Option<Arc<Semaphore>> {
+        self.job_permits.clone()
+    }
+
+    /// Get the instant the playbook times out at
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Get the token which is cancelled once the run is interrupted
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Replace the cancellation token, e.g. with a child token cancelling only the jobs of a workflow
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Get the store recording the progress of the run
+    pub fn run_state(&self) -> Option<&RunStateStore> {
+        self.run_state.as_deref()
+    }
+
+    /// Get the store recording the progress of the run, to record it from another task
+    pub fn shared_run_state(&self) -> Option<Arc<RunStateStore>> {
+        self.run_state.clone()
+    }
 }
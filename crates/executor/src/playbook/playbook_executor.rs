@@ -1,17 +1,38 @@
 use super::{PlaybookContextBuilder, PlaybookContextBuilderError};
-use crate::workflow::WorkflowExecutor;
-use thiserror::Error;
+use crate::deadline::deadline_after;
+use crate::graph::WorkflowGraph;
+use crate::plan::PlaybookPlan;
+use crate::report::{RunReport, Timer, WorkflowReport};
+use crate::state::RunStateStore;
+use crate::workflow::{WorkflowExecutor, WorkflowExecutorError};
 use config::logging::{__tracing as tracing, error, instrument};
 use interpreter::playbook::{Playbook, Version};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
 
 pub struct PlaybookExecutor {}
 
 impl PlaybookExecutor {
     /// Execute the playbook and produce outputs.
-    /// Workflows in the playbook are executed in parallel, or sequentially by depending on each others.
-    pub async fn run(playbook: Playbook) -> Result<(), PlaybookExecutorError> {
+    /// Workflows in the playbook are executed in parallel, or sequentially by depending on each others. At most
+    /// `max_parallel` jobs of the playbook run at the same time, and running jobs are cancelled once the `timeout` of
+    /// the playbook passes.
+    /// Once `cancellation` is cancelled, no more job or step starts and running steps are interrupted, except that
+    /// sent transactions are recorded by their hashes instead of being awaited.
+    /// The progress is recorded into `run_state` if set. Steps it records as completed are not executed again and
+    /// their saved outputs are restored, steps whose transaction is sent but not confirmed wait for its receipt
+    /// instead of sending it again.
+    /// Return the [`RunReport`] of every workflow, job and step, which is partial if the run is cancelled. Failures
+    /// during the run are recorded in the report.
+    pub async fn run(
+        playbook: Playbook,
+        cancellation: CancellationToken,
+        run_state: Option<Arc<RunStateStore>>,
+    ) -> Result<RunReport, PlaybookExecutorError> {
         match playbook.version() {
-            Version::V1 => Self::run_v1(playbook).await,
+            Version::V1 => Self::run_v1(playbook, cancellation, run_state).await,
             #[allow(unreachable_patterns)]
             other => Err(PlaybookExecutorError::PlaybookVersionNotSupported(
                 other.to_string(),
@@ -19,12 +40,69 @@ impl PlaybookExecutor {
         }
     }
 
+    /// Build the execution plan of the playbook without connecting to any RPC provider. Workflows are ordered by
+    /// name.
+    pub fn plan(playbook: &Playbook) -> Result<PlaybookPlan, PlaybookExecutorError> {
+        match playbook.version() {
+            Version::V1 => {}
+            #[allow(unreachable_patterns)]
+            other => {
+                return Err(PlaybookExecutorError::PlaybookVersionNotSupported(
+                    other.to_string(),
+                ))
+            }
+        }
+
+        let workflows = playbook.shared_workflows();
+        let mut workflow_names = workflows.keys().collect::<Vec<_>>();
+        workflow_names.sort_by_key(|workflow_name| workflow_name.as_str());
+
+        let workflows = workflow_names
+            .into_iter()
+            .map(|workflow_name| {
+                WorkflowExecutor::plan(
+                    workflow_name,
+                    &workflows[workflow_name],
+                    playbook.shared_setup(),
+                )
+            })
+            .collect::<Result<Vec<_>, WorkflowExecutorError>>()?;
+
+        Ok(PlaybookPlan { workflows })
+    }
+
+    /// Build the job dependency graph of each workflow in the playbook. Workflows are ordered by name.
+    pub fn graph(playbook: &Playbook) -> Result<Vec<WorkflowGraph>, PlaybookExecutorError> {
+        let workflows = playbook.shared_workflows();
+        let mut workflow_names = workflows.keys().collect::<Vec<_>>();
+        workflow_names.sort_by_key(|workflow_name| workflow_name.as_str());
+
+        let workflow_graphs = workflow_names
+            .into_iter()
+            .map(|workflow_name| WorkflowExecutor::graph(workflow_name, &workflows[workflow_name]))
+            .collect::<Result<Vec<_>, WorkflowExecutorError>>()?;
+
+        Ok(workflow_graphs)
+    }
+
     /// Execute the playbook version 1
     #[instrument(name = "PlaybookExecutorV1", skip_all)]
-    async fn run_v1(playbook: Playbook) -> Result<(), PlaybookExecutorError> {
+    async fn run_v1(
+        playbook: Playbook,
+        cancellation: CancellationToken,
+        run_state: Option<Arc<RunStateStore>>,
+    ) -> Result<RunReport, PlaybookExecutorError> {
+        let timer = Timer::start();
         let shared_setup = playbook.shared_setup();
+        let job_permits = playbook
+            .max_parallel()
+            .map(|max_parallel| Arc::new(Semaphore::new(max_parallel.get())));
         let playbook_context = PlaybookContextBuilder::default()
             .shared_setup(shared_setup)
+            .job_permits(job_permits)
+            .deadline(deadline_after(playbook.timeout(), None))
+            .cancellation(cancellation)
+            .run_state(run_state.clone())
             .build()?
             .into_shared_mutex();
 
@@ -38,14 +116,36 @@ impl PlaybookExecutor {
                 let playbook_context = playbook_context.clone();
 
                 async move {
-                    WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context)
-                        .await
+                    let timer = Timer::start();
+
+                    WorkflowExecutor::execute(
+                        workflow_name.clone(),
+                        workflow_config,
+                        playbook_context,
+                    )
+                    .await
+                    .unwrap_or_else(|err| {
+                        error!("error execute workflow {:#?}: {}", workflow_name, err);
+                        WorkflowReport::failed(workflow_name.as_str(), timer.stop(), &err)
+                    })
                 }
             })
         });
-        futures::future::join_all(tasks).await;
+        let workflow_results = futures::future::join_all(tasks).await;
 
-        Ok(())
+        let workflow_reports = workflows
+            .keys()
+            .zip(workflow_results)
+            .map(|(workflow_name, workflow_result)| {
+                workflow_result.unwrap_or_else(|err| {
+                    error!("error join workflow {:#?}: {}", workflow_name, err);
+                    WorkflowReport::failed(workflow_name.as_str(), timer.stop(), &err)
+                })
+            })
+            .collect();
+
+        Ok(RunReport::new(timer.stop(), workflow_reports)
+            .with_run_id(run_state.map(|run_state| run_state.run_id())))
     }
 }
 
@@ -57,4 +157,6 @@ pub enum PlaybookExecutorError {
     PlaybookContextBuilderError(#[from] PlaybookContextBuilderError),
     #[error(transparent)]
     TokioTaskJoinError(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    WorkflowExecutorError(#[from] WorkflowExecutorError),
 }
@@ -0,0 +1,131 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use config::logging::{__tracing as tracing, error, info, instrument};
+use ethers::providers::ProviderError;
+use futures::lock::Mutex;
+use interpreter::playbook::{RpcProvider, RpcProviderConfig, RpcProviderError};
+use thiserror::Error;
+use tokio::sync::OnceCell;
+
+/// Maximum time to wait for an RPC provider to connect
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pool of connected RPC providers. A provider is connected the first time a step needs it, then shared with all
+/// steps using the same provider config.
+#[derive(Debug, Default)]
+pub struct RpcProviderPool {
+    providers: Mutex<HashMap<RpcProviderConfig, Arc<OnceCell<RpcProvider>>>>,
+    /// Chain ids of the connected providers, fetched the first time a step sends a transaction without a chain id
+    chain_ids: Mutex<HashMap<RpcProviderConfig, Arc<OnceCell<u64>>>>,
+}
+
+impl RpcProviderPool {
+    /// Get the connected provider of the config, connecting it if it is not connected yet
+    #[instrument(name = "ConnectRpcProvider", skip_all, fields(rpc_provider = %config))]
+    pub async fn connect(
+        &self,
+        config: &RpcProviderConfig,
+    ) -> Result<RpcProvider, RpcProviderPoolError> {
+        let provider_cell = {
+            let mut providers = self.providers.lock().await;
+            providers.entry(config.clone()).or_default().clone()
+        };
+
+        let provider = provider_cell
+            .get_or_try_init(|| async {
+                info!("Connecting to {}", config);
+
+                match tokio::time::timeout(DEFAULT_CONNECT_TIMEOUT, config.connect()).await {
+                    Ok(Ok(provider)) => Ok(provider),
+                    Ok(Err(err)) => {
+                        error!("Failed to connect to {}: {}", config, err);
+                        Err(RpcProviderPoolError::ConnectError(config.to_string(), err))
+                    }
+                    Err(_) => {
+                        error!("Connecting to {} timed out", config);
+                        Err(RpcProviderPoolError::ConnectTimeout(
+                            config.to_string(),
+                            DEFAULT_CONNECT_TIMEOUT,
+                        ))
+                    }
+                }
+            })
+            .await?;
+
+        Ok(provider.clone())
+    }
+
+    /// Get the chain id of the provider, fetching it with `eth_chainId` if it is not fetched yet
+    pub async fn chain_id(&self, config: &RpcProviderConfig) -> Result<u64, RpcProviderPoolError> {
+        let chain_id_cell = {
+            let mut chain_ids = self.chain_ids.lock().await;
+            chain_ids.entry(config.clone()).or_default().clone()
+        };
+
+        let chain_id = chain_id_cell
+            .get_or_try_init(|| async {
+                let provider = self.connect(config).await?;
+                let chain_id = provider.get_chainid().await.map_err(|err| {
+                    error!("Failed to get chain id of {}: {}", config, err);
+                    RpcProviderPoolError::ChainIdError(config.to_string(), err)
+                })?;
+                Ok::<u64, RpcProviderPoolError>(chain_id.low_u64())
+            })
+            .await?;
+
+        Ok(*chain_id)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RpcProviderPoolError {
+    #[error("cannot connect to {0}: {1}")]
+    ConnectError(String, RpcProviderError),
+    #[error("connecting to {0} timed out after {1:?}")]
+    ConnectTimeout(String, Duration),
+    #[error("cannot get chain id of {0}: {1}")]
+    ChainIdError(String, ProviderError),
+}
+
+impl RpcProviderPoolError {
+    /// Whether connecting again could succeed, invalid provider configs fail again
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            RpcProviderPoolError::ConnectError(_, RpcProviderError::ProviderError(_))
+                | RpcProviderPoolError::ConnectTimeout(..)
+                | RpcProviderPoolError::ChainIdError(..)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_matches};
+
+    #[tokio::test]
+    async fn can_reuse_connected_rpc_provider() {
+        let config: RpcProviderConfig =
+            serde_yaml::from_str(r#"chain_rpc_url: "http://localhost:8545""#).unwrap();
+        let pool = RpcProviderPool::default();
+
+        assert_matches!(pool.connect(&config).await, Ok(RpcProvider::Http(_)));
+        assert_matches!(pool.connect(&config).await, Ok(RpcProvider::Http(_)));
+        assert_eq!(pool.providers.lock().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn should_return_error_when_connect_failed() {
+        let config: RpcProviderConfig = serde_yaml::from_str(
+            r#"
+            chain_rpc_url: "ws://localhost:1"
+            provider_type: Websocket
+        "#,
+        )
+        .unwrap();
+        let pool = RpcProviderPool::default();
+
+        assert_err!(pool.connect(&config).await);
+    }
+}
@@ -0,0 +1,93 @@
+use std::{collections::HashMap, sync::Arc};
+
+use config::logging::{__tracing as tracing, info, instrument};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::Address,
+};
+use futures::lock::Mutex;
+use interpreter::playbook::{SignerConfig, SignerConfigError};
+use shared::NonceManager;
+use thiserror::Error;
+use tokio::sync::OnceCell;
+
+/// Pool of the signers used during the playbook run. The wallet of a signer is built the first time a step signs
+/// with it, then shared with all steps signing with it, so a keystore is decrypted once per run. Nonces of each
+/// account are handed out by one [`NonceManager`] per chain, so jobs running at the same time never send two
+/// transactions with the same nonce.
+#[derive(Debug, Default)]
+pub struct SignerPool {
+    wallets: Mutex<HashMap<SignerConfig, Arc<OnceCell<LocalWallet>>>>,
+    nonce_managers: Mutex<HashMap<(Address, u64), Arc<NonceManager>>>,
+}
+
+impl SignerPool {
+    /// Get the wallet of the signer config, building it if it is not built yet
+    #[instrument(name = "BuildSignerWallet", skip_all)]
+    pub async fn wallet(&self, config: &SignerConfig) -> Result<LocalWallet, SignerPoolError> {
+        let wallet_cell = {
+            let mut wallets = self.wallets.lock().await;
+            wallets.entry(config.clone()).or_default().clone()
+        };
+
+        let wallet = wallet_cell
+            .get_or_try_init(|| async {
+                let config = config.clone();
+                // decrypting a keystore takes a while, keep it off the async runtime threads
+                let wallet = tokio::task::spawn_blocking(move || config.wallet()).await??;
+                info!("Built wallet of signer {:?}", wallet.address());
+                Ok::<LocalWallet, SignerPoolError>(wallet)
+            })
+            .await?;
+
+        Ok(wallet.clone())
+    }
+
+    /// Get the nonce manager of the account on the chain
+    pub async fn nonce_manager(&self, address: Address, chain_id: u64) -> Arc<NonceManager> {
+        let mut nonce_managers = self.nonce_managers.lock().await;
+        nonce_managers
+            .entry((address, chain_id))
+            .or_insert_with(|| Arc::new(NonceManager::new(address)))
+            .clone()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SignerPoolError {
+    #[error(transparent)]
+    SignerConfigError(#[from] SignerConfigError),
+    #[error("building the signer wallet failed: {0}")]
+    JoinError(#[from] tokio::task::JoinError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn can_share_wallets_and_nonce_managers() {
+        let config: SignerConfig = serde_yaml::from_str(
+            r#"private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80""#,
+        )
+        .unwrap();
+        let pool = SignerPool::default();
+
+        let wallet = pool.wallet(&config).await.unwrap();
+        assert_eq!(
+            pool.wallet(&config).await.unwrap().address(),
+            wallet.address()
+        );
+        assert_eq!(pool.wallets.lock().await.len(), 1);
+
+        let nonce_manager = pool.nonce_manager(wallet.address(), 1).await;
+        assert!(Arc::ptr_eq(
+            &nonce_manager,
+            &pool.nonce_manager(wallet.address(), 1).await
+        ));
+        assert!(!Arc::ptr_eq(
+            &nonce_manager,
+            &pool.nonce_manager(wallet.address(), 31337).await
+        ));
+    }
+}
@@ -0,0 +1,2 @@
+mod run_report;
+pub use run_report::*;
@@ -0,0 +1,548 @@
+use std::{
+    collections::BTreeMap,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use alloy_dyn_abi::DynSolType;
+use ethers::{abi::Token, types::I256};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::{CallContractOutput, SendTransactionOutput, StepOutput};
+
+use interpreter::types::{FunctionReturnTypes, SavedOutputs};
+
+/// Status of a playbook, workflow, job or step after the run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+    TimedOut,
+    Cancelled,
+    /// The transaction of the step is sent but not confirmed yet, it is only recorded in the run state while the step
+    /// waits for the receipt
+    Pending,
+}
+
+impl RunStatus {
+    /// Failed if any of the statuses failed, timed out if any of them timed out, cancelled if any of them is
+    /// cancelled, otherwise succeeded
+    pub fn from_children<'a, I>(statuses: I) -> Self
+    where
+        I: IntoIterator<Item = &'a RunStatus>,
+    {
+        let statuses = statuses.into_iter().collect::<Vec<_>>();
+
+        if statuses.contains(&&RunStatus::Failed) {
+            RunStatus::Failed
+        } else if statuses.contains(&&RunStatus::TimedOut) {
+            RunStatus::TimedOut
+        } else if statuses.contains(&&RunStatus::Cancelled) {
+            RunStatus::Cancelled
+        } else {
+            RunStatus::Succeeded
+        }
+    }
+
+    /// Whether the run failed or timed out
+    pub fn is_failure(&self) -> bool {
+        matches!(self, RunStatus::Failed | RunStatus::TimedOut)
+    }
+}
+
+/// Start time in milliseconds since the Unix epoch and duration in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    pub started_at_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// Measure [`Timing`] of a run
+#[derive(Debug, Clone, Copy)]
+pub struct Timer {
+    started_at: SystemTime,
+    instant: Instant,
+}
+
+impl Timer {
+    pub fn start() -> Self {
+        Self {
+            started_at: SystemTime::now(),
+            instant: Instant::now(),
+        }
+    }
+
+    /// Return timing from the start until now
+    pub fn stop(&self) -> Timing {
+        let started_at_ms = self
+            .started_at
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+
+        Timing {
+            started_at_ms,
+            duration_ms: self.instant.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Report of a playbook run, covering every workflow, job and step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Id of the run, which the run can be resumed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub status: RunStatus,
+    pub timing: Timing,
+    pub workflows: Vec<WorkflowReport>,
+}
+
+impl RunReport {
+    pub fn new(timing: Timing, workflows: Vec<WorkflowReport>) -> Self {
+        Self {
+            run_id: None,
+            status: RunStatus::from_children(workflows.iter().map(|workflow| &workflow.status)),
+            timing,
+            workflows,
+        }
+    }
+
+    /// Set the id of the run
+    pub fn with_run_id(mut self, run_id: Option<String>) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    /// Whether every workflow succeeded
+    pub fn is_success(&self) -> bool {
+        self.status == RunStatus::Succeeded
+    }
+}
+
+/// Report of a workflow run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowReport {
+    pub name: String,
+    pub status: RunStatus,
+    pub timing: Timing,
+    /// Error chain, from the outermost error to its root cause
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error: Vec<String>,
+    pub jobs: Vec<JobReport>,
+}
+
+impl WorkflowReport {
+    pub fn new(name: impl Into<String>, timing: Timing, jobs: Vec<JobReport>) -> Self {
+        Self {
+            name: name.into(),
+            // failures of jobs which continue on error don't fail the workflow
+            status: RunStatus::from_children(
+                jobs.iter()
+                    .filter(|job| !job.continue_on_error)
+                    .map(|job| &job.status),
+            ),
+            timing,
+            error: Vec::new(),
+            jobs,
+        }
+    }
+
+    /// Report of a workflow which failed before or while scheduling its jobs
+    pub fn failed(
+        name: impl Into<String>,
+        timing: Timing,
+        error: &(dyn std::error::Error + 'static),
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: RunStatus::Failed,
+            timing,
+            error: error_chain(error),
+            jobs: Vec::new(),
+        }
+    }
+}
+
+/// Report of a job run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobReport {
+    pub name: String,
+    pub status: RunStatus,
+    pub timing: Timing,
+    /// Error chain, from the outermost error to its root cause
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error: Vec<String>,
+    /// Why the job is skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped_reason: Option<String>,
+    /// Whether jobs depending on this job run even if it fails
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub continue_on_error: bool,
+    pub steps: Vec<StepReport>,
+    /// Outputs saved by the steps of the job, indexed by their `save_as` variable name
+    pub outputs: BTreeMap<String, Value>,
+}
+
+impl JobReport {
+    pub fn new(
+        name: impl Into<String>,
+        timing: Timing,
+        error: Option<&(dyn std::error::Error + 'static)>,
+        steps: Vec<StepReport>,
+        outputs: &SavedOutputs,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: match error {
+                Some(_) => RunStatus::Failed,
+                None => RunStatus::Succeeded,
+            },
+            timing,
+            error: error.map(error_chain).unwrap_or_default(),
+            skipped_reason: None,
+            continue_on_error: false,
+            steps,
+            outputs: outputs
+                .iter()
+                .map(|(name, value)| (name.to_string(), token_to_json(value)))
+                .collect(),
+        }
+    }
+
+    /// Report of a job which is not executed, e.g. since a job it depends on failed
+    pub fn skipped(
+        name: impl Into<String>,
+        reason: impl Into<String>,
+        steps: Vec<StepReport>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status: RunStatus::Skipped,
+            timing: Timing::default(),
+            error: Vec::new(),
+            skipped_reason: Some(reason.into()),
+            continue_on_error: false,
+            steps,
+            outputs: BTreeMap::new(),
+        }
+    }
+
+    /// Set whether jobs depending on this job run even if it fails
+    pub fn with_continue_on_error(mut self, continue_on_error: bool) -> Self {
+        self.continue_on_error = continue_on_error;
+        self
+    }
+}
+
+/// Report of a step run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReport {
+    pub name: String,
+    /// Human-readable title of the step
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub status: RunStatus,
+    pub timing: Timing,
+    /// Error chain, from the outermost error to its root cause
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub error: Vec<String>,
+    /// Decoded step output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    /// Number of attempts, only set if the step is retried
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempts: Option<u32>,
+    /// Whether the step is completed by the resumed run, so it is not executed again
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resumed: bool,
+}
+
+impl StepReport {
+    pub fn succeeded(name: impl Into<String>, timing: Timing, output: Option<Value>) -> Self {
+        Self {
+            name: name.into(),
+            title: None,
+            status: RunStatus::Succeeded,
+            timing,
+            error: Vec::new(),
+            output,
+            attempts: None,
+            resumed: false,
+        }
+    }
+
+    pub fn failed(
+        name: impl Into<String>,
+        timing: Timing,
+        error: &(dyn std::error::Error + 'static),
+    ) -> Self {
+        Self {
+            name: name.into(),
+            title: None,
+            status: RunStatus::Failed,
+            timing,
+            error: error_chain(error),
+            output: None,
+            attempts: None,
+            resumed: false,
+        }
+    }
+
+    /// Report of a step which is not executed since an earlier step of the job failed
+    pub fn skipped(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            title: None,
+            status: RunStatus::Skipped,
+            timing: Timing::default(),
+            error: Vec::new(),
+            output: None,
+            attempts: None,
+            resumed: false,
+        }
+    }
+
+    /// Report of a step which is completed by the resumed run, with the output recorded by that run
+    pub fn resumed(name: impl Into<String>, output: Option<Value>) -> Self {
+        Self {
+            name: name.into(),
+            title: None,
+            status: RunStatus::Succeeded,
+            timing: Timing::default(),
+            error: Vec::new(),
+            output,
+            attempts: None,
+            resumed: true,
+        }
+    }
+
+    /// Set the human-readable title of the step
+    pub fn with_title(mut self, title: Option<&str>) -> Self {
+        self.title = title.map(str::to_string);
+        self
+    }
+
+    /// Set the number of attempts, which is only reported if the step is retried
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = (attempts > 1).then_some(attempts);
+        self
+    }
+}
+
+/// Collect messages of the error and all of its sources
+pub fn error_chain(error: &(dyn std::error::Error + 'static)) -> Vec<String> {
+    let mut chain = vec![error.to_string()];
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        chain.push(error.to_string());
+        source = error.source();
+    }
+    chain
+}
+
+/// Decode step output into JSON, return `None` if the step has no output. Returned structs are decoded into maps
+/// keyed by field name if the return types of the called function are known.
+pub fn step_output_to_json(
+    step_output: &StepOutput,
+    return_types: Option<&[FunctionReturnTypes]>,
+) -> Option<Value> {
+    match step_output {
+        StepOutput::CallContractOutput(CallContractOutput::Tokens(tokens)) => match return_types {
+            Some(return_types) if return_types.len() == tokens.len() => Some(Value::Array(
+                tokens
+                    .iter()
+                    .zip(return_types)
+                    .map(|(token, return_type)| typed_token_to_json(token, return_type.inner_ref()))
+                    .collect(),
+            )),
+            _ => Some(Value::Array(tokens.iter().map(token_to_json).collect())),
+        },
+        StepOutput::CallContractOutput(CallContractOutput::Bytes(bytes)) => {
+            Some(Value::String(bytes.to_string()))
+        }
+        StepOutput::SendTransactionOutput(SendTransactionOutput::TransactionReceipt(receipt)) => {
+            receipt
+                .as_ref()
+                .and_then(|receipt| serde_json::to_value(receipt).ok())
+        }
+        StepOutput::DeployContractOutput(output) => Some(serde_json::json!({
+            "address": format!("{:?}", output.address),
+            "receipt": serde_json::to_value(&output.tx_receipt).ok(),
+        })),
+        _ => None,
+    }
+}
+
+/// Convert [`Token`] into JSON. Integers are converted into decimal strings to keep their precision, addresses and
+/// bytes into hex strings.
+pub fn token_to_json(token: &Token) -> Value {
+    match token {
+        Token::Address(address) => Value::String(format!("{:?}", address)),
+        Token::FixedBytes(bytes) | Token::Bytes(bytes) => {
+            Value::String(ethers::types::Bytes::from(bytes.clone()).to_string())
+        }
+        Token::Int(value) => Value::String(I256::from_raw(*value).to_string()),
+        Token::Uint(value) => Value::String(value.to_string()),
+        Token::Bool(value) => Value::Bool(*value),
+        Token::String(value) => Value::String(value.clone()),
+        Token::FixedArray(tokens) | Token::Array(tokens) | Token::Tuple(tokens) => {
+            Value::Array(tokens.iter().map(token_to_json).collect())
+        }
+    }
+}
+
+/// Convert [`Token`] of the Solidity type into JSON like [`token_to_json`], except that structs are converted into
+/// maps keyed by field name
+pub fn typed_token_to_json(token: &Token, sol_type: &DynSolType) -> Value {
+    match (sol_type, token) {
+        (
+            DynSolType::CustomStruct {
+                prop_names, tuple, ..
+            },
+            Token::Tuple(tokens),
+        ) if tuple.len() == tokens.len() && prop_names.len() == tokens.len() => Value::Object(
+            prop_names
+                .iter()
+                .zip(tuple.iter().zip(tokens))
+                .map(|(prop_name, (item_type, token))| {
+                    (prop_name.clone(), typed_token_to_json(token, item_type))
+                })
+                .collect(),
+        ),
+        (DynSolType::Tuple(item_types), Token::Tuple(tokens))
+            if item_types.len() == tokens.len() =>
+        {
+            Value::Array(
+                item_types
+                    .iter()
+                    .zip(tokens)
+                    .map(|(item_type, token)| typed_token_to_json(token, item_type))
+                    .collect(),
+            )
+        }
+        (
+            DynSolType::Array(item_type) | DynSolType::FixedArray(item_type, _),
+            Token::Array(tokens) | Token::FixedArray(tokens),
+        ) => Value::Array(
+            tokens
+                .iter()
+                .map(|token| typed_token_to_json(token, item_type))
+                .collect(),
+        ),
+        _ => token_to_json(token),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{H160, U256};
+    use serde_json::json;
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    #[error("execute step error")]
+    struct ExecuteStepError(#[source] std::io::Error);
+
+    #[test]
+    fn can_serialize_run_report() {
+        let output = StepOutput::CallContractOutput(CallContractOutput::Tokens(vec![
+            Token::Address(H160::repeat_byte(1)),
+            Token::Int(I256::from(-1).into_raw()),
+            Token::Tuple(vec![Token::Uint(U256::from(3000)), Token::Bool(true)]),
+        ]));
+        let error = ExecuteStepError(std::io::Error::other("connection refused"));
+
+        let job_report = JobReport::new(
+            "uniswap_v3",
+            Timing::default(),
+            Some(&error),
+            vec![
+                StepReport::succeeded(
+                    "get_pool",
+                    Timing::default(),
+                    step_output_to_json(&output, None),
+                ),
+                StepReport::failed("get_slot0", Timing::default(), &error),
+                StepReport::skipped("get_liquidity"),
+            ],
+            &SavedOutputs::new(),
+        );
+        let report = RunReport::new(
+            Timing::default(),
+            vec![WorkflowReport::new(
+                "workflow_1",
+                Timing::default(),
+                vec![job_report],
+            )],
+        );
+        assert!(!report.is_success());
+
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["status"], json!("failed"));
+
+        let job = &value["workflows"][0]["jobs"][0];
+        assert_eq!(
+            job["error"],
+            json!(["execute step error", "connection refused"])
+        );
+        assert_eq!(
+            job["steps"][0]["output"],
+            json!([
+                "0x0101010101010101010101010101010101010101",
+                "-1",
+                ["3000", true]
+            ])
+        );
+        assert_eq!(job["steps"][2]["status"], json!("skipped"));
+
+        let report: RunReport = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            report.workflows[0].jobs[0].steps[2].status,
+            RunStatus::Skipped
+        );
+    }
+
+    #[test]
+    fn can_decode_returned_structs_into_named_json() {
+        use interpreter::types::{DynSolTypeWrapper, SolStructs};
+
+        let structs =
+            SolStructs::from_declarations(&["struct Asset { address token; uint256 amount; }"])
+                .unwrap();
+        let return_types = ["Asset[]", "uint8"]
+            .iter()
+            .map(|return_type| {
+                structs
+                    .resolve(&DynSolTypeWrapper::parse(return_type).unwrap())
+                    .unwrap()
+            })
+            .collect::<Vec<FunctionReturnTypes>>();
+        let output = StepOutput::CallContractOutput(CallContractOutput::Tokens(vec![
+            Token::Array(vec![Token::Tuple(vec![
+                Token::Address(H160::repeat_byte(1)),
+                Token::Uint(U256::from(1000)),
+            ])]),
+            Token::Uint(U256::from(18)),
+        ]));
+
+        assert_eq!(
+            step_output_to_json(&output, Some(&return_types)).unwrap(),
+            json!([
+                [{"token": "0x0101010101010101010101010101010101010101", "amount": "1000"}],
+                "18"
+            ])
+        );
+        // without return types structs are decoded into arrays
+        assert_eq!(
+            step_output_to_json(&output, None).unwrap(),
+            json!([
+                [["0x0101010101010101010101010101010101010101", "1000"]],
+                "18"
+            ])
+        );
+    }
+}
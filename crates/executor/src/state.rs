@@ -0,0 +1,4 @@
+mod run_state;
+pub use run_state::*;
+mod run_state_store;
+pub use run_state_store::*;
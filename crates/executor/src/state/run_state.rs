@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use ethers::{
+    abi::Token,
+    types::{Address, Bytes, TxHash, U256},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::report::RunStatus;
+
+/// Progress of a playbook run, recorded after every step so the run can be resumed without executing completed steps
+/// again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    pub run_id: String,
+    /// Canonical path of the playbook file the run executes
+    pub playbook_file: PathBuf,
+    #[serde(default)]
+    pub workflows: BTreeMap<String, WorkflowState>,
+}
+
+impl RunState {
+    pub fn new(run_id: impl Into<String>, playbook_file: PathBuf) -> Self {
+        Self {
+            run_id: run_id.into(),
+            playbook_file,
+            workflows: BTreeMap::new(),
+        }
+    }
+
+    /// Get the recorded state of the step
+    pub fn step(&self, workflow_name: &str, job_name: &str, step_name: &str) -> Option<&StepState> {
+        self.workflows
+            .get(workflow_name)?
+            .jobs
+            .get(job_name)?
+            .steps
+            .get(step_name)
+    }
+
+    /// Record the state of the step, replacing the state recorded by an earlier attempt
+    pub fn set_step(
+        &mut self,
+        workflow_name: &str,
+        job_name: &str,
+        step_name: &str,
+        step_state: StepState,
+    ) {
+        self.workflows
+            .entry(workflow_name.to_string())
+            .or_default()
+            .jobs
+            .entry(job_name.to_string())
+            .or_default()
+            .steps
+            .insert(step_name.to_string(), step_state);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkflowState {
+    #[serde(default)]
+    pub jobs: BTreeMap<String, JobState>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobState {
+    #[serde(default)]
+    pub steps: BTreeMap<String, StepState>,
+}
+
+/// Recorded state of a step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepState {
+    pub status: RunStatus,
+    /// Value saved by the `output` of the step, restored when the run is resumed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saved_value: Option<TokenValue>,
+    /// Decoded step output, as in the run report
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+    /// Hash of the transaction sent by the step, recorded even if the step fails after sending it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<TxHash>,
+    /// Whether the transaction of the step is mined but reverted, so a resumed run executes the step again instead
+    /// of confirming the transaction
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub tx_reverted: bool,
+}
+
+impl StepState {
+    /// State of a step whose transaction is sent but not confirmed yet, recorded as soon as the hash is known
+    pub fn pending(tx_hash: TxHash) -> Self {
+        Self {
+            status: RunStatus::Pending,
+            saved_value: None,
+            output: None,
+            tx_hash: Some(tx_hash),
+            tx_reverted: false,
+        }
+    }
+
+    /// Hash of the transaction sent by the step which a resumed run must confirm rather than send again, none if the
+    /// transaction reverted
+    pub fn pending_tx_hash(&self) -> Option<TxHash> {
+        self.tx_hash.filter(|_| !self.tx_reverted)
+    }
+
+    /// Whether the step is completed, so it is not executed again when the run is resumed
+    pub fn is_completed(&self) -> bool {
+        self.status == RunStatus::Succeeded
+    }
+}
+
+/// Serializable [`Token`], converting back into the same token. Integers are kept as their raw 256-bit words.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum TokenValue {
+    Address(Address),
+    FixedBytes(Bytes),
+    Bytes(Bytes),
+    Int(U256),
+    Uint(U256),
+    Bool(bool),
+    String(String),
+    FixedArray(Vec<TokenValue>),
+    Array(Vec<TokenValue>),
+    Tuple(Vec<TokenValue>),
+}
+
+impl From<&Token> for TokenValue {
+    fn from(token: &Token) -> Self {
+        let values = |tokens: &[Token]| tokens.iter().map(TokenValue::from).collect();
+
+        match token {
+            Token::Address(address) => TokenValue::Address(*address),
+            Token::FixedBytes(bytes) => TokenValue::FixedBytes(bytes.clone().into()),
+            Token::Bytes(bytes) => TokenValue::Bytes(bytes.clone().into()),
+            Token::Int(value) => TokenValue::Int(*value),
+            Token::Uint(value) => TokenValue::Uint(*value),
+            Token::Bool(value) => TokenValue::Bool(*value),
+            Token::String(value) => TokenValue::String(value.clone()),
+            Token::FixedArray(tokens) => TokenValue::FixedArray(values(tokens)),
+            Token::Array(tokens) => TokenValue::Array(values(tokens)),
+            Token::Tuple(tokens) => TokenValue::Tuple(values(tokens)),
+        }
+    }
+}
+
+impl From<TokenValue> for Token {
+    fn from(value: TokenValue) -> Self {
+        let tokens = |values: Vec<TokenValue>| values.into_iter().map(Token::from).collect();
+
+        match value {
+            TokenValue::Address(address) => Token::Address(address),
+            TokenValue::FixedBytes(bytes) => Token::FixedBytes(bytes.to_vec()),
+            TokenValue::Bytes(bytes) => Token::Bytes(bytes.to_vec()),
+            TokenValue::Int(value) => Token::Int(value),
+            TokenValue::Uint(value) => Token::Uint(value),
+            TokenValue::Bool(value) => Token::Bool(value),
+            TokenValue::String(value) => Token::String(value),
+            TokenValue::FixedArray(values) => Token::FixedArray(tokens(values)),
+            TokenValue::Array(values) => Token::Array(tokens(values)),
+            TokenValue::Tuple(values) => Token::Tuple(tokens(values)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{H160, I256};
+
+    use super::*;
+
+    #[test]
+    fn can_convert_tokens_to_token_values() {
+        let token = Token::Tuple(vec![
+            Token::Address(H160::repeat_byte(1)),
+            Token::Int(I256::from(-1).into_raw()),
+            Token::FixedBytes(vec![0xab; 32]),
+            Token::Array(vec![Token::Uint(U256::from(3000)), Token::Uint(U256::MAX)]),
+            Token::String("tudo".to_string()),
+        ]);
+
+        let json = serde_json::to_string(&TokenValue::from(&token)).unwrap();
+        let token_value: TokenValue = serde_json::from_str(&json).unwrap();
+        assert_eq!(Token::from(token_value), token);
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::core::rand::{thread_rng, Rng};
+use thiserror::Error;
+
+use super::{RunState, StepState};
+
+/// Store of a [`RunState`] as a JSON file under `.tudo/runs` of the project root. The file is rewritten after every
+/// recorded step, so the progress survives an interrupted or crashed run.
+#[derive(Debug)]
+pub struct RunStateStore {
+    path: PathBuf,
+    state: Mutex<RunState>,
+}
+
+impl RunStateStore {
+    /// Directory of the run state files, relative to the project root
+    pub const DIRECTORY: &'static str = ".tudo/runs";
+
+    /// Start recording a new run of the playbook file, with a new run id
+    pub fn create(project_root: &Path, playbook_file: &Path) -> Result<Self, RunStateError> {
+        let started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis())
+            .unwrap_or_default();
+        let run_id = format!("{}-{:08x}", started_at_ms, thread_rng().gen::<u32>());
+
+        let store = Self {
+            path: Self::path(project_root, &run_id)?,
+            state: Mutex::new(RunState::new(run_id, playbook_file.canonicalize()?)),
+        };
+        store.save(&store.state())?;
+        Ok(store)
+    }
+
+    /// Open the recorded run to resume it, which must be a run of the same playbook file
+    pub fn open(
+        project_root: &Path,
+        run_id: &str,
+        playbook_file: &Path,
+    ) -> Result<Self, RunStateError> {
+        let path = Self::path(project_root, run_id)?;
+        if !path.is_file() {
+            return Err(RunStateError::RunNotFound(run_id.to_string()));
+        }
+
+        let state: RunState = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        if state.playbook_file != playbook_file.canonicalize()? {
+            return Err(RunStateError::PlaybookMismatch {
+                run_id: run_id.to_string(),
+                playbook_file: state.playbook_file,
+            });
+        }
+
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Get the id of the run
+    pub fn run_id(&self) -> String {
+        self.state().run_id.clone()
+    }
+
+    /// Get the path of the run state file
+    pub fn file_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the recorded state of the step
+    pub fn step(&self, workflow_name: &str, job_name: &str, step_name: &str) -> Option<StepState> {
+        self.state()
+            .step(workflow_name, job_name, step_name)
+            .cloned()
+    }
+
+    /// Record the state of the step and write the run state file
+    pub fn record_step(
+        &self,
+        workflow_name: &str,
+        job_name: &str,
+        step_name: &str,
+        step_state: StepState,
+    ) -> Result<(), RunStateError> {
+        let mut state = self.state();
+        state.set_step(workflow_name, job_name, step_name, step_state);
+        self.save(&state)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, RunState> {
+        // recording a step can't panic halfway, so the state is consistent even if the lock is poisoned
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Write the state into a temporary file first, so the state file is never left half written
+    fn save(&self, state: &RunState) -> Result<(), RunStateError> {
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string_pretty(state)?)?;
+        std::fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn path(project_root: &Path, run_id: &str) -> Result<PathBuf, RunStateError> {
+        let is_valid = !run_id.is_empty()
+            && run_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !is_valid {
+            return Err(RunStateError::InvalidRunId(run_id.to_string()));
+        }
+
+        let directory = project_root.join(Self::DIRECTORY);
+        std::fs::create_dir_all(&directory)?;
+        Ok(directory.join(format!("{}.json", run_id)))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RunStateError {
+    #[error("invalid run id {:#?}", .0)]
+    InvalidRunId(String),
+    #[error("run {:#?} is not found", .0)]
+    RunNotFound(String),
+    #[error("run {run_id:#?} is a run of another playbook file {playbook_file:#?}")]
+    PlaybookMismatch {
+        run_id: String,
+        playbook_file: PathBuf,
+    },
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+    #[error(transparent)]
+    SerdeJsonError(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_none, assert_some};
+    use ethers::{abi::Token, types::U256};
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{report::RunStatus, state::TokenValue};
+
+    #[test]
+    fn can_record_and_resume_run_state() {
+        let temp_dir = TempDir::new().unwrap();
+        let project_root = temp_dir.path().to_path_buf();
+        let playbook_file = project_root.join("playbook.yaml");
+        std::fs::write(&playbook_file, "version: \"1\"").unwrap();
+
+        let store = RunStateStore::create(&project_root, &playbook_file).unwrap();
+        store
+            .record_step(
+                "workflow_1",
+                "job_1",
+                "get_fee",
+                StepState {
+                    status: RunStatus::Succeeded,
+                    saved_value: Some(TokenValue::from(&Token::Uint(U256::from(3000)))),
+                    output: None,
+                    tx_hash: None,
+                    tx_reverted: false,
+                },
+            )
+            .unwrap();
+
+        let store = RunStateStore::open(&project_root, &store.run_id(), &playbook_file).unwrap();
+        let step_state = assert_some!(store.step("workflow_1", "job_1", "get_fee"));
+        assert!(step_state.is_completed());
+        assert_eq!(
+            step_state.saved_value.map(Token::from),
+            Some(Token::Uint(U256::from(3000)))
+        );
+        assert_none!(store.step("workflow_1", "job_1", "set_fee"));
+
+        let other_playbook_file = project_root.join("other_playbook.yaml");
+        std::fs::write(&other_playbook_file, "version: \"1\"").unwrap();
+        assert_matches!(
+            RunStateStore::open(&project_root, &store.run_id(), &other_playbook_file),
+            Err(RunStateError::PlaybookMismatch { .. })
+        );
+        assert_matches!(
+            RunStateStore::open(&project_root, "../playbook", &playbook_file),
+            Err(RunStateError::InvalidRunId(_))
+        );
+        assert_matches!(
+            RunStateStore::open(&project_root, "unknown", &playbook_file),
+            Err(RunStateError::RunNotFound(_))
+        );
+    }
+}
@@ -1,41 +1,756 @@
+use config::logging::{__tracing as tracing, info, instrument, warn};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Bytes, TxHash};
+use interpreter::playbook::{RpcProvider, RpcProviderError};
+use interpreter::step::{
+    StepConfig, StepConfigError, StepContextBuilder, StepContextBuilderError, StepOutputError,
+};
+use shared::{NonceManager, Step, StepError, StepErrorKind, StepOutput, TransactionSentHook};
+use std::sync::Arc;
 use thiserror::Error;
-use config::logging::{__tracing as tracing, info, instrument};
-use interpreter::step::{StepConfig, StepConfigError};
-use shared::StepError;
+use tokio::time::Instant;
 
+use crate::deadline::{deadline_after, run_until};
 use crate::job::{JobContext, JobContextError};
+use crate::playbook::{PlaybookContext, RpcProviderPoolError, SignerPoolError};
 
 pub struct StepExecutor {}
 
 impl StepExecutor {
+    /// Execute the step, retrying transient failures as configured by the `retry` of the step. Once the transaction
+    /// of the step is sent, by the resumed run with `sent_tx_hash` or by an earlier attempt, later attempts poll its
+    /// receipt instead of sending it again. If broadcasting it fails,
+    /// later attempts broadcast the same signed transaction again, so it never takes another nonce. The step is cancelled
+    /// once its `timeout` or the deadline of its job passes, or once the run is interrupted. A step which sends a
+    /// transaction is only interrupted before sending it: once it is sent, the step stops waiting for the receipt at
+    /// the deadline or the interruption and reports the transaction hash instead.
+    /// The hook is called with the hash of the transaction of the step as soon as it is sent.
+    /// Return the result of the last attempt and the number of attempts.
+    pub async fn execute_with_retry(
+        step_config: &StepConfig,
+        job_context: &mut JobContext,
+        playbook_context: &PlaybookContext,
+        job_deadline: Option<Instant>,
+        sent_tx_hash: Option<TxHash>,
+        on_tx_sent: Option<TransactionSentHook>,
+    ) -> (Result<(), ExecuteStepError>, u32) {
+        let deadline = deadline_after(step_config.timeout(), job_deadline);
+        let max_attempts = step_config
+            .retry
+            .map(|retry| retry.attempts.get())
+            .unwrap_or(1);
+        let cancellation = playbook_context.cancellation();
+        let is_interruptible = !step_config.step_type.sends_transaction();
+        let mut attempts = 1;
+        let mut sent_tx_hash = sent_tx_hash;
+        let mut raw_tx = None;
+
+        loop {
+            let execution = async {
+                match sent_tx_hash {
+                    // the transaction is sent before, poll its receipt instead of sending another
+                    Some(tx_hash) => {
+                        let confirmation = run_until(
+                            deadline,
+                            Self::confirm_transaction(
+                                step_config,
+                                job_context,
+                                playbook_context,
+                                tx_hash,
+                                raw_tx.as_ref(),
+                            ),
+                        );
+                        tokio::select! {
+                            result = confirmation => result
+                                .unwrap_or(Err(StepError::TransactionPending { tx_hash }.into())),
+                            _ = cancellation.cancelled() => {
+                                Err(StepError::TransactionCancelled { tx_hash }.into())
+                            }
+                        }
+                    }
+                    None if is_interruptible => run_until(
+                        deadline,
+                        Self::execute(
+                            step_config,
+                            job_context,
+                            playbook_context,
+                            deadline,
+                            on_tx_sent.clone(),
+                        ),
+                    )
+                    .await
+                    .unwrap_or(Err(ExecuteStepError::Timeout)),
+                    // the step stops waiting for the receipt at the deadline by itself, so the hash of the sent
+                    // transaction is always reported
+                    None => {
+                        Self::execute(
+                            step_config,
+                            job_context,
+                            playbook_context,
+                            deadline,
+                            on_tx_sent.clone(),
+                        )
+                        .await
+                    }
+                }
+            };
+            let result = if is_interruptible {
+                tokio::select! {
+                    result = execution => result,
+                    _ = cancellation.cancelled() => Err(ExecuteStepError::Cancelled),
+                }
+            } else {
+                execution.await
+            };
+
+            match (&result, &step_config.retry) {
+                (Err(err), Some(retry))
+                    if (err.is_transient() || err.can_poll_receipt())
+                        && attempts < max_attempts =>
+                {
+                    sent_tx_hash = err.tx_hash().or(sent_tx_hash);
+                    raw_tx = err.raw_tx().cloned().or(raw_tx);
+                    let backoff = retry.backoff(attempts);
+                    warn!(
+                        "Attempt {}/{} of step {:#?} failed, retrying in {:?}: {}",
+                        attempts, max_attempts, step_config.name, backoff, err
+                    );
+                    // the next attempt times out at once if the deadline passes during the backoff
+                    let retry_at = Instant::now() + backoff;
+                    let backoff = tokio::time::sleep_until(
+                        deadline.map_or(retry_at, |deadline| retry_at.min(deadline)),
+                    );
+                    tokio::select! {
+                        _ = backoff => {}
+                        _ = cancellation.cancelled() => {
+                            let err = match sent_tx_hash {
+                                Some(tx_hash) => StepError::TransactionCancelled { tx_hash }.into(),
+                                None => ExecuteStepError::Cancelled,
+                            };
+                            return (Err(err), attempts);
+                        }
+                    }
+                    attempts += 1;
+                }
+                _ => return (result, attempts),
+            }
+        }
+    }
+
     #[instrument(name = "StepExecute", skip_all)]
     pub async fn execute(
         step_config: &StepConfig,
         job_context: &mut JobContext,
+        playbook_context: &PlaybookContext,
+        deadline: Option<Instant>,
+        on_tx_sent: Option<TransactionSentHook>,
     ) -> Result<(), ExecuteStepError> {
         info!("Execute step {:#?}", step_config.name);
 
-        let step = step_config.to_step()?;
+        // the step is not interrupted by the deadline once it is built, a step sending a transaction handles the
+        // deadline by itself
+        let step = run_until(
+            deadline,
+            Self::build_step(
+                step_config,
+                job_context,
+                playbook_context,
+                deadline,
+                on_tx_sent,
+            ),
+        )
+        .await
+        .unwrap_or(Err(ExecuteStepError::Timeout))?;
         let step_output = step.execute().await?;
 
         info!("Step output {:#?}", step_output);
 
-        job_context.add_step_output(&step_config.name, step_output)?;
+        Self::save_step_output(step_config, job_context, step_output)?;
 
         info!("Finish executing step {:#?}", step_config.name);
         Ok(())
     }
+
+    /// Build the step with the RPC provider, the chain id and the signer it needs
+    async fn build_step(
+        step_config: &StepConfig,
+        job_context: &JobContext,
+        playbook_context: &PlaybookContext,
+        deadline: Option<Instant>,
+        on_tx_sent: Option<TransactionSentHook>,
+    ) -> Result<Box<dyn Step>, ExecuteStepError> {
+        let shared_setup = playbook_context.shared_setup();
+        let rpc_provider = Self::connect_rpc_provider(step_config, playbook_context).await?;
+        let chain_id = Self::chain_id(step_config, playbook_context).await?;
+        let (wallet, nonce_manager) = Self::signer(step_config, playbook_context, chain_id).await?;
+
+        let step_context = StepContextBuilder::default()
+            .setup(shared_setup)
+            .rpc_provider(rpc_provider)
+            .chain_id(chain_id)
+            .wallet(wallet)
+            .nonce_manager(nonce_manager)
+            .on_tx_sent(on_tx_sent)
+            .outputs(job_context.outputs().clone())
+            .deadline(deadline)
+            .cancellation(playbook_context.cancellation().clone())
+            .build()?;
+
+        Ok(step_config.to_step(&step_context)?)
+    }
+
+    /// Complete a step whose transaction is sent by the resumed run or an earlier attempt with the receipt of the
+    /// transaction, so the transaction is never sent twice. The signed transaction is broadcast again first if
+    /// broadcasting it failed. Fail if the transaction is not mined yet.
+    #[instrument(
+        name = "StepConfirmTransaction",
+        skip(step_config, job_context, playbook_context, raw_tx)
+    )]
+    pub async fn confirm_transaction(
+        step_config: &StepConfig,
+        job_context: &mut JobContext,
+        playbook_context: &PlaybookContext,
+        tx_hash: TxHash,
+        raw_tx: Option<&Bytes>,
+    ) -> Result<(), ExecuteStepError> {
+        info!(
+            "Confirm transaction {:?} of step {:#?}",
+            tx_hash, step_config.name
+        );
+
+        let rpc_provider = Self::connect_rpc_provider(step_config, playbook_context)
+            .await?
+            .ok_or(RpcProviderError::MissingRpcProvider)?;
+        if let Some(raw_tx) = raw_tx {
+            // the RPC provider rejects the transaction if it is already sent or mined, which the receipt tells
+            if let Err(err) = rpc_provider.send_raw_transaction(raw_tx.clone()).await {
+                warn!(
+                    "Broadcasting transaction {:?} again failed: {}",
+                    tx_hash, err
+                );
+            }
+        }
+        let tx_receipt = rpc_provider
+            .get_transaction_receipt(tx_hash)
+            .await
+            .map_err(|err| StepError::TransactionUnconfirmed {
+                tx_hash,
+                kind: StepErrorKind::from_middleware_error(&err),
+                message: err.to_string(),
+            })?
+            .ok_or(ExecuteStepError::UnconfirmedTransaction(tx_hash))?;
+
+        // the step decodes its output from the receipt, e.g. the address of a deployed contract
+        let chain_id = Self::chain_id(step_config, playbook_context).await?;
+        let (wallet, _) = Self::signer(step_config, playbook_context, chain_id).await?;
+        let step_context = StepContextBuilder::default()
+            .setup(playbook_context.shared_setup())
+            .rpc_provider(Some(rpc_provider))
+            .chain_id(chain_id)
+            .wallet(wallet)
+            .outputs(job_context.outputs().clone())
+            .cancellation(playbook_context.cancellation().clone())
+            .build()?;
+        let step = step_config.to_step(&step_context)?;
+        let step_output = step.output_of_receipt(tx_receipt)?;
+
+        Self::save_step_output(step_config, job_context, step_output)
+    }
+
+    /// Connect the RPC provider only when the step needs it
+    async fn connect_rpc_provider(
+        step_config: &StepConfig,
+        playbook_context: &PlaybookContext,
+    ) -> Result<Option<RpcProvider>, ExecuteStepError> {
+        let Some(rpc_provider_source) = step_config.rpc_provider() else {
+            return Ok(None);
+        };
+
+        let shared_setup = playbook_context.shared_setup();
+        let rpc_provider_config = rpc_provider_source.resolve(shared_setup.as_deref())?;
+        let rpc_provider = playbook_context
+            .rpc_provider_pool()
+            .connect(rpc_provider_config)
+            .await?;
+        Ok(Some(rpc_provider))
+    }
+
+    /// Chain id the transaction of the step is signed for, which is fetched from the RPC provider if the step sends
+    /// a transaction without a chain id. Return None if the step sends no transaction.
+    async fn chain_id(
+        step_config: &StepConfig,
+        playbook_context: &PlaybookContext,
+    ) -> Result<Option<u64>, ExecuteStepError> {
+        if !step_config.step_type.sends_transaction() || step_config.chain_id().is_some() {
+            return Ok(step_config.chain_id());
+        }
+        let Some(rpc_provider_source) = step_config.rpc_provider() else {
+            return Ok(None);
+        };
+
+        let shared_setup = playbook_context.shared_setup();
+        let rpc_provider_config = rpc_provider_source.resolve(shared_setup.as_deref())?;
+        let chain_id = playbook_context
+            .rpc_provider_pool()
+            .chain_id(rpc_provider_config)
+            .await?;
+        Ok(Some(chain_id))
+    }
+
+    /// Get the wallet of the signer of the step from the signer pool, so it is built once per run, along with the
+    /// nonce manager of the account on the chain
+    async fn signer(
+        step_config: &StepConfig,
+        playbook_context: &PlaybookContext,
+        chain_id: Option<u64>,
+    ) -> Result<(Option<LocalWallet>, Option<Arc<NonceManager>>), ExecuteStepError> {
+        let Some(signer_source) = step_config.signer() else {
+            return Ok((None, None));
+        };
+
+        let shared_setup = playbook_context.shared_setup();
+        let signer_config = signer_source
+            .resolve(shared_setup.as_deref())
+            .map_err(SignerPoolError::from)?;
+        let signer_pool = playbook_context.signer_pool();
+        let wallet = signer_pool.wallet(signer_config).await?;
+        let nonce_manager = match chain_id {
+            Some(chain_id) => Some(signer_pool.nonce_manager(wallet.address(), chain_id).await),
+            None => None,
+        };
+        Ok((Some(wallet), nonce_manager))
+    }
+
+    fn save_step_output(
+        step_config: &StepConfig,
+        job_context: &mut JobContext,
+        step_output: StepOutput,
+    ) -> Result<(), ExecuteStepError> {
+        if let Some(output) = &step_config.output {
+            let value = output.value_of(&step_output)?;
+            job_context.save_output(&output.save_as, value)?;
+        }
+        job_context.add_step_output(&step_config.name, step_output)?;
+        Ok(())
+    }
 }
 
 /// Error happens during step execution
 #[derive(Debug, Error)]
 pub enum ExecuteStepError {
+    #[error("step timed out")]
+    Timeout,
+    #[error("step cancelled")]
+    Cancelled,
+    #[error("transaction {0:?} sent before is not mined yet")]
+    UnconfirmedTransaction(TxHash),
     #[error("build step input error {0}")]
     BuildStepInputError(String),
     #[error(transparent)]
     StepConfigError(#[from] StepConfigError),
     #[error(transparent)]
+    StepContextBuilderError(#[from] StepContextBuilderError),
+    #[error(transparent)]
+    RpcProviderError(#[from] RpcProviderError),
+    #[error(transparent)]
+    RpcProviderPoolError(#[from] RpcProviderPoolError),
+    #[error(transparent)]
+    SignerPoolError(#[from] SignerPoolError),
+    #[error(transparent)]
     StepError(#[from] StepError),
     #[error(transparent)]
     JobContextError(#[from] JobContextError),
+    #[error(transparent)]
+    StepOutputError(#[from] StepOutputError),
+}
+
+impl ExecuteStepError {
+    /// Whether executing the step again could succeed, e.g. after a transport error or a rate limit. Reverts and
+    /// invalid configs fail at once.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ExecuteStepError::StepError(err) => err.is_transient(),
+            ExecuteStepError::RpcProviderPoolError(err) => err.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// Whether the transaction of the step is sent, but it is not mined yet or its receipt is not fetched for a
+    /// transient reason, or broadcasting it failed, so polling the receipt again could succeed
+    pub fn can_poll_receipt(&self) -> bool {
+        match self {
+            ExecuteStepError::UnconfirmedTransaction(_)
+            | ExecuteStepError::StepError(StepError::TransactionNotBroadcast { .. }) => true,
+            ExecuteStepError::StepError(err @ StepError::TransactionUnconfirmed { .. }) => {
+                err.kind().is_transient()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the step is cancelled by its deadline, or its transaction is not mined before the deadline
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            ExecuteStepError::Timeout => true,
+            ExecuteStepError::StepError(err) => err.kind() == StepErrorKind::Timeout,
+            _ => false,
+        }
+    }
+
+    /// Hash of the transaction which is sent before the step fails, if any
+    pub fn tx_hash(&self) -> Option<TxHash> {
+        match self {
+            ExecuteStepError::UnconfirmedTransaction(tx_hash) => Some(*tx_hash),
+            ExecuteStepError::StepError(err) => err.tx_hash(),
+            _ => None,
+        }
+    }
+
+    /// Signed transaction of the step which must be broadcast again since broadcasting it failed, if any
+    pub fn raw_tx(&self) -> Option<&Bytes> {
+        match self {
+            ExecuteStepError::StepError(err) => err.raw_tx(),
+            _ => None,
+        }
+    }
+
+    /// Whether the transaction of the step is mined but reverted
+    pub fn is_reverted(&self) -> bool {
+        matches!(
+            self,
+            ExecuteStepError::StepError(StepError::TransactionReverted { .. })
+        )
+    }
+
+    /// Whether the step is interrupted by the cancellation of the run, or its transaction is not mined before that
+    pub fn is_cancelled(&self) -> bool {
+        match self {
+            ExecuteStepError::Cancelled => true,
+            ExecuteStepError::StepError(err) => err.kind() == StepErrorKind::Cancelled,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::assert_matches;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::playbook::PlaybookContextBuilder;
+
+    use super::*;
+
+    /// Serve JSON-RPC requests over HTTP, sending raw transactions succeeds and every other request fails. The
+    /// connection is closed without a response for the first `failed_broadcasts` raw transactions. Return the URL of
+    /// the RPC provider and the broadcast raw transactions.
+    async fn spawn_rpc_provider(
+        failed_broadcasts: usize,
+    ) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        let raw_txs = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let broadcasts = raw_txs.clone();
+        tokio::spawn(async move {
+            while let Ok((mut connection, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                let body_start = loop {
+                    if let Some(index) = request.windows(4).position(|window| window == b"\r\n\r\n")
+                    {
+                        break index + 4;
+                    }
+                    match connection.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                };
+                let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+                let content_length = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|length| length.trim().parse::<usize>().ok())
+                    .unwrap_or_default();
+                while request.len() < body_start + content_length {
+                    match connection.read(&mut buf).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+
+                let request: serde_json::Value =
+                    serde_json::from_slice(&request[body_start..]).unwrap_or_default();
+                let body = match request["method"].as_str() {
+                    Some("eth_getTransactionCount") => {
+                        r#"{"jsonrpc":"2.0","id":1,"result":"0x0"}"#.to_string()
+                    }
+                    Some("eth_sendRawTransaction") => {
+                        let raw_tx = request["params"][0].as_str().unwrap_or_default().to_string();
+                        let tx_hash = TxHash::from(ethers::utils::keccak256(
+                            raw_tx.parse::<Bytes>().unwrap_or_default(),
+                        ));
+                        let mut broadcasts = broadcasts.lock().unwrap();
+                        broadcasts.push(raw_tx);
+                        if broadcasts.len() <= failed_broadcasts {
+                            continue;
+                        }
+                        format!(r#"{{"jsonrpc":"2.0","id":1,"result":"{:?}"}}"#, tx_hash)
+                    }
+                    _ => r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32603,"message":"internal error"}}"#
+                        .to_string(),
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = connection.write_all(response.as_bytes()).await;
+            }
+        });
+
+        (rpc_url, raw_txs)
+    }
+
+    #[tokio::test]
+    async fn can_retry_transient_step_errors() {
+        // no RPC provider listens on the port
+        let yaml = r#"
+            type: CallContract
+            name: get_owner
+            arguments:
+                chain_rpc_url: "http://localhost:1"
+                contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                function_signature: "owner()"
+                function_arguments: []
+            output:
+                save_as: OWNER
+            retry:
+                attempts: 3
+                backoff_ms: 1
+                jitter: false
+        "#;
+        let step_config: StepConfig = serde_yaml::from_str(yaml).unwrap();
+        let playbook_context = PlaybookContextBuilder::default().build().unwrap();
+
+        let (result, attempts) = StepExecutor::execute_with_retry(
+            &step_config,
+            &mut JobContext::default(),
+            &playbook_context,
+            None,
+            None,
+            None,
+        )
+        .await;
+        assert!(result.unwrap_err().is_transient());
+        assert_eq!(attempts, 3);
+
+        // the output is not saved by any earlier step, which fails again if retried
+        let yaml = yaml.replace(
+            "\"0x1F98431c8aD98523631AE4a59f267346ea31F984\"",
+            "\"${{ outputs.POOL_ADDRESS }}\"",
+        );
+        let step_config: StepConfig = serde_yaml::from_str(&yaml).unwrap();
+
+        let (result, attempts) = StepExecutor::execute_with_retry(
+            &step_config,
+            &mut JobContext::default(),
+            &playbook_context,
+            None,
+            None,
+            None,
+        )
+        .await;
+        assert!(!result.unwrap_err().is_transient());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn cannot_send_transaction_again_when_receipt_polling_fails() {
+        let (rpc_url, raw_txs) = spawn_rpc_provider(0).await;
+        let yaml = r#"
+            type: SendTransaction
+            name: transfer
+            timeout_ms: 500
+            arguments:
+                chain_rpc_url: "RPC_URL"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                chain_id: 31337
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+                value: 1000
+                gas: 21000
+                gas_price: 1
+            retry:
+                attempts: 3
+                backoff_ms: 1
+                jitter: false
+        "#
+        .replace("RPC_URL", &rpc_url);
+        let step_config: StepConfig = serde_yaml::from_str(&yaml).unwrap();
+        let playbook_context = PlaybookContextBuilder::default().build().unwrap();
+
+        let (result, attempts) = StepExecutor::execute_with_retry(
+            &step_config,
+            &mut JobContext::default(),
+            &playbook_context,
+            None,
+            None,
+            None,
+        )
+        .await;
+        let err = result.unwrap_err();
+        let raw_txs = raw_txs.lock().unwrap().clone();
+        assert_eq!(raw_txs.len(), 1);
+        assert_eq!(
+            err.tx_hash(),
+            Some(TxHash::from(ethers::utils::keccak256(
+                raw_txs[0].parse::<Bytes>().unwrap()
+            )))
+        );
+        assert!(!err.is_transient());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn can_broadcast_same_transaction_again_when_broadcast_fails() {
+        let (rpc_url, raw_txs) = spawn_rpc_provider(1).await;
+        let yaml = r#"
+            type: SendTransaction
+            name: transfer
+            timeout_ms: 500
+            arguments:
+                chain_rpc_url: "RPC_URL"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                chain_id: 31337
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+                value: 1000
+                gas: 21000
+                gas_price: 1
+            retry:
+                attempts: 3
+                backoff_ms: 1
+                jitter: false
+        "#
+        .replace("RPC_URL", &rpc_url);
+        let step_config: StepConfig = serde_yaml::from_str(&yaml).unwrap();
+        let playbook_context = PlaybookContextBuilder::default().build().unwrap();
+
+        let (result, attempts) = StepExecutor::execute_with_retry(
+            &step_config,
+            &mut JobContext::default(),
+            &playbook_context,
+            None,
+            None,
+            None,
+        )
+        .await;
+        // the second attempt broadcasts the same signed transaction, then fetching its receipt fails
+        let err = result.unwrap_err();
+        assert_matches!(
+            err,
+            ExecuteStepError::StepError(StepError::TransactionUnconfirmed { .. })
+        );
+        assert_eq!(attempts, 2);
+        let raw_txs = raw_txs.lock().unwrap().clone();
+        assert_eq!(raw_txs.len(), 2);
+        assert_eq!(raw_txs[0], raw_txs[1]);
+    }
+
+    #[tokio::test]
+    async fn can_time_out_transaction_before_it_is_sent() {
+        // the RPC provider accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let yaml = r#"
+            type: SendTransaction
+            name: transfer
+            timeout_ms: 200
+            arguments:
+                chain_rpc_url: "RPC_URL"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                chain_id: 31337
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+                value: 1000
+        "#
+        .replace("RPC_URL", &rpc_url);
+        let step_config: StepConfig = serde_yaml::from_str(&yaml).unwrap();
+        let playbook_context = PlaybookContextBuilder::default().build().unwrap();
+
+        let (result, _) = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            StepExecutor::execute_with_retry(
+                &step_config,
+                &mut JobContext::default(),
+                &playbook_context,
+                None,
+                None,
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+        let err = result.unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(err.tx_hash(), None);
+    }
+
+    #[tokio::test]
+    async fn can_cancel_confirming_transaction_of_resumed_run() {
+        // the RPC provider accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let yaml = r#"
+            type: SendTransaction
+            name: transfer
+            arguments:
+                chain_rpc_url: "RPC_URL"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                chain_id: 31337
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+        "#
+        .replace("RPC_URL", &rpc_url);
+        let step_config: StepConfig = serde_yaml::from_str(&yaml).unwrap();
+        let cancellation = tokio_util::sync::CancellationToken::new();
+        let playbook_context = PlaybookContextBuilder::default()
+            .build()
+            .unwrap()
+            .with_cancellation(cancellation.clone());
+        cancellation.cancel();
+
+        let (result, _) = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            StepExecutor::execute_with_retry(
+                &step_config,
+                &mut JobContext::default(),
+                &playbook_context,
+                None,
+                Some(TxHash::repeat_byte(1)),
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+        let err = result.unwrap_err();
+        assert!(err.is_cancelled());
+        assert_eq!(err.tx_hash(), Some(TxHash::repeat_byte(1)));
+    }
 }
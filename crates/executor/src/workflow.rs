@@ -2,3 +2,5 @@ mod workflow_context;
 pub use workflow_context::*;
 mod workflow_executor;
 pub use workflow_executor::*;
+mod workflow_scheduler;
+pub use workflow_scheduler::*;
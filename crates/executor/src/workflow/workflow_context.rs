@@ -1,25 +1,19 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
 
-use futures::lock::Mutex;
+use interpreter::{job::JobName, types::SavedOutputs};
 use thiserror::Error;
-use tokio::sync::broadcast::{Receiver, Sender};
-use interpreter::job::JobName;
 
-use crate::job::JobContext;
+use crate::{job::JobContext, report::RunStatus};
 
 #[derive(Default)]
 pub struct WorkflowContext {
     job_contexts: HashMap<JobName, JobContext>,
-    pending_jobs: HashMap<JobName, Sender<()>>,
+    job_statuses: HashMap<JobName, RunStatus>,
+    /// The first failed job which stops the workflow when `fail_fast` is set
+    failed_job: Option<JobName>,
 }
 
-pub type SharedMutableWorkflowContext = Arc<Mutex<WorkflowContext>>;
-
 impl WorkflowContext {
-    pub fn new_share_mutable() -> SharedMutableWorkflowContext {
-        Arc::new(Mutex::new(WorkflowContext::default()))
-    }
-
     pub fn add_job_context(
         &mut self,
         job_name: &JobName,
@@ -33,29 +27,35 @@ impl WorkflowContext {
         Ok(())
     }
 
-    pub async fn wait_for_job_to_finish(
-        &self,
-        job_name: &JobName,
-    ) -> Result<Receiver<()>, WorkflowContextError> {
-        let sender = self
-            .pending_jobs
-            .get(job_name)
-            .ok_or(WorkflowContextError::NoPendingJob(job_name.clone()))?;
+    /// Get saved outputs of finished jobs among the given jobs
+    pub fn job_outputs(&self, job_names: &[JobName]) -> HashMap<JobName, SavedOutputs> {
+        job_names
+            .iter()
+            .filter_map(|job_name| {
+                self.job_contexts
+                    .get(job_name)
+                    .map(|job_context| (job_name.clone(), job_context.outputs().saved().clone()))
+            })
+            .collect()
+    }
 
-        Ok(sender.subscribe())
+    /// Status of the completed job
+    pub fn job_status(&self, job_name: &JobName) -> Option<RunStatus> {
+        self.job_statuses.get(job_name).copied()
     }
 
-    pub fn add_pending_job(
-        &mut self,
-        job_name: &JobName,
-        sender: Sender<()>,
-    ) -> Result<(), WorkflowContextError> {
-        if self.pending_jobs.contains_key(job_name) {
-            return Err(WorkflowContextError::JobExisted(job_name.clone()));
-        }
-        self.pending_jobs.insert(job_name.clone(), sender);
+    pub fn set_job_status(&mut self, job_name: &JobName, status: RunStatus) {
+        self.job_statuses.insert(job_name.clone(), status);
+    }
 
-        Ok(())
+    /// The first failed job which stops the workflow, if any
+    pub fn failed_job(&self) -> Option<&JobName> {
+        self.failed_job.as_ref()
+    }
+
+    /// Record a failed job stopping the workflow, only the first one is kept
+    pub fn set_failed_job(&mut self, job_name: &JobName) {
+        self.failed_job.get_or_insert_with(|| job_name.clone());
     }
 }
 
@@ -63,6 +63,4 @@ impl WorkflowContext {
 pub enum WorkflowContextError {
     #[error("job {:#?} existed", .0)]
     JobExisted(JobName),
-    #[error("no pending job {:#?}", .0)]
-    NoPendingJob(JobName),
 }
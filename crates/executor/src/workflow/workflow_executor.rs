@@ -1,121 +1,348 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use daggy::{Dag, EdgeIndex, NodeIndex, Walker};
-use futures::stream::StreamExt;
-use thiserror::Error;
-use tokio::sync::broadcast;
 use config::logging::{__tracing as tracing, error, info, instrument};
+use daggy::{Dag, EdgeIndex, NodeIndex, Walker};
 use interpreter::job::JobConfig;
 use interpreter::{
     job::JobName,
+    playbook::Setup,
+    step::{StepConfigError, StepContextBuilder, StepContextBuilderError, StepName},
     workflow::{WorkflowConfig, WorkflowName},
 };
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::task::JoinSet;
 
+use crate::deadline::{deadline_after, is_expired};
+use crate::graph::{GraphNode, WorkflowGraph};
 use crate::job::{ExecuteJobError, JobExecutor};
+use crate::plan::{JobPlan, JobStepPlan, WorkflowPlan};
 use crate::playbook::SharedMutexPlaybookContext;
-use crate::workflow::WorkflowContext;
+use crate::report::{JobReport, RunStatus, StepReport, Timer, WorkflowReport};
+use crate::workflow::{WorkflowContext, WorkflowScheduler};
 
-use super::{SharedMutableWorkflowContext, WorkflowContextError};
+use super::WorkflowContextError;
 
 pub struct WorkflowExecutor {}
 
 impl WorkflowExecutor {
+    /// Execute jobs of the workflow and return the workflow report. A job is dispatched once all its prerequisited
+    /// jobs are completed, and ready jobs run in parallel within the `max_parallel` limits of the workflow and the
+    /// playbook. Jobs not started before the `timeout` of the workflow or the playbook are recorded as timed out, and
+    /// jobs not started before the run is interrupted are recorded as cancelled. Once a job fails in a workflow with
+    /// `fail_fast`, its running jobs are cancelled like an interrupted run, and jobs not started are skipped.
+    /// Failed jobs are recorded in the report, an error is only returned if the jobs can not be scheduled.
     #[instrument(name = "WorkflowExecute", skip(workflow_config))]
     pub async fn execute(
         workflow_name: WorkflowName,
         workflow_config: WorkflowConfig,
         playbook_context: SharedMutexPlaybookContext,
-    ) -> Result<WorkflowName, WorkflowExecutorError> {
+    ) -> Result<WorkflowReport, WorkflowExecutorError> {
         info!("Executing workflow {:#?}", workflow_name);
 
-        let workflow_context = WorkflowContext::new_share_mutable();
+        let timer = Timer::start();
 
-        let mut workflow_dag = WorkflowDAG::new();
+        let mut workflow_context = WorkflowContext::default();
+        // jobs of the workflow are cancelled with the run, or once a job fails when `fail_fast` is set
+        let playbook_context = playbook_context.lock().await.clone();
+        let cancellation = playbook_context.cancellation().child_token();
+        let playbook_context = playbook_context.with_cancellation(cancellation.clone());
+        let deadline = deadline_after(workflow_config.timeout(), playbook_context.deadline());
 
-        let job_configs = workflow_config.get_jobs();
-        let jobs_num = job_configs.len();
+        let workflow_dag = WorkflowDAG::from_workflow_config(&workflow_config)?;
+        let ranked_nodes = workflow_dag.ranked_nodes();
 
-        for (job_name, job_config) in job_configs {
-            let _node_index = workflow_dag.add_node(job_name, job_config)?;
-        }
+        // a job can only read outputs of its ancestors in the DAG
+        let ancestor_jobs = ranked_nodes
+            .iter()
+            .map(|node| {
+                workflow_dag
+                    .ancestors(&node.job_name)
+                    .map(|ancestor_jobs| (node.job_name.clone(), ancestor_jobs))
+            })
+            .collect::<Result<HashMap<JobName, Vec<JobName>>, WorkflowDAGError>>()?;
 
-        for (job_name, job_config) in job_configs {
-            for prerequisited_job_name in job_config.prerequisited_jobs() {
-                workflow_dag.add_edge(prerequisited_job_name, job_name)?;
-            }
-        }
+        let mut scheduler = WorkflowScheduler::new(
+            ranked_nodes
+                .iter()
+                .map(|node| (&node.job_name, node.job_config.prerequisited_jobs())),
+        )
+        .with_max_parallel(workflow_config.max_parallel());
+        let job_permits = playbook_context.job_permits();
+        let mut running_jobs = JoinSet::new();
+        let mut job_reports = HashMap::with_capacity(ranked_nodes.len());
 
-        // sorted topological DAG tell us the executing job order
-        let sorted_graph = workflow_dag.topological_sort();
+        while !scheduler.is_finished() {
+            while let Some(job_name) = scheduler.next_ready_job() {
+                let job_config = workflow_config.get_jobs()[&job_name].clone();
 
-        let tasks =
-            sorted_graph.into_iter().map(|node| {
-                tokio::spawn({
-                    let job_name = node.job_name;
-                    let job_config = node.job_config;
-                    let workflow_context = workflow_context.clone();
+                if let Some(skipped_reason) =
+                    Self::skipped_reason(&job_config, &workflow_config, &workflow_context)
+                {
+                    info!("Skip job {:#?}: {}", job_name, skipped_reason);
 
-                    async move {
-                        Self::spawn_job(job_name, job_config, jobs_num, workflow_context).await
-                    }
-                })
-            });
+                    workflow_context.set_job_status(&job_name, RunStatus::Skipped);
+                    scheduler.complete(&job_name);
+                    job_reports.insert(
+                        job_name.clone(),
+                        JobReport::skipped(
+                            job_name.as_str(),
+                            skipped_reason,
+                            Self::skipped_step_reports(&job_config),
+                        ),
+                    );
+                    continue;
+                }
+
+                // wait for the playbook limit of running jobs, the permit is released when the job finishes
+                let job_permit = match &job_permits {
+                    Some(job_permits) => tokio::select! {
+                        job_permit = job_permits.clone().acquire_owned() => Some(job_permit?),
+                        _ = cancellation.cancelled() => None,
+                    },
+                    None => None,
+                };
+
+                let not_started = if is_expired(deadline) {
+                    Some((
+                        RunStatus::TimedOut,
+                        "workflow timed out before the job started",
+                    ))
+                } else if cancellation.is_cancelled() {
+                    Some((RunStatus::Cancelled, "run cancelled before the job started"))
+                } else {
+                    None
+                };
+                if let Some((status, reason)) = not_started {
+                    info!("Job {:#?} is not started: {}", job_name, reason);
 
-        let mut stream = futures::stream::iter(tasks).buffered(jobs_num);
+                    let mut job_report = JobReport::skipped(
+                        job_name.as_str(),
+                        reason,
+                        Self::skipped_step_reports(&job_config),
+                    );
+                    job_report.status = status;
+                    workflow_context.set_job_status(&job_name, status);
+                    scheduler.complete(&job_name);
+                    job_reports.insert(job_name.clone(), job_report);
+                    continue;
+                }
+
+                // execute the job with outputs of its ancestors
+                let job_outputs = workflow_context.job_outputs(&ancestor_jobs[&job_name]);
+                let workflow_name = workflow_name.clone();
+                let playbook_context = playbook_context.clone();
+                running_jobs.spawn(async move {
+                    let _job_permit = job_permit;
+                    let (job_context, job_report) = JobExecutor::execute(
+                        &workflow_name,
+                        &job_name,
+                        &job_config,
+                        &playbook_context,
+                        job_outputs,
+                        deadline,
+                    )
+                    .await;
+                    (job_name, job_config, job_context, job_report)
+                });
+            }
 
-        // jobs are executed in parallel, so if a job is failed, it should not affect other jobs
-        while let Some(job_result) = stream.next().await {
-            let job_result = job_result?;
+            let Some(finished_job) = running_jobs.join_next().await else {
+                break;
+            };
+            let (job_name, job_config, job_context, job_report) = finished_job?;
+            let job_report = job_report.with_continue_on_error(job_config.continue_on_error());
 
-            match job_result {
-                Ok(job_name) => {
-                    info!("Finish job {:#?}", job_name);
+            // jobs are executed in parallel, so if a job is failed, it should not affect other jobs
+            match job_report.status {
+                RunStatus::Failed | RunStatus::TimedOut => {
+                    error!(
+                        "error execute job {:#?}: {}",
+                        job_report.name,
+                        job_report.error.join(": ")
+                    );
+                    if !job_config.continue_on_error() {
+                        workflow_context.set_failed_job(&job_name);
+                        if workflow_config.fail_fast() {
+                            info!(
+                                "Cancel running jobs, workflow {:#?} fails fast",
+                                workflow_name
+                            );
+                            cancellation.cancel();
+                        }
+                    }
                 }
-                Err(err) => {
-                    error!("error execute job {:#?}", err);
+                _ => {
+                    info!("Finish job {:#?}", job_report.name);
                 }
             }
+
+            // outputs saved before a failure are still readable by jobs continuing on error
+            workflow_context.add_job_context(&job_name, job_context)?;
+            workflow_context.set_job_status(&job_name, job_report.status);
+            scheduler.complete(&job_name);
+            job_reports.insert(job_name, job_report);
         }
 
-        Ok(workflow_name.clone())
+        // report jobs in rank order
+        let job_reports = ranked_nodes
+            .iter()
+            .filter_map(|node| job_reports.remove(&node.job_name))
+            .collect();
+
+        Ok(WorkflowReport::new(
+            workflow_name.as_str(),
+            timer.stop(),
+            job_reports,
+        ))
     }
 
-    async fn spawn_job(
-        job_name: JobName,
-        job_config: JobConfig,
-        maximum_num_rx: usize,
-        workflow_context: SharedMutableWorkflowContext,
-    ) -> Result<JobName, WorkflowExecutorError> {
-        let (tx, _rx) = broadcast::channel::<()>(maximum_num_rx);
-
-        let dependencies = job_config
-            .prerequisited_jobs()
-            .iter()
-            .map(|prerequisited_job_name| {
-                let workflow_context = workflow_context.clone();
-
-                async move {
-                    let workflow_context_mutex = workflow_context.lock().await;
-                    workflow_context_mutex
-                        .wait_for_job_to_finish(prerequisited_job_name)
-                        .await
-                }
-            });
+    /// Build the dependency graph of the jobs in the workflow, jobs are ordered by their rank in the DAG
+    pub fn graph(
+        workflow_name: &WorkflowName,
+        workflow_config: &WorkflowConfig,
+    ) -> Result<WorkflowGraph, WorkflowExecutorError> {
+        let workflow_dag = WorkflowDAG::from_workflow_config(workflow_config)?;
+        let ranked_nodes = workflow_dag.ranked_nodes();
 
-        // wait for all prerequisited jobs to finish first
-        futures::future::join_all(dependencies).await;
+        let mut edges = Vec::new();
+        for node in &ranked_nodes {
+            let mut prerequisited_jobs = node.job_config.prerequisited_jobs().to_vec();
+            prerequisited_jobs.sort_by(|a, b| a.as_str().cmp(b.as_str()));
 
-        // add pending jobs
-        let mut workflow_context_mutex = workflow_context.lock().await;
-        workflow_context_mutex.add_pending_job(&job_name, tx.clone())?;
+            edges.extend(
+                prerequisited_jobs
+                    .iter()
+                    .map(|job_name| (job_name.to_string(), node.job_name.to_string())),
+            );
+        }
+
+        let nodes = ranked_nodes
+            .into_iter()
+            .map(|node| GraphNode {
+                name: node.job_name.to_string(),
+                rank: node.job_rank.unwrap_or_default(),
+                step_count: node.job_config.steps().len(),
+                status: None,
+            })
+            .collect();
+
+        Ok(WorkflowGraph {
+            name: workflow_name.to_string(),
+            nodes,
+            edges,
+        })
+    }
 
-        // execute the job
-        let job_context = JobExecutor::execute(&job_name, &job_config).await?;
-        workflow_context_mutex.add_job_context(&job_name, job_context)?;
+    /// Build the execution plan of the workflow without executing or connecting anything. Jobs are ordered by their
+    /// rank in the DAG.
+    pub fn plan(
+        workflow_name: &WorkflowName,
+        workflow_config: &WorkflowConfig,
+        shared_setup: Option<Arc<Setup>>,
+    ) -> Result<WorkflowPlan, WorkflowExecutorError> {
+        let workflow_dag = WorkflowDAG::from_workflow_config(workflow_config)?;
+        let step_context = StepContextBuilder::default().setup(shared_setup).build()?;
 
-        let _ = tx.send(());
+        let jobs = workflow_dag
+            .ranked_nodes()
+            .into_iter()
+            .map(|node| {
+                let steps = node
+                    .job_config
+                    .steps()
+                    .iter()
+                    .map(|step_config| {
+                        let plan = step_config.plan(&step_context).map_err(|source| {
+                            WorkflowExecutorError::PlanStepError {
+                                job_name: node.job_name.clone(),
+                                step_name: step_config.name.clone(),
+                                source: Box::new(source),
+                            }
+                        })?;
+
+                        Ok(JobStepPlan {
+                            name: step_config.name.to_string(),
+                            title: step_config.title.clone(),
+                            step_type: step_config.step_type.to_string(),
+                            save_as: step_config
+                                .output
+                                .as_ref()
+                                .map(|output| output.save_as.to_string()),
+                            plan,
+                        })
+                    })
+                    .collect::<Result<Vec<JobStepPlan>, WorkflowExecutorError>>()?;
 
-        Ok(job_name.clone())
+                Ok(JobPlan {
+                    name: node.job_name.to_string(),
+                    rank: node.job_rank.unwrap_or_default(),
+                    depends_on: node
+                        .job_config
+                        .prerequisited_jobs()
+                        .iter()
+                        .map(|job_name| job_name.to_string())
+                        .collect(),
+                    steps,
+                })
+            })
+            .collect::<Result<Vec<JobPlan>, WorkflowExecutorError>>()?;
+
+        Ok(WorkflowPlan {
+            name: workflow_name.to_string(),
+            jobs,
+        })
+    }
+
+    /// Why the job is not executed: a prerequisited job failed or timed out without `continue_on_error` or is
+    /// skipped, or the workflow is stopped by `fail_fast`
+    fn skipped_reason(
+        job_config: &JobConfig,
+        workflow_config: &WorkflowConfig,
+        workflow_context: &WorkflowContext,
+    ) -> Option<String> {
+        for prerequisited_job_name in job_config.prerequisited_jobs() {
+            let continue_on_error = workflow_config
+                .get_jobs()
+                .get(prerequisited_job_name)
+                .is_some_and(JobConfig::continue_on_error);
+
+            let reason = match workflow_context.job_status(prerequisited_job_name) {
+                Some(RunStatus::Succeeded) => None,
+                Some(RunStatus::Failed | RunStatus::TimedOut) if continue_on_error => None,
+                Some(RunStatus::Failed) => Some("failed"),
+                Some(RunStatus::TimedOut) => Some("timed out"),
+                Some(RunStatus::Cancelled) => Some("was cancelled"),
+                Some(RunStatus::Skipped) => Some("was skipped"),
+                Some(RunStatus::Pending) | None => Some("did not finish"),
+            };
+            if let Some(reason) = reason {
+                return Some(format!(
+                    "prerequisite job `{}` {}",
+                    prerequisited_job_name.as_str(),
+                    reason
+                ));
+            }
+        }
+
+        if workflow_config.fail_fast() {
+            return workflow_context.failed_job().map(|failed_job_name| {
+                format!(
+                    "workflow stopped by fail_fast after job `{}` failed",
+                    failed_job_name.as_str()
+                )
+            });
+        }
+
+        None
+    }
+
+    fn skipped_step_reports(job_config: &JobConfig) -> Vec<StepReport> {
+        job_config
+            .steps()
+            .iter()
+            .map(|step| StepReport::skipped(step.name.as_str()).with_title(step.title.as_deref()))
+            .collect()
     }
 }
 
@@ -133,6 +360,26 @@ impl WorkflowDAG {
         }
     }
 
+    /// Build the DAG of jobs in the workflow, edges point from prerequisited jobs to their dependent jobs
+    pub fn from_workflow_config(
+        workflow_config: &WorkflowConfig,
+    ) -> Result<Self, WorkflowDAGError> {
+        let mut workflow_dag = WorkflowDAG::new();
+        let job_configs = workflow_config.get_jobs();
+
+        for (job_name, job_config) in job_configs {
+            let _node_index = workflow_dag.add_node(job_name, job_config)?;
+        }
+
+        for (job_name, job_config) in job_configs {
+            for prerequisited_job_name in job_config.prerequisited_jobs() {
+                workflow_dag.add_edge(prerequisited_job_name, job_name)?;
+            }
+        }
+
+        Ok(workflow_dag)
+    }
+
     /// Add node to the DAG
     pub fn add_node(
         &mut self,
@@ -187,9 +434,67 @@ impl WorkflowDAG {
         Ok(edge_index)
     }
 
+    /// Get all jobs which the job depends on, directly or transitively
+    pub fn ancestors(&self, job_name: &JobName) -> Result<Vec<JobName>, WorkflowDAGError> {
+        let node_index = *self
+            .job_to_node_index
+            .get(job_name)
+            .ok_or(WorkflowDAGError::NodeNotExisted(job_name.clone()))?;
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![node_index];
+
+        while let Some(node_index) = stack.pop() {
+            let mut parents_walker = self.dag.parents(node_index);
+
+            while let Some((_, parent_node_index)) = parents_walker.walk_next(&self.dag) {
+                if visited.insert(parent_node_index) {
+                    stack.push(parent_node_index);
+                }
+            }
+        }
+
+        Ok(visited
+            .into_iter()
+            .filter_map(|node_index| self.dag.node_weight(node_index))
+            .map(|node| node.job_name.clone())
+            .collect())
+    }
+
+    /// Return nodes sorted by rank, then by job name. The rank of a job is the length of the longest chain of
+    /// prerequisited jobs before it, so a job always comes after the jobs it depends on.
+    pub fn ranked_nodes(&self) -> Vec<WorkflowDAGNode> {
+        let mut ranks: HashMap<NodeIndex<u32>, u32> = HashMap::new();
+        let mut nodes = Vec::with_capacity(self.dag.node_count());
+
+        for mut node in self.topological_sort() {
+            let node_index = self.job_to_node_index[&node.job_name];
+            let rank = self
+                .dag
+                .parents(node_index)
+                .iter(&self.dag)
+                .filter_map(|(_, parent_index)| ranks.get(&parent_index))
+                .map(|parent_rank| parent_rank + 1)
+                .max()
+                .unwrap_or_default();
+
+            ranks.insert(node_index, rank);
+            node.job_rank = Some(rank);
+            nodes.push(node);
+        }
+
+        nodes.sort_by(|a, b| {
+            (a.job_rank, a.job_name.as_str()).cmp(&(b.job_rank, b.job_name.as_str()))
+        });
+        nodes
+    }
+
     /// Topological sort the DAG
     pub fn topological_sort(&self) -> Vec<WorkflowDAGNode> {
         let node_count = self.dag.node_count();
+        if node_count == 0 {
+            return Vec::new();
+        }
         let mut visited = vec![false; node_count];
         let mut ordering = vec![0usize; node_count];
         let mut i = node_count - 1;
@@ -243,7 +548,7 @@ pub enum WorkflowDAGError {
     NodeExisted(JobName),
     #[error("node {:#?} not existed", .0)]
     NodeNotExisted(JobName),
-    #[error("cylic edge in DAG, error {:#?}", .0)]
+    #[error("cyclic edge in DAG, error {:#?}", .0)]
     CyclicEdge(String),
 }
 
@@ -269,11 +574,22 @@ pub enum WorkflowExecutorError {
     #[error(transparent)]
     TokioTaskJoinError(#[from] tokio::task::JoinError),
     #[error(transparent)]
+    AcquireJobPermitError(#[from] tokio::sync::AcquireError),
+    #[error(transparent)]
     WorkflowDAGError(#[from] WorkflowDAGError),
     #[error(transparent)]
     ExecuteJobError(#[from] ExecuteJobError),
     #[error(transparent)]
     WorkflowContextError(#[from] WorkflowContextError),
+    #[error(transparent)]
+    StepContextBuilderError(#[from] StepContextBuilderError),
+    #[error("cannot plan step {:#?} of job {:#?}", .step_name, .job_name)]
+    PlanStepError {
+        job_name: JobName,
+        step_name: StepName,
+        #[source]
+        source: Box<StepConfigError>,
+    },
 }
 
 #[cfg(test)]
@@ -281,7 +597,7 @@ mod tests {
     use std::str::FromStr;
 
     use crate::playbook::PlaybookContextBuilder;
-    use interpreter::{job::JobConfigBuilder, workflow::WorkflowConfigBuilder};
+    use interpreter::{job::JobConfigBuilder, playbook::Playbook, workflow::WorkflowConfigBuilder};
 
     use super::*;
 
@@ -361,8 +677,448 @@ mod tests {
             .unwrap()
             .into_shared_mutex();
 
-        WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context)
-            .await
+        let workflow_report =
+            WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context)
+                .await
+                .unwrap();
+        assert_eq!(workflow_report.status, RunStatus::Succeeded);
+        assert_eq!(workflow_report.jobs.len(), 6);
+        // jobs are reported in rank order whatever order they finish in
+        let job_names = workflow_report
+            .jobs
+            .iter()
+            .map(|job| job.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            job_names,
+            vec!["job_1", "job_2", "job_3", "job_4", "job_5", "job_6"]
+        );
+    }
+
+    #[tokio::test]
+    async fn can_skip_jobs_depending_on_failed_jobs() {
+        // `approve` and `check` fail since no RPC provider listens on the port, `check` continues on error
+        let yaml = r#"
+            version: "1"
+            jobs:
+                approve:
+                    steps:
+                      - type: CallContract
+                        name: approve
+                        arguments:
+                            chain_rpc_url: "ws://localhost:1"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "owner()"
+                            function_arguments: []
+                        output:
+                            save_as: OWNER
+                swap:
+                    steps: []
+                notify:
+                    steps: []
+                check:
+                    continue_on_error: true
+                    steps:
+                      - type: CallContract
+                        name: check
+                        arguments:
+                            chain_rpc_url: "ws://localhost:1"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "owner()"
+                            function_arguments: []
+                        output:
+                            save_as: CHECKED_OWNER
+                report:
+                    steps: []
+            workflows:
+                workflow_1:
+                    fail_fast: false
+                    jobs:
+                    - approve
+                    - name: swap
+                      depends_on: [approve]
+                    - name: notify
+                      depends_on: [swap]
+                    - check
+                    - name: report
+                      depends_on: [check]
+        "#;
+
+        let playbook: Playbook = serde_yaml::from_str(yaml).unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let workflow_config = playbook.shared_workflows()[&workflow_name].clone();
+        assert!(!workflow_config.fail_fast());
+
+        let playbook_context = PlaybookContextBuilder::default()
+            .build()
+            .unwrap()
+            .into_shared_mutex();
+        let workflow_report =
+            WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context)
+                .await
+                .unwrap();
+        assert_eq!(workflow_report.status, RunStatus::Failed);
+
+        let job_report = |name: &str| {
+            workflow_report
+                .jobs
+                .iter()
+                .find(|job| job.name == name)
+                .unwrap()
+        };
+        assert_eq!(job_report("approve").status, RunStatus::Failed);
+        assert_eq!(job_report("swap").status, RunStatus::Skipped);
+        assert_eq!(
+            job_report("swap").skipped_reason.as_deref(),
+            Some("prerequisite job `approve` failed")
+        );
+        assert_eq!(
+            job_report("notify").skipped_reason.as_deref(),
+            Some("prerequisite job `swap` was skipped")
+        );
+        assert_eq!(job_report("check").status, RunStatus::Failed);
+        assert!(job_report("check").continue_on_error);
+        assert_eq!(job_report("report").status, RunStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn can_time_out_jobs() {
+        // the RPC provider accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let yaml = r#"
+            version: "1"
+            jobs:
+                get_owner:
+                    timeout_ms: 100
+                    steps:
+                      - type: CallContract
+                        name: get_owner
+                        arguments:
+                            chain_rpc_url: "RPC_URL"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "owner()"
+                            function_arguments: []
+                        output:
+                            save_as: OWNER
+                      - type: BlankStep
+                        name: after_get_owner
+                notify:
+                    steps: []
+            workflows:
+                workflow_1:
+                    jobs:
+                    - get_owner
+                    - name: notify
+                      depends_on: [get_owner]
+        "#
+        .replace("RPC_URL", &rpc_url);
+
+        let playbook: Playbook = serde_yaml::from_str(&yaml).unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let workflow_config = playbook.shared_workflows()[&workflow_name].clone();
+        let playbook_context = PlaybookContextBuilder::default()
+            .build()
+            .unwrap()
+            .into_shared_mutex();
+
+        let workflow_report =
+            WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context)
+                .await
+                .unwrap();
+        assert_eq!(workflow_report.status, RunStatus::TimedOut);
+
+        let get_owner = &workflow_report.jobs[0];
+        assert_eq!(get_owner.status, RunStatus::TimedOut);
+        assert_eq!(get_owner.steps[0].status, RunStatus::TimedOut);
+        assert_eq!(get_owner.steps[1].status, RunStatus::Skipped);
+        assert_eq!(
+            workflow_report.jobs[1].skipped_reason.as_deref(),
+            Some("prerequisite job `get_owner` timed out")
+        );
+    }
+
+    #[tokio::test]
+    async fn can_cancel_jobs() {
+        // the RPC provider accepts connections but never responds
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let yaml = r#"
+            version: "1"
+            jobs:
+                get_owner:
+                    steps:
+                      - type: CallContract
+                        name: get_owner
+                        arguments:
+                            chain_rpc_url: "RPC_URL"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "owner()"
+                            function_arguments: []
+                        output:
+                            save_as: OWNER
+                      - type: BlankStep
+                        name: after_get_owner
+                notify:
+                    steps: []
+                report:
+                    steps: []
+            workflows:
+                workflow_1:
+                    # report is dispatched after get_owner
+                    max_parallel: 1
+                    jobs:
+                    - get_owner
+                    - name: notify
+                      depends_on: [get_owner]
+                    - report
+        "#
+        .replace("RPC_URL", &rpc_url);
+
+        let playbook: Playbook = serde_yaml::from_str(&yaml).unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let workflow_config = playbook.shared_workflows()[&workflow_name].clone();
+        let cancellation = tokio_util::sync::CancellationToken::new();
+        let playbook_context = PlaybookContextBuilder::default()
+            .cancellation(cancellation.clone())
+            .build()
+            .unwrap()
+            .into_shared_mutex();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            cancellation.cancel();
+        });
+        let workflow_report =
+            WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context)
+                .await
+                .unwrap();
+        assert_eq!(workflow_report.status, RunStatus::Cancelled);
+
+        let job_reports = workflow_report
+            .jobs
+            .iter()
+            .map(|job_report| (job_report.name.as_str(), job_report))
+            .collect::<HashMap<_, _>>();
+        let get_owner = job_reports["get_owner"];
+        assert_eq!(get_owner.status, RunStatus::Cancelled);
+        assert_eq!(get_owner.steps[0].status, RunStatus::Cancelled);
+        assert_eq!(get_owner.steps[1].status, RunStatus::Skipped);
+        assert_eq!(
+            job_reports["notify"].skipped_reason.as_deref(),
+            Some("prerequisite job `get_owner` was cancelled")
+        );
+        assert_eq!(job_reports["report"].status, RunStatus::Cancelled);
+        assert_eq!(
+            job_reports["report"].skipped_reason.as_deref(),
+            Some("run cancelled before the job started")
+        );
+    }
+
+    #[tokio::test]
+    async fn can_cancel_running_jobs_on_fail_fast() {
+        // the RPC provider of `get_owner` accepts connections but never responds, `approve` fails since no RPC
+        // provider listens on the port
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let yaml = r#"
+            version: "1"
+            jobs:
+                approve:
+                    steps:
+                      - type: CallContract
+                        name: approve
+                        arguments:
+                            chain_rpc_url: "ws://localhost:1"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "owner()"
+                            function_arguments: []
+                        output:
+                            save_as: OWNER
+                get_owner:
+                    steps:
+                      - type: CallContract
+                        name: get_owner
+                        arguments:
+                            chain_rpc_url: "RPC_URL"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "owner()"
+                            function_arguments: []
+                        output:
+                            save_as: POOL_OWNER
+                notify:
+                    steps: []
+            workflows:
+                workflow_1:
+                    fail_fast: true
+                    jobs:
+                    - approve
+                    - get_owner
+                    - name: notify
+                      depends_on: [get_owner]
+        "#
+        .replace("RPC_URL", &rpc_url);
+
+        let playbook: Playbook = serde_yaml::from_str(&yaml).unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let workflow_config = playbook.shared_workflows()[&workflow_name].clone();
+        let cancellation = tokio_util::sync::CancellationToken::new();
+        let playbook_context = PlaybookContextBuilder::default()
+            .cancellation(cancellation.clone())
+            .build()
+            .unwrap()
+            .into_shared_mutex();
+
+        let workflow_report = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            WorkflowExecutor::execute(workflow_name, workflow_config, playbook_context),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(workflow_report.status, RunStatus::Failed);
+
+        let job_reports = workflow_report
+            .jobs
+            .iter()
+            .map(|job_report| (job_report.name.as_str(), job_report))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(job_reports["approve"].status, RunStatus::Failed);
+        assert_eq!(job_reports["get_owner"].status, RunStatus::Cancelled);
+        assert_eq!(job_reports["notify"].status, RunStatus::Skipped);
+        // only the jobs of the workflow are cancelled, not the run
+        assert!(!cancellation.is_cancelled());
+    }
+
+    #[test]
+    fn can_plan_workflow_in_rank_order() {
+        let yaml = r#"
+            version: "1"
+            jobs:
+                get_pool:
+                    steps:
+                      - type: CallContract
+                        name: get_pool
+                        arguments:
+                            chain_rpc_url: "https://eth.llamarpc.com"
+                            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                            function_signature: "getPool(address,address,uint24)"
+                            function_arguments:
+                                - type: address
+                                  value: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                                - type: address
+                                  value: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+                                - type: uint24
+                                  value: 3000
+                            function_return_types: [address]
+                        output:
+                            save_as: POOL_ADDRESS
+                get_slot0:
+                    steps:
+                      - type: CallContract
+                        name: get_slot0
+                        arguments:
+                            chain_rpc_url: "https://eth.llamarpc.com"
+                            contract_address: "${{ jobs.get_pool.outputs.POOL_ADDRESS }}"
+                            function_signature: "slot0()"
+                            function_arguments: []
+                        output:
+                            save_as: SLOT0
+                get_liquidity:
+                    steps: []
+            workflows:
+                workflow_1:
+                    jobs:
+                    - name: get_slot0
+                      depends_on: [get_pool]
+                    - get_pool
+                    - name: get_liquidity
+                      depends_on: [get_slot0]
+        "#;
+
+        let playbook: Playbook = serde_yaml::from_str(yaml).unwrap();
+        let workflow_name = WorkflowName::from_str("workflow_1").unwrap();
+        let workflow_config = playbook.shared_workflows()[&workflow_name].clone();
+
+        let workflow_plan =
+            WorkflowExecutor::plan(&workflow_name, &workflow_config, playbook.shared_setup())
+                .unwrap();
+
+        let job_names = workflow_plan
+            .jobs
+            .iter()
+            .map(|job| (job.name.as_str(), job.rank))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            job_names,
+            vec![("get_pool", 0), ("get_slot0", 1), ("get_liquidity", 2)]
+        );
+
+        let get_pool_step = &workflow_plan.jobs[0].steps[0];
+        assert_eq!(get_pool_step.save_as.as_deref(), Some("POOL_ADDRESS"));
+        assert!(get_pool_step.plan.calldata.is_some());
+        assert_eq!(
+            workflow_plan.jobs[1].steps[0].plan.references,
+            vec!["${{ jobs.get_pool.outputs.POOL_ADDRESS }}"]
+        );
+    }
+
+    #[test]
+    fn can_get_ancestor_jobs() {
+        // job_3 depends on job_2, job_2 depends on job_1, job_4 has no dependency
+        let job_names = ["job_1", "job_2", "job_3", "job_4"]
+            .map(|job_name| JobName::from_str(job_name).unwrap());
+        let job_config = JobConfigBuilder::default()
+            .steps(vec![])
+            .depends_on(vec![])
+            .build()
             .unwrap();
+
+        let mut workflow_dag = WorkflowDAG::new();
+        for job_name in &job_names {
+            workflow_dag.add_node(job_name, &job_config).unwrap();
+        }
+        workflow_dag.add_edge(&job_names[0], &job_names[1]).unwrap();
+        workflow_dag.add_edge(&job_names[1], &job_names[2]).unwrap();
+
+        let ancestors: HashSet<JobName> = workflow_dag
+            .ancestors(&job_names[2])
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            ancestors,
+            HashSet::from([job_names[0].clone(), job_names[1].clone()])
+        );
+        assert!(workflow_dag.ancestors(&job_names[3]).unwrap().is_empty());
+        assert!(workflow_dag.ancestors(&job_names[0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_sort_workflow_without_jobs() {
+        let workflow_dag = WorkflowDAG::new();
+        assert!(workflow_dag.topological_sort().is_empty());
+        assert!(workflow_dag.ranked_nodes().is_empty());
     }
 }
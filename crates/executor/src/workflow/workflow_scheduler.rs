@@ -0,0 +1,151 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
+};
+
+use interpreter::job::JobName;
+
+/// Dispatch jobs of a workflow in dependency order. A job becomes ready once all its prerequisited jobs are
+/// completed, ready jobs are dispatched in the order they become ready, as long as fewer than `max_parallel` jobs are
+/// dispatched but not completed.
+#[derive(Debug, Default)]
+pub struct WorkflowScheduler {
+    /// Number of prerequisited jobs which are not completed yet
+    in_degrees: HashMap<JobName, usize>,
+    /// Jobs depending on the job
+    dependent_jobs: HashMap<JobName, Vec<JobName>>,
+    ready_jobs: VecDeque<JobName>,
+    /// Number of jobs which are not completed yet
+    uncompleted_jobs: usize,
+    /// Number of dispatched jobs which are not completed yet
+    running_jobs: usize,
+    max_parallel: Option<NonZeroUsize>,
+}
+
+impl WorkflowScheduler {
+    /// Create a scheduler from jobs with their prerequisited jobs, jobs without prerequisited jobs are ready in the
+    /// given order
+    pub fn new<'a, I>(jobs: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a JobName, &'a [JobName])>,
+    {
+        let mut scheduler = Self::default();
+
+        for (job_name, prerequisited_jobs) in jobs {
+            scheduler
+                .in_degrees
+                .insert(job_name.clone(), prerequisited_jobs.len());
+            scheduler.uncompleted_jobs += 1;
+
+            if prerequisited_jobs.is_empty() {
+                scheduler.ready_jobs.push_back(job_name.clone());
+            }
+            for prerequisited_job_name in prerequisited_jobs {
+                scheduler
+                    .dependent_jobs
+                    .entry(prerequisited_job_name.clone())
+                    .or_default()
+                    .push(job_name.clone());
+            }
+        }
+
+        scheduler
+    }
+
+    /// Limit the number of dispatched jobs which are not completed yet
+    pub fn with_max_parallel(mut self, max_parallel: Option<NonZeroUsize>) -> Self {
+        self.max_parallel = max_parallel;
+        self
+    }
+
+    /// Take the next job whose prerequisited jobs are all completed, none if `max_parallel` jobs are running
+    pub fn next_ready_job(&mut self) -> Option<JobName> {
+        if self
+            .max_parallel
+            .is_some_and(|max_parallel| self.running_jobs >= max_parallel.get())
+        {
+            return None;
+        }
+
+        let job_name = self.ready_jobs.pop_front()?;
+        self.running_jobs += 1;
+        Some(job_name)
+    }
+
+    /// Mark the job as completed, whatever its status is, jobs depending on it become ready once all their
+    /// prerequisited jobs are completed
+    pub fn complete(&mut self, job_name: &JobName) {
+        self.uncompleted_jobs = self.uncompleted_jobs.saturating_sub(1);
+        self.running_jobs = self.running_jobs.saturating_sub(1);
+
+        for dependent_job_name in self.dependent_jobs.get(job_name).into_iter().flatten() {
+            if let Some(in_degree) = self.in_degrees.get_mut(dependent_job_name) {
+                *in_degree = in_degree.saturating_sub(1);
+                if *in_degree == 0 {
+                    self.ready_jobs.push_back(dependent_job_name.clone());
+                }
+            }
+        }
+    }
+
+    /// Whether all jobs are completed
+    pub fn is_finished(&self) -> bool {
+        self.uncompleted_jobs == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn can_dispatch_jobs_once_prerequisited_jobs_completed() {
+        // job_3 depends on job_1 + job_2, job_4 depends on job_3
+        let [job_1, job_2, job_3, job_4] = ["job_1", "job_2", "job_3", "job_4"]
+            .map(|job_name| JobName::from_str(job_name).unwrap());
+        let job_3_prerequisited_jobs = [job_1.clone(), job_2.clone()];
+        let job_4_prerequisited_jobs = [job_3.clone()];
+
+        let mut scheduler = WorkflowScheduler::new([
+            (&job_1, &[][..]),
+            (&job_2, &[][..]),
+            (&job_3, &job_3_prerequisited_jobs[..]),
+            (&job_4, &job_4_prerequisited_jobs[..]),
+        ]);
+
+        assert_eq!(scheduler.next_ready_job(), Some(job_1.clone()));
+        assert_eq!(scheduler.next_ready_job(), Some(job_2.clone()));
+        assert_eq!(scheduler.next_ready_job(), None);
+
+        scheduler.complete(&job_2);
+        assert_eq!(scheduler.next_ready_job(), None);
+        scheduler.complete(&job_1);
+        assert_eq!(scheduler.next_ready_job(), Some(job_3.clone()));
+
+        scheduler.complete(&job_3);
+        assert_eq!(scheduler.next_ready_job(), Some(job_4.clone()));
+        assert!(!scheduler.is_finished());
+        scheduler.complete(&job_4);
+        assert!(scheduler.is_finished());
+    }
+
+    #[test]
+    fn can_limit_running_jobs() {
+        let [job_1, job_2, job_3] =
+            ["job_1", "job_2", "job_3"].map(|job_name| JobName::from_str(job_name).unwrap());
+
+        let mut scheduler =
+            WorkflowScheduler::new([(&job_1, &[][..]), (&job_2, &[][..]), (&job_3, &[][..])])
+                .with_max_parallel(NonZeroUsize::new(2));
+
+        assert_eq!(scheduler.next_ready_job(), Some(job_1.clone()));
+        assert_eq!(scheduler.next_ready_job(), Some(job_2.clone()));
+        assert_eq!(scheduler.next_ready_job(), None);
+
+        scheduler.complete(&job_1);
+        assert_eq!(scheduler.next_ready_job(), Some(job_3.clone()));
+        assert_eq!(scheduler.next_ready_job(), None);
+    }
+}
@@ -1,7 +1,8 @@
 use alloy_primitives::{Address, Bytes, FixedBytes, I256, U256};
 use ethers::types::H160;
+use thiserror::Error;
 
-/// Used to convert Alloy primitives types to ethers-rs types
+/// Used to convert between Alloy primitives types and ethers-rs types
 pub struct AlloyConverter {}
 
 impl AlloyConverter {
@@ -12,16 +13,39 @@ impl AlloyConverter {
         H160::from(bytes)
     }
 
-    /// Convert from alloy [`U256`] into ethers-rs [`U256`].
-    pub fn from_alloy_uint(uint: &U256, _size: usize) -> ethers::types::U256 {
-        let uint_str = uint.to_string();
-        ethers::types::U256::from_dec_str(&uint_str).unwrap()
+    /// Convert from alloy [`U256`] into ethers-rs [`U256`], both types store little-endian limbs. The value must fit
+    /// in `size` bits.
+    pub fn from_alloy_uint(
+        uint: &U256,
+        size: usize,
+    ) -> Result<ethers::types::U256, AlloyConverterError> {
+        if uint.bit_len() > size {
+            return Err(AlloyConverterError::UintOverflow {
+                value: uint.to_string(),
+                size,
+            });
+        }
+        Ok(ethers::types::U256(uint.into_limbs()))
     }
 
-    /// Convert from alloy [`I256`] into ethers-rs [`I256`]
-    pub fn from_alloy_int(alloy_value: &I256, _size: usize) -> ethers::types::I256 {
-        let int_str = alloy_value.to_string();
-        ethers::types::I256::from_dec_str(&int_str).unwrap()
+    /// Convert from alloy [`I256`] into ethers-rs [`I256`] through their two's complement representation. The value
+    /// must fit in `size` bits.
+    pub fn from_alloy_int(
+        int: &I256,
+        size: usize,
+    ) -> Result<ethers::types::I256, AlloyConverterError> {
+        let raw = int.into_raw();
+        // two's complement value fits in `size` bits if the bits other than the sign bit fit in `size - 1`
+        let magnitude = if int.is_negative() { !raw } else { raw };
+        if magnitude.bit_len() >= size {
+            return Err(AlloyConverterError::IntOverflow {
+                value: int.to_string(),
+                size,
+            });
+        }
+        Ok(ethers::types::I256::from_raw(ethers::types::U256(
+            raw.into_limbs(),
+        )))
     }
 
     /// Convert from alloy [`Bytes`] into ethers-rs [`Bytes`]
@@ -34,6 +58,29 @@ impl AlloyConverter {
         let bytes_vec = bytes.0.to_vec();
         ethers::types::Bytes::from(bytes_vec)
     }
+
+    /// Convert from ethers-rs [`H160`] into alloy [`Address`]
+    pub fn to_alloy_address(address: &H160) -> Address {
+        Address::from(address.0)
+    }
+
+    /// Convert from ethers-rs [`U256`] into alloy [`U256`], both types store little-endian limbs
+    pub fn to_alloy_uint(uint: &ethers::types::U256) -> U256 {
+        U256::from_limbs(uint.0)
+    }
+
+    /// Convert from ethers-rs [`I256`] into alloy [`I256`]
+    pub fn to_alloy_int(int: &ethers::types::I256) -> I256 {
+        I256::from_raw(Self::to_alloy_uint(&int.into_raw()))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AlloyConverterError {
+    #[error("value {value} does not fit in uint{size}")]
+    UintOverflow { value: String, size: usize },
+    #[error("value {value} does not fit in int{size}")]
+    IntOverflow { value: String, size: usize },
 }
 
 #[cfg(test)]
@@ -45,12 +92,12 @@ mod tests {
     proptest! {
         #[test]
         fn can_convert_from_alloy_uint(alloy_uint in U256::arbitrary()) {
-            let ethers_uint = AlloyConverter::from_alloy_uint(&alloy_uint, U256::BITS);
+            let ethers_uint = AlloyConverter::from_alloy_uint(&alloy_uint, U256::BITS).unwrap();
             assert_eq!(alloy_uint.to_string(), ethers_uint.to_string());
         }
         #[test]
         fn can_convert_from_alloy_int(alloy_int in I256::arbitrary()) {
-            let ethers_int = AlloyConverter::from_alloy_int(&alloy_int, I256::BITS);
+            let ethers_int = AlloyConverter::from_alloy_int(&alloy_int, I256::BITS).unwrap();
             assert_eq!(alloy_int.to_string(), ethers_int.to_string());
         }
         #[test]
@@ -59,11 +106,36 @@ mod tests {
             assert_eq!(alloy_bytes.to_string(), ethers_bytes.to_string());
         }
 
+        #[test]
+        fn can_convert_to_alloy_uint(alloy_uint in U256::arbitrary()) {
+            let ethers_uint = AlloyConverter::from_alloy_uint(&alloy_uint, U256::BITS).unwrap();
+            assert_eq!(AlloyConverter::to_alloy_uint(&ethers_uint), alloy_uint);
+        }
+
+        #[test]
+        fn can_convert_to_alloy_int(alloy_int in I256::arbitrary()) {
+            let ethers_int = AlloyConverter::from_alloy_int(&alloy_int, I256::BITS).unwrap();
+            assert_eq!(AlloyConverter::to_alloy_int(&ethers_int), alloy_int);
+        }
+
         #[test]
         fn can_convert_from_alloy_fixed_bytes(alloy_bytes in FixedBytes::<64>::arbitrary()) {
             let ethers_bytes = AlloyConverter::from_alloy_fixed_bytes(&alloy_bytes);
             assert_eq!(alloy_bytes.to_string(), ethers_bytes.to_string());
         }
+    }
+
+    #[test]
+    fn can_check_size_of_converted_integers() {
+        assert!(AlloyConverter::from_alloy_uint(&U256::from(255), 8).is_ok());
+        assert!(AlloyConverter::from_alloy_uint(&U256::from(256), 8).is_err());
 
+        let min_int8 = I256::try_from(-128).unwrap();
+        assert_eq!(
+            AlloyConverter::from_alloy_int(&min_int8, 8).unwrap(),
+            ethers::types::I256::from(-128)
+        );
+        assert!(AlloyConverter::from_alloy_int(&I256::try_from(-129).unwrap(), 8).is_err());
+        assert!(AlloyConverter::from_alloy_int(&I256::try_from(128).unwrap(), 8).is_err());
     }
 }
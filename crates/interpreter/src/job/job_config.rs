@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use derive_builder::Builder;
 use serde::Deserialize;
 
@@ -9,6 +11,14 @@ use super::JobName;
 #[derive(Debug, Builder, Deserialize, Clone)]
 pub struct JobConfig {
     steps: Vec<StepConfig>,
+    /// Let jobs depending on this job run even if it fails, its failure doesn't fail the workflow
+    #[serde(default)]
+    #[builder(default)]
+    continue_on_error: bool,
+    /// Maximum execution time of the job in milliseconds, unlimited if not set
+    #[serde(default)]
+    #[builder(default)]
+    timeout_ms: Option<u64>,
     /// Prerequisited Job
     #[serde(skip)]
     depends_on: Vec<JobName>,
@@ -26,4 +36,12 @@ impl JobConfig {
     pub fn steps(&self) -> &[StepConfig] {
         &self.steps
     }
+
+    pub fn continue_on_error(&self) -> bool {
+        self.continue_on_error
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
 }
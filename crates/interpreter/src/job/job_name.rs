@@ -1,16 +1,37 @@
-use derive_more::{Deref, FromStr};
-use serde::Deserialize;
+use derive_more::Deref;
 use serde_valid::Validate;
 
+use crate::types::impl_name;
+
 /// JobName can only contains alphanumeric, `_` or `-` characters, up to a maximum of 200 characters.
-#[derive(Debug, Deref, Deserialize, Validate, Eq, PartialEq, Hash, Clone, FromStr)]
+#[derive(Debug, Deref, Validate, Eq, PartialEq, Hash, Clone)]
 pub struct JobName(#[validate(pattern = r#"^[a-zA-Z0-9][a-zA-Z0-9_-]{1,199}$"#)] String);
 
+impl_name!(JobName, "job name");
+
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use super::*;
+    use crate::types::InvalidNameError;
+    use claims::assert_err;
     use proptest::prelude::*;
 
+    #[test]
+    fn cannot_parse_invalid_job_name() {
+        for job_name in ["a", "get pool", "-get_pool", "get/pool"] {
+            assert_err!(JobName::from_str(job_name));
+            assert_err!(serde_yaml::from_str::<JobName>(&format!(
+                "\"{}\"",
+                job_name
+            )));
+        }
+
+        let error = JobName::from_str("get pool").unwrap_err();
+        assert_eq!(error, InvalidNameError::new("job name", "get pool"));
+    }
+
     fn valid_job_names() -> impl Strategy<Value = String> {
         proptest::string::string_regex("[a-zA-Z0-9][a-zA-Z0-9_-]{1,199}").unwrap()
     }
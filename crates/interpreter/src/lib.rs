@@ -7,4 +7,5 @@ pub mod job;
 pub mod playbook;
 pub mod step;
 pub mod types;
+pub mod validation;
 pub mod workflow;
@@ -4,5 +4,7 @@ mod setup;
 pub use setup::*;
 mod playbook_block;
 pub use playbook_block::*;
+mod playbook_dir;
+pub(crate) use playbook_dir::*;
 mod playbook_config;
 pub use playbook_config::*;
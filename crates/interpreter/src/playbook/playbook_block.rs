@@ -1,4 +1,12 @@
 mod rpc_provider;
 pub use rpc_provider::*;
+mod rpc_client;
+pub use rpc_client::*;
 mod variable;
 pub use variable::*;
+mod signer;
+pub use signer::*;
+mod contract;
+pub use contract::*;
+mod artifacts;
+pub use artifacts::*;
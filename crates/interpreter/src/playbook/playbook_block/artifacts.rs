@@ -0,0 +1,325 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use ethers::{abi::Abi, types::Bytes};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::types::SolStructs;
+
+use super::ContractConfig;
+
+/// Contract ABIs and creation bytecodes loaded from build artifacts, either a Foundry `out/` directory or a Hardhat
+/// `artifacts/` directory
+#[derive(Debug, Clone, Default)]
+pub struct Artifacts {
+    contracts: HashMap<String, Vec<Artifact>>,
+}
+
+/// Build artifact of a contract
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    /// Contract name, e.g. `UniswapV3Factory`
+    pub name: String,
+    /// Source file of the contract, e.g. `UniswapV3Factory.sol` for Foundry or `contracts/UniswapV3Factory.sol` for
+    /// Hardhat
+    pub source: String,
+    pub contract: ContractConfig,
+}
+
+impl Artifact {
+    /// Parse the JSON content of an artifact file. Return None if the JSON is not a contract artifact, which has no
+    /// `abi` field.
+    pub fn parse(content: &str) -> Result<Option<ArtifactJson>, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(content)?;
+        if value.get("abi").is_none() {
+            return Ok(None);
+        }
+        let structs = SolStructs::from_json_abi(&value["abi"]);
+        let mut artifact_json: ArtifactJson = serde_json::from_value(value)?;
+        artifact_json.structs = structs;
+        Ok(Some(artifact_json))
+    }
+
+    fn load(path: &Path) -> Result<Option<Self>, ArtifactError> {
+        let content = std::fs::read_to_string(path).map_err(|source| ArtifactError::IoError {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let Some(artifact_json) =
+            Self::parse(&content).map_err(|source| ArtifactError::InvalidArtifact {
+                path: path.to_path_buf(),
+                source,
+            })?
+        else {
+            return Ok(None);
+        };
+
+        // Foundry writes the artifact of a contract into `<source file>/<contract name>.json`
+        let file_name = |path: Option<&Path>| {
+            path.and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        let name = artifact_json.contract_name.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        let source = artifact_json
+            .source_name
+            .clone()
+            .unwrap_or_else(|| file_name(path.parent()));
+
+        Ok(Some(Self {
+            name,
+            source,
+            contract: artifact_json.into_contract(),
+        }))
+    }
+
+    /// Fully qualified name of the contract, e.g. `UniswapV3Factory.sol:UniswapV3Factory`
+    pub fn qualified_name(&self) -> String {
+        format!("{}:{}", self.source, self.name)
+    }
+}
+
+/// Contract artifact written by Foundry or Hardhat. The creation bytecode is a hex string in Hardhat artifacts, and
+/// an object with the hex string in its `object` field in Foundry artifacts.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactJson {
+    pub abi: Abi,
+    #[serde(default)]
+    pub bytecode: Option<serde_json::Value>,
+    pub contract_name: Option<String>,
+    pub source_name: Option<String>,
+    /// Structs used by the ABI, which are collected from the raw JSON since [`Abi`] drops their names
+    #[serde(skip)]
+    pub structs: SolStructs,
+}
+
+impl ArtifactJson {
+    /// Return the creation bytecode. Return None for interfaces and abstract contracts which have no bytecode, or for
+    /// bytecode with unlinked libraries.
+    pub fn bytecode(&self) -> Option<Bytes> {
+        let bytecode = match self.bytecode.as_ref()? {
+            serde_json::Value::String(bytecode) => bytecode,
+            serde_json::Value::Object(bytecode) => bytecode.get("object")?.as_str()?,
+            _ => return None,
+        };
+        bytecode
+            .parse::<Bytes>()
+            .ok()
+            .filter(|bytecode| !bytecode.is_empty())
+    }
+
+    /// Convert the artifact into a contract config without address
+    pub fn into_contract(self) -> ContractConfig {
+        ContractConfig {
+            address: None,
+            bytecode: self.bytecode(),
+            abi: self.abi,
+            artifact: None,
+            structs: self.structs,
+        }
+    }
+}
+
+impl Artifacts {
+    /// Load the artifacts of every contract found in the directories. Debug files and build info of Hardhat are
+    /// skipped.
+    pub fn load<P: AsRef<Path>>(directories: &[P]) -> Result<Self, ArtifactError> {
+        let mut artifacts = Self::default();
+        for directory in directories {
+            let directory = directory.as_ref();
+            if !directory.is_dir() {
+                return Err(ArtifactError::DirectoryNotFound(directory.to_path_buf()));
+            }
+            artifacts.load_directory(directory)?;
+        }
+        Ok(artifacts)
+    }
+
+    fn load_directory(&mut self, directory: &Path) -> Result<(), ArtifactError> {
+        let io_error = |source| ArtifactError::IoError {
+            path: directory.to_path_buf(),
+            source,
+        };
+
+        let mut paths = std::fs::read_dir(directory)
+            .map_err(io_error)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<PathBuf>, std::io::Error>>()
+            .map_err(io_error)?;
+        paths.sort();
+
+        for path in paths {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if path.is_dir() {
+                if file_name != "build-info" {
+                    self.load_directory(&path)?;
+                }
+            } else if file_name.ends_with(".json") && !file_name.ends_with(".dbg.json") {
+                if let Some(artifact) = Artifact::load(&path)? {
+                    self.contracts
+                        .entry(artifact.name.clone())
+                        .or_default()
+                        .push(artifact);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the artifact of a contract by its name, or by its fully qualified name `<source file>:<contract name>` if
+    /// several source files declare contracts with the same name
+    pub fn get(&self, name: &str) -> Result<&Artifact, ArtifactError> {
+        let (source, contract_name) = match name.rsplit_once(':') {
+            Some((source, contract_name)) => (Some(source), contract_name),
+            None => (None, name),
+        };
+
+        let candidates = self
+            .contracts
+            .get(contract_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|artifact| match source {
+                Some(source) => {
+                    artifact.source == source || artifact.source.ends_with(&format!("/{}", source))
+                }
+                None => true,
+            })
+            .collect::<Vec<&Artifact>>();
+
+        match candidates.as_slice() {
+            [artifact] => Ok(artifact),
+            [] => Err(ArtifactError::ArtifactNotFound(name.to_string())),
+            _ => Err(ArtifactError::AmbiguousArtifact {
+                name: name.to_string(),
+                qualified_names: candidates.iter().map(|a| a.qualified_name()).collect(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ArtifactError {
+    #[error("artifacts directory {:#?} is not found", .0)]
+    DirectoryNotFound(PathBuf),
+    #[error("cannot read artifact {path:#?}: {source}")]
+    IoError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid artifact {path:#?}: {source}")]
+    InvalidArtifact {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("artifact of contract `{}` is not found", .0)]
+    ArtifactNotFound(String),
+    #[error(
+        "several contracts are named `{name}`, use one of the qualified names {qualified_names:?}"
+    )]
+    AmbiguousArtifact {
+        name: String,
+        qualified_names: Vec<String>,
+    },
+    #[error("cannot load the artifact of contract `{contract}`: {source}")]
+    ContractArtifactError {
+        contract: String,
+        source: Box<ArtifactError>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_matches, assert_none, assert_some};
+
+    const ABI: &str = r#"[{"type":"function","name":"owner","inputs":[],"outputs":[{"name":"","type":"address"}],"stateMutability":"view"}]"#;
+
+    fn write_file(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn can_load_foundry_and_hardhat_artifacts() {
+        let root = std::env::temp_dir().join(format!("tudo-artifacts-{}", std::process::id()));
+
+        // Foundry
+        write_file(
+            &root.join("out/Factory.sol/Factory.json"),
+            &format!(r#"{{"abi":{},"bytecode":{{"object":"0x6080"}}}}"#, ABI),
+        );
+        write_file(
+            &root.join("out/IOwnable.sol/IOwnable.json"),
+            &format!(r#"{{"abi":{},"bytecode":{{"object":"0x"}}}}"#, ABI),
+        );
+        write_file(&root.join("out/build-info/abc.json"), r#"{"id":"abc"}"#);
+        // Hardhat
+        write_file(
+            &root.join("artifacts/contracts/Pool.sol/Pool.json"),
+            &format!(
+                r#"{{"contractName":"Pool","sourceName":"contracts/Pool.sol","abi":{},"bytecode":"0x6081"}}"#,
+                ABI
+            ),
+        );
+        write_file(
+            &root.join("artifacts/contracts/Pool.sol/Pool.dbg.json"),
+            r#"{"buildInfo":"../../build-info/abc.json"}"#,
+        );
+        write_file(
+            &root.join("artifacts/contracts/test/Pool.sol/Pool.json"),
+            &format!(
+                r#"{{"contractName":"Pool","sourceName":"contracts/test/Pool.sol","abi":{},"bytecode":"0x"}}"#,
+                ABI
+            ),
+        );
+
+        let artifacts = Artifacts::load(&[root.join("out"), root.join("artifacts")]).unwrap();
+
+        let factory = artifacts.get("Factory").unwrap();
+        assert_eq!(factory.qualified_name(), "Factory.sol:Factory");
+        assert_eq!(
+            factory.contract.bytecode,
+            Some("0x6080".parse::<Bytes>().unwrap())
+        );
+        assert_some!(factory.contract.abi.functions_by_name("owner").ok());
+        assert_none!(&artifacts.get("IOwnable").unwrap().contract.bytecode);
+
+        assert_matches!(
+            artifacts.get("Pool"),
+            Err(ArtifactError::AmbiguousArtifact { .. })
+        );
+        let pool = artifacts.get("contracts/Pool.sol:Pool").unwrap();
+        assert_eq!(
+            pool.contract.bytecode,
+            Some("0x6081".parse::<Bytes>().unwrap())
+        );
+        assert_eq!(
+            artifacts.get("test/Pool.sol:Pool").unwrap().source,
+            "contracts/test/Pool.sol"
+        );
+        assert_matches!(
+            artifacts.get("Router"),
+            Err(ArtifactError::ArtifactNotFound(_))
+        );
+        assert_matches!(
+            Artifacts::load(&[root.join("build")]),
+            Err(ArtifactError::DirectoryNotFound(_))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+use alloy_primitives::Address;
+use ethers::{
+    abi::{Abi, AbiParser, Function, Param, ParamType},
+    types::Bytes,
+};
+use serde::Deserialize;
+use serde_value::Value;
+use thiserror::Error;
+
+use crate::{
+    playbook::PlaybookDir,
+    types::{SolStructError, SolStructs},
+};
+
+use super::{Artifact, ArtifactError, Artifacts, Variable};
+
+/// ContractConfig describes a contract by its ABI, so steps can call its functions by name and the argument and
+/// return types are inferred from the ABI.
+///
+/// The ABI is either the path of a JSON ABI or artifact file, a list of human-readable signatures like
+/// `function getPool(address,address,uint24) view returns (address)`, or the ABI of a contract `artifact` found in
+/// the artifacts directories of the setup.
+#[derive(Debug, Clone)]
+pub struct ContractConfig {
+    /// Address of the deployed contract, steps can override it with `contract_address` or `to`
+    pub address: Option<Address>,
+    pub abi: Abi,
+    /// Creation bytecode, either written in hex string or loaded from an artifact
+    pub bytecode: Option<Bytes>,
+    /// Name of the contract artifact, which the ABI and the bytecode are loaded from
+    pub artifact: Option<String>,
+    /// Structs used by the ABI, e.g. `ExactInputSingleParams` of the Uniswap router
+    pub structs: SolStructs,
+}
+
+impl ContractConfig {
+    /// Find the function called with `argument_count` arguments. The function is either a name, or a full signature
+    /// like `safeTransferFrom(address,address,uint256)` to pick one of the overloaded functions.
+    pub fn function(
+        &self,
+        function: &str,
+        argument_count: usize,
+    ) -> Result<&Function, ContractConfigError> {
+        if function.contains('(') {
+            let function_signature: String = function.split_whitespace().collect();
+            return self
+                .abi
+                .functions()
+                .find(|abi_function| Self::signature(abi_function) == function_signature)
+                .ok_or_else(|| ContractConfigError::FunctionNotFound(function.to_string()));
+        }
+
+        let overloads = self
+            .abi
+            .functions_by_name(function)
+            .map_err(|_| ContractConfigError::FunctionNotFound(function.to_string()))?;
+        match overloads.as_slice() {
+            [abi_function] => Ok(abi_function),
+            _ => {
+                let mut matching_overloads = overloads
+                    .iter()
+                    .filter(|abi_function| abi_function.inputs.len() == argument_count);
+                match (matching_overloads.next(), matching_overloads.next()) {
+                    (Some(abi_function), None) => Ok(abi_function),
+                    _ => Err(ContractConfigError::AmbiguousFunction {
+                        function: function.to_string(),
+                        signatures: overloads.iter().map(Self::signature).collect(),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Return the canonical signature of the function, e.g. `getPool(address,address,uint24)`
+    pub fn signature(function: &Function) -> String {
+        let input_types = function
+            .inputs
+            .iter()
+            .map(|input| input.kind.to_string())
+            .collect::<Vec<String>>();
+        format!("{}({})", function.name, input_types.join(","))
+    }
+
+    /// Load the ABI and the bytecode of the contract artifact, if the contract is declared by its artifact name
+    pub fn link(&mut self, artifacts: &Artifacts) -> Result<(), ArtifactError> {
+        if let Some(artifact) = &self.artifact {
+            let artifact = artifacts.get(artifact)?;
+            self.abi = artifact.contract.abi.clone();
+            self.bytecode = artifact.contract.bytecode.clone();
+            self.structs = artifact.contract.structs.clone();
+        }
+        Ok(())
+    }
+
+    fn load_abi(abi: Value) -> Result<(Abi, Option<Bytes>, SolStructs), ContractConfigError> {
+        match abi {
+            Value::String(_) => {
+                let path = Variable::deserialize(abi)
+                    .map_err(|err| ContractConfigError::InvalidAbi(err.to_string()))?;
+                let content = std::fs::read_to_string(PlaybookDir::resolve(path.as_ref()))
+                    .map_err(|source| ContractConfigError::ReadAbiFileError {
+                        path: path.as_ref().to_string(),
+                        source,
+                    })?;
+                let parse_error = |source| ContractConfigError::ParseAbiFileError {
+                    path: path.as_ref().to_string(),
+                    source,
+                };
+                match Artifact::parse(&content).map_err(parse_error)? {
+                    Some(artifact_json) => {
+                        let contract = artifact_json.into_contract();
+                        Ok((contract.abi, contract.bytecode, contract.structs))
+                    }
+                    None => {
+                        let value: serde_json::Value =
+                            serde_json::from_str(&content).map_err(parse_error)?;
+                        let structs = SolStructs::from_json_abi(&value);
+                        Ok((
+                            serde_json::from_value(value).map_err(parse_error)?,
+                            None,
+                            structs,
+                        ))
+                    }
+                }
+            }
+            Value::Seq(_) => {
+                let signatures = Vec::<String>::deserialize(abi)
+                    .map_err(|err| ContractConfigError::InvalidAbi(err.to_string()))?;
+                let signatures = signatures.iter().map(String::as_str).collect::<Vec<&str>>();
+                let mut parser = AbiParser::default();
+                let mut abi = parser
+                    .parse(&signatures)
+                    .map_err(|err| ContractConfigError::InvalidAbi(err.to_string()))?;
+                let structs = SolStructs::from_declarations(
+                    &signatures
+                        .iter()
+                        .filter(|signature| signature.trim_start().starts_with("struct"))
+                        .collect::<Vec<_>>(),
+                )?;
+                Self::name_struct_params(&parser, &mut abi);
+                Ok((abi, None, structs))
+            }
+            _ => Err(ContractConfigError::InvalidAbi(
+                "expected the path of a JSON ABI file or a list of human-readable signatures"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Set the internal type of the struct parameters of a human-readable ABI, which the parser only records by name
+    fn name_struct_params(parser: &AbiParser, abi: &mut Abi) {
+        let name_input = |function: &str, input: &mut Param| {
+            if let Some(struct_name) = parser
+                .function_params
+                .get(&(function.to_string(), input.name.clone()))
+            {
+                input.internal_type = Some(SolStructs::internal_type(struct_name, &input.kind));
+            }
+        };
+
+        if let Some(constructor) = &mut abi.constructor {
+            for input in &mut constructor.inputs {
+                name_input("constructor", input);
+            }
+        }
+        for function in abi.functions.values_mut().flatten() {
+            for input in &mut function.inputs {
+                name_input(&function.name, input);
+            }
+
+            fn is_tuple(kind: &ParamType) -> bool {
+                match kind {
+                    ParamType::Tuple(_) => true,
+                    ParamType::Array(kind) | ParamType::FixedArray(kind, _) => is_tuple(kind),
+                    _ => false,
+                }
+            }
+            let mut output_structs = parser.outputs.get(&function.name).into_iter().flatten();
+            for output in function
+                .outputs
+                .iter_mut()
+                .filter(|output| is_tuple(&output.kind))
+            {
+                if let Some(struct_name) = output_structs.next() {
+                    output.internal_type =
+                        Some(SolStructs::internal_type(struct_name, &output.kind));
+                }
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Debug, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct ContractConfigHelper {
+            address: Option<Address>,
+            abi: Option<Value>,
+            bytecode: Option<Bytes>,
+            artifact: Option<String>,
+        }
+
+        let helper = ContractConfigHelper::deserialize(deserializer)?;
+        let (abi, bytecode, structs) = match (helper.abi, &helper.artifact) {
+            (Some(abi), None) => {
+                let (abi, bytecode, structs) =
+                    Self::load_abi(abi).map_err(serde::de::Error::custom)?;
+                (abi, helper.bytecode.or(bytecode), structs)
+            }
+            (None, Some(_)) if helper.bytecode.is_some() => {
+                return Err(serde::de::Error::custom(
+                    "`bytecode` cannot be used with `artifact`, which gives the bytecode",
+                ))
+            }
+            // the ABI is loaded when the setup links its contracts with the artifacts
+            (None, Some(_)) => (Abi::default(), None, SolStructs::default()),
+            (Some(_), Some(_)) => {
+                return Err(serde::de::Error::custom(
+                    "`abi` and `artifact` cannot be used together",
+                ))
+            }
+            (None, None) => {
+                return Err(serde::de::Error::custom(
+                    "missing field `abi` or `artifact`",
+                ))
+            }
+        };
+
+        Ok(Self {
+            address: helper.address,
+            abi,
+            bytecode,
+            artifact: helper.artifact,
+            structs,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ContractConfigError {
+    #[error("invalid contract ABI: {}", .0)]
+    InvalidAbi(String),
+    #[error("cannot read ABI file {path:#?}: {source}")]
+    ReadAbiFileError {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("cannot parse ABI file {path:#?}: {source}")]
+    ParseAbiFileError {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error("function `{}` is not found in the contract ABI", .0)]
+    FunctionNotFound(String),
+    #[error(
+        "function `{function}` is overloaded, call it by one of the signatures {signatures:?}"
+    )]
+    AmbiguousFunction {
+        function: String,
+        signatures: Vec<String>,
+    },
+    #[error(transparent)]
+    SolStructError(#[from] SolStructError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_matches, assert_ok};
+
+    #[test]
+    fn can_deserialize_contract_with_human_readable_abi() {
+        let yaml = r#"
+            address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+            abi:
+                - function getPool(address,address,uint24) view returns (address)
+                - function safeTransferFrom(address,address,uint256)
+                - function safeTransferFrom(address,address,uint256,bytes)
+        "#;
+
+        let contract: ContractConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(contract.address.is_some());
+
+        let get_pool = contract.function("getPool", 3).unwrap();
+        assert_eq!(
+            ContractConfig::signature(get_pool),
+            "getPool(address,address,uint24)"
+        );
+
+        // overloaded functions are picked by the number of arguments or by their signature
+        assert_eq!(
+            contract
+                .function("safeTransferFrom", 4)
+                .unwrap()
+                .inputs
+                .len(),
+            4
+        );
+        assert_ok!(contract.function("safeTransferFrom(address, address, uint256)", 4));
+        assert_matches!(
+            contract.function("safeTransferFrom", 2),
+            Err(ContractConfigError::AmbiguousFunction { .. })
+        );
+        assert_matches!(
+            contract.function("setOwner", 1),
+            Err(ContractConfigError::FunctionNotFound(_))
+        );
+    }
+
+    #[test]
+    fn can_deserialize_contract_with_abi_file() {
+        let abi_path = std::env::temp_dir().join(format!("tudo-abi-{}.json", std::process::id()));
+        std::fs::write(
+            &abi_path,
+            r#"[{"type":"function","name":"owner","inputs":[],"outputs":[{"name":"","type":"address"}],"stateMutability":"view"}]"#,
+        )
+        .unwrap();
+
+        let yaml = format!("abi: {}", abi_path.display());
+        let contract: ContractConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert!(contract.address.is_none());
+        assert_eq!(contract.function("owner", 0).unwrap().outputs.len(), 1);
+
+        // relative paths are resolved against the directory of the playbook file
+        let yaml = format!("abi: {}", abi_path.file_name().unwrap().to_str().unwrap());
+        let playbook_file = abi_path.with_file_name("playbook.yaml");
+        let contract: ContractConfig =
+            PlaybookDir::scope(Some(&playbook_file), || serde_yaml::from_str(&yaml)).unwrap();
+        assert_eq!(contract.function("owner", 0).unwrap().outputs.len(), 1);
+
+        std::fs::remove_file(&abi_path).unwrap();
+        assert!(serde_yaml::from_str::<ContractConfig>(&yaml).is_err());
+    }
+}
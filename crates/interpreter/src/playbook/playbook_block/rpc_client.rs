@@ -0,0 +1,221 @@
+use std::{fmt::Debug, time::Duration};
+
+use async_trait::async_trait;
+use ethers::{
+    providers::{Http, Ipc, JsonRpcClient, ProviderError, RpcError, Ws},
+    types::{Bytes, TxHash},
+    utils::keccak256,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use shared::is_already_known;
+
+/// Methods which broadcast a transaction, a member may broadcast it even if it fails to respond
+const SEND_METHODS: [&str; 2] = ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// RpcClient is the transport of a single RPC endpoint, used as a member of Quorum and Fallback providers
+#[derive(Debug, Clone)]
+pub enum RpcClient {
+    Http(Http),
+    Websocket(Ws),
+    Ipc(Ipc),
+}
+
+#[async_trait]
+impl JsonRpcClient for RpcClient {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            RpcClient::Http(client) => Ok(client.request(method, params).await?),
+            RpcClient::Websocket(client) => Ok(client.request(method, params).await?),
+            RpcClient::Ipc(client) => Ok(client.request(method, params).await?),
+        }
+    }
+}
+
+/// FallbackProvider sends requests to its members in order, and fails over to the next member when a member returns
+/// a transport error or does not respond in time. A transaction which a failed member may have broadcast is sent if
+/// the next member already knows it, and its rejection by the next member does not tell it is not sent.
+#[derive(Debug, Clone)]
+pub struct FallbackProvider {
+    providers: Vec<RpcClient>,
+    timeout: Duration,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<RpcClient>, timeout: Duration) -> Self {
+        Self { providers, timeout }
+    }
+
+    /// Hash of the raw transaction broadcast by `eth_sendRawTransaction`
+    fn raw_tx_hash<T, R>(params: &T) -> Option<R>
+    where
+        T: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params).ok()?;
+        let raw_tx: Bytes = serde_json::from_value(params.get(0)?.clone()).ok()?;
+        let tx_hash = TxHash::from(keccak256(raw_tx));
+        serde_json::from_value(serde_json::to_value(tx_hash).ok()?).ok()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FallbackProvider {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let mut errors = Vec::with_capacity(self.providers.len());
+        let is_send = SEND_METHODS.contains(&method);
+        // whether a member which failed may have broadcast the transaction
+        let mut may_be_sent = false;
+
+        for provider in &self.providers {
+            match tokio::time::timeout(self.timeout, provider.request(method, &params)).await {
+                Ok(Ok(response)) => return Ok(response),
+                Ok(Err(err)) => match err.as_error_response() {
+                    Some(error_response) if may_be_sent => {
+                        if method == SEND_METHODS[0] && is_already_known(error_response) {
+                            if let Some(tx_hash) = Self::raw_tx_hash(&params) {
+                                return Ok(tx_hash);
+                            }
+                        }
+                        errors.push(err.to_string());
+                        return Err(ProviderError::CustomError(format!(
+                            "the transaction may be sent by a fallback provider which failed: [{}]",
+                            errors.join(", ")
+                        )));
+                    }
+                    // a JSON-RPC error response (e.g. execution reverted) would be the same on every member
+                    Some(_) => return Err(err),
+                    None => {
+                        errors.push(err.to_string());
+                        may_be_sent |= is_send;
+                    }
+                },
+                Err(_) => {
+                    errors.push(format!("request timed out after {:?}", self.timeout));
+                    may_be_sent |= is_send;
+                }
+            }
+        }
+
+        Err(ProviderError::CustomError(format!(
+            "all fallback providers failed: [{}]",
+            errors.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+    use ethers::providers::{Middleware, Provider};
+    use std::str::FromStr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve JSON-RPC requests over HTTP, answering every request with the body
+    async fn spawn_rpc_member(body: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut connection, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match connection.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = connection.write_all(response.as_bytes()).await;
+            }
+        });
+        rpc_url
+    }
+
+    #[tokio::test]
+    async fn should_fail_over_to_next_provider() {
+        // the second member answers every request with the chain id of Anvil
+        let rpc_url = spawn_rpc_member(r#"{"jsonrpc":"2.0","id":0,"result":"0x7a69"}"#).await;
+
+        let provider = Provider::new(FallbackProvider::new(
+            vec![
+                RpcClient::Http(Http::from_str("http://localhost:1").unwrap()),
+                RpcClient::Http(Http::from_str(&rpc_url).unwrap()),
+            ],
+            Duration::from_secs(5),
+        ));
+
+        assert_eq!(provider.get_chainid().await.unwrap(), 31337.into());
+    }
+
+    #[tokio::test]
+    async fn should_return_error_when_all_providers_failed() {
+        let provider = Provider::new(FallbackProvider::new(
+            vec![
+                RpcClient::Http(Http::from_str("http://localhost:1").unwrap()),
+                RpcClient::Http(Http::from_str("http://localhost:2").unwrap()),
+            ],
+            Duration::from_secs(5),
+        ));
+
+        assert_err!(provider.get_chainid().await);
+    }
+
+    #[tokio::test]
+    async fn can_fail_over_transaction_already_sent() {
+        // the first member accepts connections but never responds, it may have broadcast the transaction
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let silent_rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+        let fallback_provider = |rpc_url: &str| {
+            Provider::new(FallbackProvider::new(
+                vec![
+                    RpcClient::Http(Http::from_str(&silent_rpc_url).unwrap()),
+                    RpcClient::Http(Http::from_str(rpc_url).unwrap()),
+                ],
+                Duration::from_millis(100),
+            ))
+        };
+        let raw_tx = Bytes::from_static(&[1, 2, 3]);
+
+        let rpc_url = spawn_rpc_member(
+            r#"{"jsonrpc":"2.0","id":0,"error":{"code":-32000,"message":"already known"}}"#,
+        )
+        .await;
+        let provider = fallback_provider(&rpc_url);
+        let pending_tx = provider.send_raw_transaction(raw_tx.clone()).await.unwrap();
+        assert_eq!(*pending_tx, TxHash::from(keccak256(&raw_tx)));
+
+        // the rejection of the next member does not tell the transaction is not sent
+        let rpc_url = spawn_rpc_member(
+            r#"{"jsonrpc":"2.0","id":0,"error":{"code":-32000,"message":"nonce too low"}}"#,
+        )
+        .await;
+        let err = fallback_provider(&rpc_url)
+            .send_raw_transaction(raw_tx)
+            .await
+            .unwrap_err();
+        assert!(err.as_error_response().is_none());
+    }
+}
@@ -1,9 +1,341 @@
-#[allow(unused)]
-use ethers::providers::{Authorization, Http, Ipc, Provider, QuorumProvider, Ws};
-use serde::Deserialize;
+use std::{fmt::Display, time::Duration};
+
+use ethers::{
+    providers::{
+        Authorization, ConnectionDetails, Http, Ipc, Middleware, Provider, ProviderError, Quorum,
+        QuorumProvider, WeightedProvider, Ws,
+    },
+    types::{Bytes, TransactionReceipt, TxHash, U256},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use url::Url;
 
-/// RpcProvider supports deserialization from yaml file to construct ethers-rs provider type.
+use crate::{playbook::Setup, types::VariableName};
+
+use super::{FallbackProvider, RpcClient};
+
+/// RpcProviderConfig is a plain, serializable description of an RPC provider. Parsing it never touches the network,
+/// the provider is only connected when [`RpcProviderConfig::connect`] is called.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(try_from = "RpcProviderConfigHelper")]
+pub struct RpcProviderConfig {
+    pub provider_type: RpcProviderTypes,
+    /// Endpoint url, not used by Quorum and Fallback providers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_rpc_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bearer: Option<String>,
+    /// Weight of the provider as a member of a Quorum provider, default to 1
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u64>,
+    /// Members of a Quorum or Fallback provider
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub providers: Vec<RpcProviderConfig>,
+    /// Quorum threshold of a Quorum provider, default to majority of the members weight
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumThreshold>,
+    /// Request timeout in seconds of each member of a Fallback provider
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcProviderConfigHelper {
+    #[serde(default)]
+    pub provider_type: RpcProviderTypes,
+    pub chain_rpc_url: Option<String>,
+    #[serde(alias = "chain_rpc_username")]
+    pub username: Option<String>,
+    #[serde(alias = "chain_rpc_password")]
+    pub password: Option<String>,
+    #[serde(alias = "chain_rpc_bearer")]
+    pub bearer: Option<String>,
+    pub weight: Option<u64>,
+    #[serde(default)]
+    pub providers: Vec<RpcProviderConfig>,
+    pub quorum: Option<QuorumThreshold>,
+    pub timeout: Option<u64>,
+}
+
+impl TryFrom<RpcProviderConfigHelper> for RpcProviderConfig {
+    type Error = RpcProviderError;
+
+    fn try_from(helper: RpcProviderConfigHelper) -> Result<Self, Self::Error> {
+        match helper.provider_type {
+            RpcProviderTypes::Http
+            | RpcProviderTypes::HttpWithBasicAuth
+            | RpcProviderTypes::HttpWithBearerAuth
+            | RpcProviderTypes::Websocket
+            | RpcProviderTypes::WebsocketWithBasicAuth
+            | RpcProviderTypes::WebsocketWithBearerAuth => {
+                let chain_rpc_url = helper
+                    .chain_rpc_url
+                    .as_ref()
+                    .ok_or(RpcProviderError::MissingField("chain_rpc_url"))?;
+                Url::parse(chain_rpc_url)?;
+            }
+            RpcProviderTypes::Ipc => {
+                if helper.chain_rpc_url.is_none() {
+                    return Err(RpcProviderError::MissingField("chain_rpc_url"));
+                }
+            }
+            RpcProviderTypes::Quorum | RpcProviderTypes::Fallback => {
+                if helper.providers.is_empty() {
+                    return Err(RpcProviderError::MissingField("providers"));
+                }
+                if helper.providers.iter().any(|member| member.is_group()) {
+                    return Err(RpcProviderError::InvalidRpcProvider(format!(
+                        "members of a {} provider must be single endpoints",
+                        helper.provider_type
+                    )));
+                }
+            }
+        }
+
+        match helper.provider_type {
+            RpcProviderTypes::HttpWithBasicAuth | RpcProviderTypes::WebsocketWithBasicAuth => {
+                if helper.username.is_none() {
+                    return Err(RpcProviderError::MissingField("username"));
+                }
+                if helper.password.is_none() {
+                    return Err(RpcProviderError::MissingField("password"));
+                }
+            }
+            RpcProviderTypes::HttpWithBearerAuth | RpcProviderTypes::WebsocketWithBearerAuth
+                if helper.bearer.is_none() =>
+            {
+                return Err(RpcProviderError::MissingField("bearer"));
+            }
+            _ => {}
+        }
+
+        if helper.weight == Some(0) {
+            return Err(RpcProviderError::InvalidRpcProvider(
+                "`weight` must be greater than 0".to_string(),
+            ));
+        }
+        if matches!(helper.quorum, Some(QuorumThreshold::Percentage(percentage)) if percentage > 100)
+        {
+            return Err(RpcProviderError::InvalidRpcProvider(
+                "quorum percentage must not be greater than 100".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            provider_type: helper.provider_type,
+            chain_rpc_url: helper.chain_rpc_url,
+            username: helper.username,
+            password: helper.password,
+            bearer: helper.bearer,
+            weight: helper.weight,
+            providers: helper.providers,
+            quorum: helper.quorum,
+            timeout: helper.timeout,
+        })
+    }
+}
+
+impl RpcProviderConfig {
+    /// Default request timeout of each member of a Fallback provider
+    pub const DEFAULT_FALLBACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Connect to the RPC provider. Http providers are built without any network request, Websocket and Ipc
+    /// providers open their connection here.
+    pub async fn connect(&self) -> Result<RpcProvider, RpcProviderError> {
+        match self.provider_type {
+            RpcProviderTypes::Http => Ok(RpcProvider::Http(Provider::new(self.http_client()?))),
+            RpcProviderTypes::HttpWithBasicAuth => Ok(RpcProvider::HttpWithBasicAuth(
+                Provider::new(self.http_client()?),
+            )),
+            RpcProviderTypes::HttpWithBearerAuth => Ok(RpcProvider::HttpWithBearerAuth(
+                Provider::new(self.http_client()?),
+            )),
+            RpcProviderTypes::Websocket => Ok(RpcProvider::Websocket(Provider::new(
+                self.ws_client().await?,
+            ))),
+            RpcProviderTypes::WebsocketWithBasicAuth => Ok(RpcProvider::WebsocketWithBasicAuth(
+                Provider::new(self.ws_client().await?),
+            )),
+            RpcProviderTypes::WebsocketWithBearerAuth => Ok(RpcProvider::WebsocketWithBearerAuth(
+                Provider::new(self.ws_client().await?),
+            )),
+            RpcProviderTypes::Ipc => Ok(RpcProvider::Ipc(Provider::new(self.ipc_client().await?))),
+            RpcProviderTypes::Quorum => {
+                let mut members = Vec::with_capacity(self.providers.len());
+                for member in &self.providers {
+                    members.push(WeightedProvider::with_weight(
+                        member.connect_client().await?,
+                        member.weight.unwrap_or(1),
+                    ));
+                }
+
+                let quorum = self.quorum.unwrap_or_default().into();
+                Ok(RpcProvider::Quorum(Provider::new(QuorumProvider::new(
+                    quorum, members,
+                ))))
+            }
+            RpcProviderTypes::Fallback => {
+                let mut members = Vec::with_capacity(self.providers.len());
+                for member in &self.providers {
+                    members.push(member.connect_client().await?);
+                }
+
+                let timeout = self
+                    .timeout
+                    .map(Duration::from_secs)
+                    .unwrap_or(Self::DEFAULT_FALLBACK_TIMEOUT);
+                Ok(RpcProvider::Fallback(Provider::new(FallbackProvider::new(
+                    members, timeout,
+                ))))
+            }
+        }
+    }
+
+    /// Connect to a single endpoint, used for members of Quorum and Fallback providers
+    async fn connect_client(&self) -> Result<RpcClient, RpcProviderError> {
+        match self.provider_type {
+            RpcProviderTypes::Http
+            | RpcProviderTypes::HttpWithBasicAuth
+            | RpcProviderTypes::HttpWithBearerAuth => Ok(RpcClient::Http(self.http_client()?)),
+            RpcProviderTypes::Websocket
+            | RpcProviderTypes::WebsocketWithBasicAuth
+            | RpcProviderTypes::WebsocketWithBearerAuth => {
+                Ok(RpcClient::Websocket(self.ws_client().await?))
+            }
+            RpcProviderTypes::Ipc => Ok(RpcClient::Ipc(self.ipc_client().await?)),
+            RpcProviderTypes::Quorum | RpcProviderTypes::Fallback => {
+                Err(RpcProviderError::InvalidRpcProvider(format!(
+                    "{} provider is not a single endpoint",
+                    self.provider_type
+                )))
+            }
+        }
+    }
+
+    /// Whether the provider groups other providers
+    fn is_group(&self) -> bool {
+        matches!(
+            self.provider_type,
+            RpcProviderTypes::Quorum | RpcProviderTypes::Fallback
+        )
+    }
+
+    fn chain_rpc_url(&self) -> Result<&str, RpcProviderError> {
+        self.chain_rpc_url
+            .as_deref()
+            .ok_or(RpcProviderError::MissingField("chain_rpc_url"))
+    }
+
+    fn http_client(&self) -> Result<Http, RpcProviderError> {
+        let url = Url::parse(self.chain_rpc_url()?)?;
+        match self.authorization()? {
+            Some(authorization) => Http::new_with_auth(url, authorization)
+                .map_err(|err| RpcProviderError::HttpClientError(err.to_string())),
+            None => Ok(Http::new(url)),
+        }
+    }
+
+    async fn ws_client(&self) -> Result<Ws, RpcProviderError> {
+        let connection_details =
+            ConnectionDetails::new(self.chain_rpc_url()?, self.authorization()?);
+        let client = Ws::connect(connection_details)
+            .await
+            .map_err(ProviderError::from)?;
+        Ok(client)
+    }
+
+    async fn ipc_client(&self) -> Result<Ipc, RpcProviderError> {
+        let client = Ipc::connect(self.chain_rpc_url()?)
+            .await
+            .map_err(ProviderError::from)?;
+        Ok(client)
+    }
+
+    fn authorization(&self) -> Result<Option<Authorization>, RpcProviderError> {
+        match self.provider_type {
+            RpcProviderTypes::HttpWithBasicAuth | RpcProviderTypes::WebsocketWithBasicAuth => {
+                let username = self
+                    .username
+                    .as_ref()
+                    .ok_or(RpcProviderError::MissingField("username"))?;
+                let password = self
+                    .password
+                    .as_ref()
+                    .ok_or(RpcProviderError::MissingField("password"))?;
+                Ok(Some(Authorization::basic(username, password)))
+            }
+            RpcProviderTypes::HttpWithBearerAuth | RpcProviderTypes::WebsocketWithBearerAuth => {
+                let bearer = self
+                    .bearer
+                    .as_ref()
+                    .ok_or(RpcProviderError::MissingField("bearer"))?;
+                Ok(Some(Authorization::bearer(bearer)))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Display the provider type and host only, the full url could contain an API key
+impl Display for RpcProviderConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_group() {
+            let members = self
+                .providers
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>();
+            return write!(
+                f,
+                "{} provider [{}]",
+                self.provider_type,
+                members.join(", ")
+            );
+        }
+
+        let chain_rpc_url = self.chain_rpc_url.as_deref().unwrap_or_default();
+        let host = Url::parse(chain_rpc_url)
+            .ok()
+            .and_then(|url| url.host_str().map(ToOwned::to_owned))
+            .unwrap_or_else(|| chain_rpc_url.to_string());
+        write!(f, "{} provider {}", self.provider_type, host)
+    }
+}
+
+/// Quorum threshold of a Quorum provider, a response is returned once members with enough weight agree on it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq, Hash)]
+pub enum QuorumThreshold {
+    /// All members must agree
+    All,
+    /// More than half of the members weight must agree
+    #[default]
+    Majority,
+    /// A percentage of the members weight must agree
+    Percentage(u8),
+    /// A number of members must agree, regardless of their weight
+    ProviderCount(usize),
+    /// A total weight of members must agree
+    Weight(u64),
+}
+
+impl From<QuorumThreshold> for Quorum {
+    fn from(threshold: QuorumThreshold) -> Self {
+        match threshold {
+            QuorumThreshold::All => Quorum::All,
+            QuorumThreshold::Majority => Quorum::Majority,
+            QuorumThreshold::Percentage(percentage) => Quorum::Percentage(percentage),
+            QuorumThreshold::ProviderCount(count) => Quorum::ProviderCount(count),
+            QuorumThreshold::Weight(weight) => Quorum::Weight(weight),
+        }
+    }
+}
+
+/// RpcProvider is a connected ethers-rs provider, built from a [`RpcProviderConfig`].
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub enum RpcProvider {
@@ -14,115 +346,158 @@ pub enum RpcProvider {
     WebsocketWithBasicAuth(Provider<Ws>),
     WebsocketWithBearerAuth(Provider<Ws>),
     Ipc(Provider<Ipc>),
-    // Quorum(QuorumProvider),
+    Quorum(Provider<QuorumProvider<RpcClient>>),
+    Fallback(Provider<FallbackProvider>),
 }
 
-impl<'de> Deserialize<'de> for RpcProvider {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Debug, Deserialize)]
-        struct RpcProviderHelper {
-            #[serde(default)]
-            pub provider_type: RpcProviderTypes,
-            pub chain_rpc_url: String,
-            #[serde(alias = "chain_rpc_username")]
-            pub username: Option<String>,
-            #[serde(alias = "chain_rpc_password")]
-            pub password: Option<String>,
-            #[serde(alias = "chain_rpc_bearer")]
-            pub bearer: Option<String>,
+impl RpcProvider {
+    /// Get the chain id of the connected network
+    pub async fn get_chainid(&self) -> Result<U256, ProviderError> {
+        match self {
+            RpcProvider::Http(provider)
+            | RpcProvider::HttpWithBasicAuth(provider)
+            | RpcProvider::HttpWithBearerAuth(provider) => provider.get_chainid().await,
+            RpcProvider::Websocket(provider)
+            | RpcProvider::WebsocketWithBasicAuth(provider)
+            | RpcProvider::WebsocketWithBearerAuth(provider) => provider.get_chainid().await,
+            RpcProvider::Ipc(provider) => provider.get_chainid().await,
+            RpcProvider::Quorum(provider) => provider.get_chainid().await,
+            RpcProvider::Fallback(provider) => provider.get_chainid().await,
         }
+    }
 
-        let helper = RpcProviderHelper::deserialize(deserializer)?;
-        match helper.provider_type {
-            RpcProviderTypes::Http => {
-                let provider = Provider::<Http>::try_from(helper.chain_rpc_url)
-                    .map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::Http(provider))
+    /// Get the receipt of the transaction, none if it is not mined yet
+    pub async fn get_transaction_receipt(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<Option<TransactionReceipt>, ProviderError> {
+        match self {
+            RpcProvider::Http(provider)
+            | RpcProvider::HttpWithBasicAuth(provider)
+            | RpcProvider::HttpWithBearerAuth(provider) => {
+                provider.get_transaction_receipt(tx_hash).await
             }
-            RpcProviderTypes::HttpWithBasicAuth => {
-                let username = helper
-                    .username
-                    .ok_or("missing `username` field")
-                    .map_err(serde::de::Error::custom)?;
-                let password = helper
-                    .password
-                    .ok_or("missing `password` field")
-                    .map_err(serde::de::Error::custom)?;
-
-                let url = Url::parse(&helper.chain_rpc_url).map_err(serde::de::Error::custom)?;
-                let provider = Http::new_with_auth(url, Authorization::basic(username, password))
-                    .map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::HttpWithBasicAuth(Provider::new(provider)))
+            RpcProvider::Websocket(provider)
+            | RpcProvider::WebsocketWithBasicAuth(provider)
+            | RpcProvider::WebsocketWithBearerAuth(provider) => {
+                provider.get_transaction_receipt(tx_hash).await
             }
-            RpcProviderTypes::HttpWithBearerAuth => {
-                let bearer = helper
-                    .bearer
-                    .ok_or("missing `bearer field`")
-                    .map_err(serde::de::Error::custom)?;
-                let url = Url::parse(&helper.chain_rpc_url).map_err(serde::de::Error::custom)?;
-                let provider = Http::new_with_auth(url, Authorization::bearer(bearer))
-                    .map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::HttpWithBearerAuth(Provider::new(provider)))
+            RpcProvider::Ipc(provider) => provider.get_transaction_receipt(tx_hash).await,
+            RpcProvider::Quorum(provider) => provider.get_transaction_receipt(tx_hash).await,
+            RpcProvider::Fallback(provider) => provider.get_transaction_receipt(tx_hash).await,
+        }
+    }
+
+    /// Broadcast the signed transaction, return its hash
+    pub async fn send_raw_transaction(&self, raw_tx: Bytes) -> Result<TxHash, ProviderError> {
+        match self {
+            RpcProvider::Http(provider)
+            | RpcProvider::HttpWithBasicAuth(provider)
+            | RpcProvider::HttpWithBearerAuth(provider) => {
+                Ok(*provider.send_raw_transaction(raw_tx).await?)
             }
-            RpcProviderTypes::Websocket => {
-                let async_conn = Provider::<Ws>::connect(helper.chain_rpc_url);
-                let rt = tokio::runtime::Runtime::new().map_err(serde::de::Error::custom)?;
-                let provider = rt.block_on(async_conn).map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::Websocket(provider))
+            RpcProvider::Websocket(provider)
+            | RpcProvider::WebsocketWithBasicAuth(provider)
+            | RpcProvider::WebsocketWithBearerAuth(provider) => {
+                Ok(*provider.send_raw_transaction(raw_tx).await?)
             }
-            RpcProviderTypes::WebsocketWithBasicAuth => {
-                let username = helper
-                    .username
-                    .ok_or("missing `username` field")
-                    .map_err(serde::de::Error::custom)?;
-                let password = helper
-                    .password
-                    .ok_or("missing `password` field")
-                    .map_err(serde::de::Error::custom)?;
+            RpcProvider::Ipc(provider) => Ok(*provider.send_raw_transaction(raw_tx).await?),
+            RpcProvider::Quorum(provider) => Ok(*provider.send_raw_transaction(raw_tx).await?),
+            RpcProvider::Fallback(provider) => Ok(*provider.send_raw_transaction(raw_tx).await?),
+        }
+    }
+}
 
-                let async_conn = Provider::<Ws>::connect_with_auth(
-                    helper.chain_rpc_url,
-                    Authorization::basic(username, password),
-                );
+/// The RPC provider of a step, which is the name of a provider defined in `setup.rpc_providers` with the
+/// `rpc_provider` field, an inline provider defined with the `chain_rpc_url` field, or both. The inline provider is
+/// used as a fallback when the named provider is not defined in setup.
+#[derive(Debug, Clone)]
+pub struct RpcProviderSource {
+    pub name: Option<VariableName>,
+    pub inline: Option<RpcProviderConfig>,
+}
 
-                let rt = tokio::runtime::Runtime::new().map_err(serde::de::Error::custom)?;
-                let provider = rt.block_on(async_conn).map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::WebsocketWithBasicAuth(provider))
-            }
-            RpcProviderTypes::WebsocketWithBearerAuth => {
-                let bearer = helper
-                    .bearer
-                    .ok_or("missing `bearer field`")
-                    .map_err(serde::de::Error::custom)?;
-                let async_conn = Provider::<Ws>::connect_with_auth(
-                    helper.chain_rpc_url,
-                    Authorization::bearer(bearer),
-                );
-
-                let rt = tokio::runtime::Runtime::new().map_err(serde::de::Error::custom)?;
-                let provider = rt.block_on(async_conn).map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::WebsocketWithBearerAuth(provider))
-            }
-            RpcProviderTypes::Ipc => {
-                let async_conn = Provider::<Ipc>::connect_ipc(helper.chain_rpc_url);
-
-                let rt = tokio::runtime::Runtime::new().map_err(serde::de::Error::custom)?;
-                let provider = rt.block_on(async_conn).map_err(serde::de::Error::custom)?;
-                Ok(RpcProvider::Ipc(provider))
-            } // RpcProviderTypes::Quorum => {
-              //     struct ChainRpcHelper {
-              //         pub providers: Vec<
-              //     }
-              // }
+impl RpcProviderSource {
+    /// Resolve the RPC provider config, looking up named providers in the playbook setup
+    pub fn resolve<'a>(
+        &'a self,
+        setup: Option<&'a Setup>,
+    ) -> Result<&'a RpcProviderConfig, RpcProviderError> {
+        let named_provider = self
+            .name
+            .as_ref()
+            .and_then(|name| setup.and_then(|setup| setup.get_rpc_provider(name.as_str())));
+
+        match (named_provider, &self.inline, &self.name) {
+            (Some(rpc_provider), _, _) => Ok(rpc_provider),
+            (None, Some(rpc_provider), _) => Ok(rpc_provider),
+            (None, None, Some(name)) => Err(RpcProviderError::RpcProviderNotDefined(name.clone())),
+            (None, None, None) => Err(RpcProviderError::MissingRpcProvider),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RpcProviderSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = serde_value::Value::deserialize(deserializer)?;
+
+        let serde_value::Value::Map(mapping) = &value else {
+            return Err(serde::de::Error::custom(
+                "expected a map to represent RPC provider",
+            ));
+        };
+
+        let name = mapping
+            .get(&serde_value::Value::String("rpc_provider".to_string()))
+            .cloned()
+            .map(VariableName::deserialize)
+            .transpose()
+            .map_err(serde::de::Error::custom)?;
+
+        let inline =
+            if mapping.contains_key(&serde_value::Value::String("chain_rpc_url".to_string())) {
+                Some(RpcProviderConfig::deserialize(value).map_err(serde::de::Error::custom)?)
+            } else {
+                None
+            };
+
+        if name.is_none() && inline.is_none() {
+            return Err(serde::de::Error::custom(
+                "missing field `rpc_provider` or `chain_rpc_url`",
+            ));
         }
+
+        Ok(Self { name, inline })
     }
 }
 
+#[derive(Debug, Error)]
+pub enum RpcProviderError {
+    #[error("rpc provider {:#?} is not defined in setup", .0)]
+    RpcProviderNotDefined(VariableName),
+    #[error("missing rpc provider")]
+    MissingRpcProvider,
+    #[error("rpc provider is not connected")]
+    RpcProviderNotConnected,
+    #[error("missing `{0}` field")]
+    MissingField(&'static str),
+    #[error("invalid rpc provider: {0}")]
+    InvalidRpcProvider(String),
+    #[error(transparent)]
+    UrlParseError(#[from] url::ParseError),
+    #[error("cannot build http client: {0}")]
+    HttpClientError(String),
+    #[error(transparent)]
+    ProviderError(#[from] ProviderError),
+}
+
 #[non_exhaustive]
-#[derive(Debug, Deserialize, Default, strum::Display)]
+#[derive(
+    Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq, Hash, strum::Display,
+)]
 pub enum RpcProviderTypes {
     #[default]
     Http,
@@ -132,34 +507,41 @@ pub enum RpcProviderTypes {
     WebsocketWithBasicAuth,
     WebsocketWithBearerAuth,
     Ipc,
-    // Quorum,
+    /// Send requests to all members and return the response once the quorum threshold agrees on it
+    Quorum,
+    /// Send requests to members in order, failing over to the next member on errors or timeouts
+    Fallback,
 }
 
 #[cfg(test)]
 mod tests {
-    use claims::assert_matches;
+    use claims::{assert_err, assert_matches};
     use ethers::utils::Anvil;
 
     use super::*;
 
-    #[test]
-    fn can_parse_default_rpc_provider() {
+    #[tokio::test]
+    async fn can_parse_default_rpc_provider() {
         let yaml = r#"
             chain_rpc_url: "https://eth.llamarpc.com"
         "#;
 
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(rpc_provider_config.provider_type, RpcProviderTypes::Http);
+
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::Http(_));
     }
 
-    #[test]
-    fn can_parse_http_rpc_provider() {
+    #[tokio::test]
+    async fn can_parse_http_rpc_provider() {
         let yaml = r#"
             chain_rpc_url: "https://eth.llamarpc.com"
             provider_type: Http
         "#;
 
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::Http(_));
 
         let yaml = r#"
@@ -169,7 +551,8 @@ mod tests {
             password: test
         "#;
 
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::HttpWithBasicAuth(_));
 
         let yaml = r#"
@@ -178,13 +561,137 @@ mod tests {
             bearer: thisisabearertoken
         "#;
 
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::HttpWithBearerAuth(_));
     }
 
     #[test]
+    fn can_parse_ws_rpc_provider_offline() {
+        // nothing is listening on this port, parsing must not try to connect
+        let yaml = r#"
+            chain_rpc_url: "ws://localhost:1"
+            provider_type: WebsocketWithBearerAuth
+            bearer: thisisabearertoken
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(
+            rpc_provider_config.provider_type,
+            RpcProviderTypes::WebsocketWithBearerAuth
+        );
+    }
+
+    #[test]
+    fn should_return_error_when_parse_invalid_rpc_provider() {
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            provider_type: HttpWithBasicAuth
+            username: test
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+
+        let yaml = r#"
+            chain_rpc_url: "ws://localhost:8545"
+            provider_type: WebsocketWithBearerAuth
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+
+        let yaml = r#"
+            chain_rpc_url: "not a url"
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+    }
+
+    #[test]
+    fn can_serialize_rpc_provider_config() {
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            provider_type: HttpWithBearerAuth
+            bearer: thisisabearertoken
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let serialized = serde_yaml::to_string(&rpc_provider_config).unwrap();
+        let deserialized: RpcProviderConfig = serde_yaml::from_str(&serialized).unwrap();
+        assert_eq!(rpc_provider_config, deserialized);
+    }
+
+    #[test]
+    fn should_not_display_full_url() {
+        let yaml = r#"
+            chain_rpc_url: "https://mainnet.infura.io/v3/thisisasecretapikey"
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            rpc_provider_config.to_string(),
+            "Http provider mainnet.infura.io"
+        );
+    }
+
+    #[test]
+    fn can_parse_rpc_provider_source() {
+        let yaml = r#"
+            rpc_provider: MAINNET
+        "#;
+        let rpc_provider_source: RpcProviderSource = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(rpc_provider_source.name, Some(_));
+        assert_matches!(rpc_provider_source.inline, None);
+
+        let yaml = r#"
+            rpc_provider: MAINNET
+            chain_rpc_url: "https://eth.llamarpc.com"
+        "#;
+        let rpc_provider_source: RpcProviderSource = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(rpc_provider_source.name, Some(_));
+        assert_matches!(rpc_provider_source.inline, Some(_));
+
+        let yaml = r#"
+            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderSource>(yaml));
+    }
+
+    #[test]
+    fn can_resolve_rpc_provider_source() {
+        let setup = r#"
+            rpc_providers:
+                MAINNET:
+                    chain_rpc_url: "https://eth.llamarpc.com"
+                    provider_type: HttpWithBearerAuth
+                    bearer: thisisabearertoken
+        "#;
+        let setup: Setup = serde_yaml::from_str(setup).unwrap();
+
+        let named: RpcProviderSource = serde_yaml::from_str("rpc_provider: MAINNET").unwrap();
+        assert_matches!(
+            named
+                .resolve(Some(&setup))
+                .map(|config| config.provider_type),
+            Ok(RpcProviderTypes::HttpWithBearerAuth)
+        );
+        assert_matches!(
+            named.resolve(None),
+            Err(RpcProviderError::RpcProviderNotDefined(_))
+        );
+
+        let yaml = r#"
+            rpc_provider: GOERLI
+            chain_rpc_url: "https://eth.llamarpc.com"
+        "#;
+        let fallback: RpcProviderSource = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(
+            fallback
+                .resolve(Some(&setup))
+                .map(|config| config.provider_type),
+            Ok(RpcProviderTypes::Http)
+        );
+    }
+
+    #[tokio::test]
     #[ignore]
-    fn can_parse_ws_rpc_provider() {
+    async fn can_connect_ws_rpc_provider() {
         let _anvil = Anvil::new().port(8545u16).spawn();
 
         let yaml = r#"
@@ -192,7 +699,8 @@ mod tests {
             provider_type: Websocket
         "#;
 
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::Websocket(_));
 
         let yaml = r#"
@@ -202,7 +710,8 @@ mod tests {
             password: test
         "#;
 
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::WebsocketWithBasicAuth(_));
 
         let yaml = r#"
@@ -210,35 +719,107 @@ mod tests {
             provider_type: WebsocketWithBearerAuth
             bearer: thisisabearertoken
         "#;
-        let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
         assert_matches!(rpc_provider, RpcProvider::WebsocketWithBearerAuth(_));
     }
 
-    // #[test]
-    // fn can_parse_ipc_provider() {
-    //     let yaml: &str = r#"
-    //         chain_rpc_url: "~/.ethereum/geth.ipc"
-    //         provider_type: Ipc
-    //     "#;
-
-    //     let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
-    //     assert_matches!(rpc_provider, RpcProvider::Ipc(_));
-    // }
-
-    // #[test]
-    // fn can_parse_quorum_rpc_provider() {
-    //     let yaml: &str = r#"
-    //         provider_type: Quorum
-    //         providers:
-    //         - chain_rpc_url: "https://eth.llamarpc.com"
-    //         - chain_rpc_url: "http://localhost:8545"
-    //           provider_type: Http
-    //           weight: 2
-    //         - chain_rpc_url: "ws://localhost::8545"
-    //           provider_type: Websocket
-    //           weight: 3
-    //     "#;
-
-    //     let rpc_provider: RpcProvider = serde_yaml::from_str(yaml).unwrap();
-    // }
+    #[test]
+    fn can_parse_ipc_provider() {
+        let yaml: &str = r#"
+            chain_rpc_url: "~/.ethereum/geth.ipc"
+            provider_type: Ipc
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(rpc_provider_config.provider_type, RpcProviderTypes::Ipc);
+    }
+
+    #[test]
+    fn can_parse_quorum_rpc_provider() {
+        let yaml = r#"
+            provider_type: Quorum
+            quorum: !Percentage 60
+            providers:
+            - chain_rpc_url: "https://eth.llamarpc.com"
+            - chain_rpc_url: "http://localhost:8545"
+              provider_type: Http
+              weight: 2
+            - chain_rpc_url: "ws://localhost:8545"
+              provider_type: Websocket
+              weight: 3
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(rpc_provider_config.provider_type, RpcProviderTypes::Quorum);
+        assert_matches!(
+            rpc_provider_config.quorum,
+            Some(QuorumThreshold::Percentage(60))
+        );
+        assert_eq!(rpc_provider_config.providers.len(), 3);
+        assert_eq!(rpc_provider_config.providers[2].weight, Some(3));
+    }
+
+    #[tokio::test]
+    async fn can_connect_http_quorum_and_fallback_rpc_provider() {
+        let yaml = r#"
+            provider_type: Quorum
+            providers:
+            - chain_rpc_url: "https://eth.llamarpc.com"
+            - chain_rpc_url: "https://cloudflare-eth.com"
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            rpc_provider_config.to_string(),
+            "Quorum provider [Http provider eth.llamarpc.com, Http provider cloudflare-eth.com]"
+        );
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
+        assert_matches!(rpc_provider, RpcProvider::Quorum(_));
+
+        let yaml = r#"
+            provider_type: Fallback
+            timeout: 5
+            providers:
+            - chain_rpc_url: "https://eth.llamarpc.com"
+            - chain_rpc_url: "https://cloudflare-eth.com"
+        "#;
+
+        let rpc_provider_config: RpcProviderConfig = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider = rpc_provider_config.connect().await.unwrap();
+        assert_matches!(rpc_provider, RpcProvider::Fallback(_));
+    }
+
+    #[test]
+    fn should_return_error_when_parse_invalid_quorum_rpc_provider() {
+        let yaml = r#"
+            provider_type: Quorum
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+
+        let yaml = r#"
+            provider_type: Quorum
+            providers:
+            - chain_rpc_url: "https://eth.llamarpc.com"
+              weight: 0
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+
+        let yaml = r#"
+            provider_type: Quorum
+            quorum: !Percentage 101
+            providers:
+            - chain_rpc_url: "https://eth.llamarpc.com"
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+
+        let yaml = r#"
+            provider_type: Fallback
+            providers:
+            - provider_type: Quorum
+              providers:
+              - chain_rpc_url: "https://eth.llamarpc.com"
+        "#;
+        assert_err!(serde_yaml::from_str::<RpcProviderConfig>(yaml));
+    }
 }
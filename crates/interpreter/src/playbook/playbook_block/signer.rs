@@ -0,0 +1,334 @@
+use std::path::PathBuf;
+
+use ethers::signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, WalletError};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{playbook::Setup, types::VariableName};
+
+use super::Variable;
+
+/// SignerConfig describes a local account which is used to sign transactions.
+///
+/// Key material and passwords are kept in [`Variable`]s, so they can be read from environment variables with
+/// `${ENV_VAR}` syntax instead of being written in the playbook file.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SignerConfig {
+    /// Raw hex encoded private key
+    PrivateKey(Variable),
+    /// Encrypted JSON keystore file
+    Keystore { path: Variable, password: Variable },
+    /// BIP-39 mnemonic phrase, the account is derived from `m/44'/60'/0'/0/{index}` unless a derivation path is given
+    Mnemonic {
+        phrase: Variable,
+        derivation_path: Option<String>,
+        index: Option<u32>,
+        password: Option<Variable>,
+    },
+}
+
+impl SignerConfig {
+    /// Build a [`LocalWallet`] from the signer configuration. Decrypting a keystore is slow on purpose, the executor
+    /// builds the wallet of each signer once per run.
+    pub fn wallet(&self) -> Result<LocalWallet, SignerConfigError> {
+        match self {
+            SignerConfig::PrivateKey(private_key) => {
+                let private_key = private_key.as_ref();
+                let wallet = private_key
+                    .strip_prefix("0x")
+                    .unwrap_or(private_key)
+                    .parse::<LocalWallet>()?;
+                Ok(wallet)
+            }
+            SignerConfig::Keystore { path, password } => {
+                let wallet =
+                    LocalWallet::decrypt_keystore(PathBuf::from(path.as_ref()), password.as_ref())?;
+                Ok(wallet)
+            }
+            SignerConfig::Mnemonic {
+                phrase,
+                derivation_path,
+                index,
+                password,
+            } => {
+                let mut builder = MnemonicBuilder::<English>::default().phrase(phrase.as_ref());
+
+                if let Some(derivation_path) = derivation_path {
+                    builder = builder.derivation_path(derivation_path)?;
+                } else if let Some(index) = index {
+                    builder = builder.index(*index)?;
+                }
+                if let Some(password) = password {
+                    builder = builder.password(password.as_ref());
+                }
+
+                Ok(builder.build()?)
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SignerConfig {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Debug, Deserialize)]
+        struct SignerConfigHelper {
+            #[serde(default)]
+            pub signer_type: SignerTypes,
+            pub private_key: Option<Variable>,
+            #[serde(alias = "keystore")]
+            pub keystore_path: Option<Variable>,
+            #[serde(alias = "mnemonic")]
+            pub phrase: Option<Variable>,
+            pub derivation_path: Option<String>,
+            pub index: Option<u32>,
+            pub password: Option<Variable>,
+        }
+
+        let helper = SignerConfigHelper::deserialize(deserializer)?;
+        match helper.signer_type {
+            SignerTypes::PrivateKey => {
+                let private_key = helper
+                    .private_key
+                    .ok_or("missing `private_key` field")
+                    .map_err(serde::de::Error::custom)?;
+                Ok(SignerConfig::PrivateKey(private_key))
+            }
+            SignerTypes::Keystore => {
+                let path = helper
+                    .keystore_path
+                    .ok_or("missing `keystore_path` field")
+                    .map_err(serde::de::Error::custom)?;
+                let password = helper
+                    .password
+                    .ok_or("missing `password` field")
+                    .map_err(serde::de::Error::custom)?;
+                Ok(SignerConfig::Keystore { path, password })
+            }
+            SignerTypes::Mnemonic => {
+                let phrase = helper
+                    .phrase
+                    .ok_or("missing `phrase` field")
+                    .map_err(serde::de::Error::custom)?;
+                if helper.derivation_path.is_some() && helper.index.is_some() {
+                    return Err(serde::de::Error::custom(
+                        "`derivation_path` and `index` cannot be used together",
+                    ));
+                }
+                Ok(SignerConfig::Mnemonic {
+                    phrase,
+                    derivation_path: helper.derivation_path,
+                    index: helper.index,
+                    password: helper.password,
+                })
+            }
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Deserialize, Default, strum::Display)]
+pub enum SignerTypes {
+    #[default]
+    PrivateKey,
+    Keystore,
+    Mnemonic,
+}
+
+/// The signer of a step, which is either the name of a signer defined in `setup.signers` or an inline
+/// [`SignerConfig`]
+#[derive(Debug, Clone)]
+pub enum SignerSource {
+    Named(VariableName),
+    Inline(SignerConfig),
+}
+
+impl SignerSource {
+    /// Resolve the signer configuration, looking up named signers in the playbook setup
+    pub fn resolve<'a>(
+        &'a self,
+        setup: Option<&'a Setup>,
+    ) -> Result<&'a SignerConfig, SignerConfigError> {
+        match self {
+            SignerSource::Named(signer_name) => setup
+                .and_then(|setup| setup.get_signer(signer_name.as_str()))
+                .ok_or(SignerConfigError::SignerNotDefined(signer_name.clone())),
+            SignerSource::Inline(signer_config) => Ok(signer_config),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SignerSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = serde_value::Value::deserialize(deserializer)?;
+
+        match value {
+            serde_value::Value::String(_) => VariableName::deserialize(value)
+                .map(SignerSource::Named)
+                .map_err(serde::de::Error::custom),
+            serde_value::Value::Map(_) => SignerConfig::deserialize(value)
+                .map(SignerSource::Inline)
+                .map_err(serde::de::Error::custom),
+            _ => Err(serde::de::Error::custom(
+                "expected a signer name or a signer configuration",
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SignerConfigError {
+    #[error(transparent)]
+    WalletError(#[from] WalletError),
+    #[error("signer {:#?} is not defined in setup", .0)]
+    SignerNotDefined(VariableName),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_matches};
+    use ethers::signers::Signer;
+    use std::env;
+
+    // anvil default accounts
+    const MNEMONIC: &str = "test test test test test test test test test test test junk";
+    const PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    const ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+    const SECOND_ADDRESS: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+
+    #[test]
+    fn can_parse_private_key_signer() {
+        let yaml = format!(
+            r#"
+            private_key: "{}"
+        "#,
+            PRIVATE_KEY
+        );
+
+        let signer: SignerConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_matches!(signer, SignerConfig::PrivateKey(_));
+
+        let wallet = signer.wallet().unwrap();
+        assert_eq!(wallet.address(), ADDRESS.parse().unwrap());
+    }
+
+    #[test]
+    fn can_parse_private_key_signer_from_env() {
+        env::set_var("SIGNER_PRIVATE_KEY", PRIVATE_KEY);
+        let yaml = r#"
+            signer_type: PrivateKey
+            private_key: ${SIGNER_PRIVATE_KEY}
+        "#;
+
+        let signer: SignerConfig = serde_yaml::from_str(yaml).unwrap();
+        let wallet = signer.wallet().unwrap();
+        assert_eq!(wallet.address(), ADDRESS.parse().unwrap());
+    }
+
+    #[test]
+    fn should_return_error_when_private_key_is_missing() {
+        let yaml = r#"
+            signer_type: PrivateKey
+        "#;
+
+        assert_err!(serde_yaml::from_str::<SignerConfig>(yaml));
+    }
+
+    #[test]
+    fn can_parse_mnemonic_signer() {
+        env::set_var("SIGNER_MNEMONIC", MNEMONIC);
+        let yaml = r#"
+            signer_type: Mnemonic
+            phrase: ${SIGNER_MNEMONIC}
+        "#;
+
+        let signer: SignerConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(signer.wallet().unwrap().address(), ADDRESS.parse().unwrap());
+
+        let yaml = r#"
+            signer_type: Mnemonic
+            phrase: ${SIGNER_MNEMONIC}
+            index: 1
+        "#;
+
+        let signer: SignerConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            signer.wallet().unwrap().address(),
+            SECOND_ADDRESS.parse().unwrap()
+        );
+
+        let yaml = r#"
+            signer_type: Mnemonic
+            phrase: ${SIGNER_MNEMONIC}
+            derivation_path: "m/44'/60'/0'/0/1"
+        "#;
+
+        let signer: SignerConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            signer.wallet().unwrap().address(),
+            SECOND_ADDRESS.parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn should_return_error_when_mnemonic_has_index_and_derivation_path() {
+        let yaml = r#"
+            signer_type: Mnemonic
+            phrase: "test test test test test test test test test test test junk"
+            derivation_path: "m/44'/60'/0'/0/1"
+            index: 1
+        "#;
+
+        assert_err!(serde_yaml::from_str::<SignerConfig>(yaml));
+    }
+
+    #[test]
+    fn can_parse_keystore_signer() {
+        let dir = env::temp_dir();
+        let keystore_name = format!("tudo-keystore-{}", uuid::Uuid::new_v4());
+        let mut rng = ethers::core::rand::thread_rng();
+        let (wallet, _) =
+            LocalWallet::new_keystore(&dir, &mut rng, "keystore password", Some(&keystore_name))
+                .unwrap();
+
+        env::set_var("SIGNER_KEYSTORE_PASSWORD", "keystore password");
+        let yaml = format!(
+            r#"
+            signer_type: Keystore
+            keystore_path: "{}"
+            password: ${{SIGNER_KEYSTORE_PASSWORD}}
+        "#,
+            dir.join(&keystore_name).display()
+        );
+
+        let signer: SignerConfig = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(signer.wallet().unwrap().address(), wallet.address());
+
+        std::fs::remove_file(dir.join(keystore_name)).unwrap();
+    }
+
+    #[test]
+    fn can_parse_signer_source() {
+        let signer_source: SignerSource = serde_yaml::from_str("DEPLOYER").unwrap();
+        assert_matches!(signer_source, SignerSource::Named(_));
+
+        let yaml = format!(
+            r#"
+            private_key: "{}"
+        "#,
+            PRIVATE_KEY
+        );
+        let signer_source: SignerSource = serde_yaml::from_str(&yaml).unwrap();
+        assert_matches!(
+            signer_source,
+            SignerSource::Inline(SignerConfig::PrivateKey(_))
+        );
+    }
+}
@@ -9,9 +9,15 @@ lazy_static! {
 }
 
 /// A Variable that value is a String and can contains environment variables in format `${ENV_VAR}`
-#[derive(Debug, Serialize, Into)]
+#[derive(Debug, Clone, Serialize, Into, PartialEq, Eq, Hash)]
 pub struct Variable(String);
 
+impl AsRef<str> for Variable {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
 impl<'de> Deserialize<'de> for Variable {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -1,5 +1,6 @@
 use crate::{
     job::{JobConfig, JobName},
+    types::VariableName,
     workflow::{
         JobConfigInWorkflowEnum, WorkflowConfig, WorkflowConfigBuilder, WorkflowConfigBuilderError,
         WorkflowConfigHelper, WorkflowName,
@@ -8,10 +9,13 @@ use crate::{
 use derive_builder::Builder;
 use handlebars::Handlebars;
 use serde::Deserialize;
-use std::{collections::HashMap, fs::File, io::Read, path::Path, sync::Arc};
+use std::{
+    collections::HashMap, fs::File, io::Read, num::NonZeroUsize, path::Path, sync::Arc,
+    time::Duration,
+};
 use thiserror::Error;
 
-use super::{Setup, Version};
+use super::{PlaybookDir, Setup, Variable, Version};
 
 /// Helper struct when deserializing Playbook
 #[derive(Debug, Deserialize)]
@@ -20,6 +24,10 @@ pub struct PlaybookDeserializeHelper {
     pub setup: Option<Setup>,
     pub jobs: HashMap<JobName, JobConfig>,
     pub workflows: HashMap<WorkflowName, WorkflowConfigHelper>,
+    #[serde(default)]
+    pub max_parallel: Option<NonZeroUsize>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
 }
 
 impl TryFrom<PlaybookDeserializeHelper> for Playbook {
@@ -64,6 +72,9 @@ impl TryFrom<PlaybookDeserializeHelper> for Playbook {
 
             let workflow_config = WorkflowConfigBuilder::default()
                 .jobs(job_config_mapping)
+                .fail_fast(workflow_config_helper.fail_fast)
+                .max_parallel(workflow_config_helper.max_parallel)
+                .timeout_ms(workflow_config_helper.timeout_ms)
                 .build()?;
 
             workflows.insert(workflow_name, workflow_config);
@@ -76,6 +87,8 @@ impl TryFrom<PlaybookDeserializeHelper> for Playbook {
             .setup(setup)
             .jobs(jobs)
             .workflows(workflows)
+            .max_parallel(value.max_parallel)
+            .timeout_ms(value.timeout_ms)
             .build()?;
 
         Ok(playbook)
@@ -100,42 +113,62 @@ pub struct Playbook {
     setup: Option<Arc<Setup>>,
     jobs: Arc<HashMap<JobName, JobConfig>>,
     workflows: Arc<HashMap<WorkflowName, WorkflowConfig>>,
+    /// Maximum number of jobs running at the same time across all workflows, unlimited if not set
+    #[builder(default)]
+    max_parallel: Option<NonZeroUsize>,
+    /// Maximum execution time of the playbook in milliseconds, unlimited if not set
+    #[builder(default)]
+    timeout_ms: Option<u64>,
 }
 
+/// Prefix of step output references, e.g. `${{ outputs.POOL_ADDRESS }}`
+const OUTPUT_REFERENCE_PREFIX: &str = "${{";
+/// Handlebars renders `\{{` as a literal `{{`
+const ESCAPED_OUTPUT_REFERENCE_PREFIX: &str = "$\\{{";
+
 impl Playbook {
     /// Parse Playbook from file
     pub fn from_file<P: AsRef<Path>>(file_path: P) -> Result<Self, PlaybookError> {
-        let mut file = File::open(file_path)?;
+        let mut file = File::open(&file_path)?;
         let mut content = String::new();
         file.read_to_string(&mut content)?;
 
         // preprocess
         let content = Self::preprocess(&content)?;
 
-        let playbook: Playbook = serde_yaml::from_str(&content)?;
+        // relative paths in the playbook are resolved against the directory of the playbook file
+        let playbook: Playbook =
+            PlaybookDir::scope(Some(&file_path), || serde_yaml::from_str(&content))?;
 
         Ok(playbook)
     }
 
-    /// Preprocess will replace variable references with handlebars syntax with pre-defined variables in the config setup
-    fn preprocess<S>(content: S) -> Result<String, PlaybookError>
+    /// Preprocess will replace variable references with handlebars syntax with pre-defined variables in the config setup.
+    /// Step output references `${{ outputs.NAME }}` are escaped, so they are kept for the executor to resolve.
+    pub(crate) fn preprocess<S>(content: S) -> Result<String, PlaybookError>
     where
         S: AsRef<str>,
     {
         let content = content.as_ref();
 
+        // only the variables are needed, the rest of the setup is parsed once with the playbook
+        #[derive(Debug, Deserialize)]
+        struct VariablesHelper {
+            variables: Option<HashMap<VariableName, Variable>>,
+        }
         #[derive(Debug, Deserialize)]
         struct SetupHelper {
-            setup: Option<Setup>,
+            setup: Option<VariablesHelper>,
         }
         let helper: SetupHelper = serde_yaml::from_str(content)?;
-        dbg!(&helper);
         if let Some(setup) = helper.setup {
             let mut handlebars = Handlebars::new();
 
             handlebars.set_strict_mode(true);
 
             if let Some(variables) = &setup.variables {
+                let content =
+                    content.replace(OUTPUT_REFERENCE_PREFIX, ESCAPED_OUTPUT_REFERENCE_PREFIX);
                 handlebars.register_template_string("variables", content)?;
                 let rendered = handlebars.render("variables", variables)?;
                 return Ok(rendered);
@@ -164,6 +197,21 @@ impl Playbook {
     pub fn shared_jobs(&self) -> Arc<HashMap<JobName, JobConfig>> {
         self.jobs.clone()
     }
+
+    /// Get the maximum number of jobs running at the same time across all workflows
+    pub fn max_parallel(&self) -> Option<NonZeroUsize> {
+        self.max_parallel
+    }
+
+    /// Override the maximum number of jobs running at the same time across all workflows
+    pub fn set_max_parallel(&mut self, max_parallel: Option<NonZeroUsize>) {
+        self.max_parallel = max_parallel;
+    }
+
+    /// Get the maximum execution time of the playbook
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -180,6 +228,8 @@ pub enum PlaybookError {
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use claims::assert_matches;
 
     use super::*;
@@ -192,7 +242,8 @@ mod tests {
                 uniswap_v3_eth_usdc_3000_pool_address:
                     steps:
                       - type: CallContract
-                        name: "Get ETH/USDC 0.3% fee pool address"
+                        name: get_eth_usdc_3000_pool_address
+                        title: "Get ETH/USDC 0.3% fee pool address"
                         arguments:
                             chain_rpc_url: "https://eth.llamarpc.com"
                             contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
@@ -217,6 +268,72 @@ mod tests {
         assert_matches!(playbook.version, Version::V1);
     }
 
+    #[test]
+    fn can_deserialize_max_parallel() {
+        let yaml = r#"
+            version: "1"
+            max_parallel: 4
+            jobs:
+                job_1:
+                    steps: []
+            workflows:
+                workflow_1:
+                    max_parallel: 2
+                    jobs:
+                    - job_1
+                workflow_2:
+                    jobs:
+                    - job_1
+        "#;
+
+        let playbook: Playbook = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(playbook.max_parallel(), NonZeroUsize::new(4));
+
+        let workflows = playbook.shared_workflows();
+        let max_parallel = |workflow_name: &str| {
+            workflows[&WorkflowName::from_str(workflow_name).unwrap()].max_parallel()
+        };
+        assert_eq!(max_parallel("workflow_1"), NonZeroUsize::new(2));
+        assert_eq!(max_parallel("workflow_2"), None);
+
+        // at least one job must be able to run
+        let yaml = yaml.replace("max_parallel: 4", "max_parallel: 0");
+        assert!(serde_yaml::from_str::<Playbook>(&yaml).is_err());
+    }
+
+    #[test]
+    fn can_load_artifacts_relative_to_playbook_file() {
+        let dir = std::env::temp_dir().join(format!("tudo-playbook-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("out/Factory.sol")).unwrap();
+        std::fs::write(
+            dir.join("out/Factory.sol/Factory.json"),
+            r#"{"abi":[{"type":"function","name":"owner","inputs":[],"outputs":[{"name":"","type":"address"}],"stateMutability":"view"}],"bytecode":{"object":"0x6080"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("playbook.yaml"),
+            r#"
+            version: "1"
+            setup:
+                artifacts: [out]
+            jobs:
+                job_1:
+                    steps: []
+            workflows:
+                workflow_1:
+                    jobs:
+                    - job_1
+            "#,
+        )
+        .unwrap();
+
+        let playbook = Playbook::from_file(dir.join("playbook.yaml")).unwrap();
+        let setup = playbook.shared_setup().unwrap();
+        assert!(setup.get_contract("Factory").is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn can_preprocess_playbook() {
         let yaml = r#"
@@ -231,7 +348,8 @@ mod tests {
                 uniswap_v3_eth_usdc_3000_pool_address:
                     steps:
                       - type: CallContract
-                        name: "Get ETH/USDC 0.3% fee pool address"
+                        name: get_eth_usdc_3000_pool_address
+                        title: "Get ETH/USDC 0.3% fee pool address"
                         arguments:
                             chain_rpc_url: {{ETH_RPC_URL}}
                             contract_address: {{UNISWAP_V3_FACTORY}}
@@ -254,4 +372,34 @@ mod tests {
 
         let _processed_content = Playbook::preprocess(yaml).unwrap();
     }
+
+    #[test]
+    fn can_preprocess_playbook_with_step_output_references() {
+        let yaml = r#"
+            version: "1"
+            setup:
+                variables:
+                    ETH_RPC_URL: https://eth.llamarpc.com
+            jobs:
+                uniswap_v3_eth_usdc_3000_pool:
+                    steps:
+                      - type: CallContract
+                        name: get_eth_usdc_3000_pool_slot0
+                        title: "Get ETH/USDC 0.3% fee pool slot0"
+                        arguments:
+                            chain_rpc_url: {{ETH_RPC_URL}}
+                            contract_address: "${{ outputs.ETH_USDC_3000_BPS_POOL_ADDRESS }}"
+                            function_signature: "slot0()"
+                            function_arguments: []
+            workflows:
+                workflow_1:
+                    jobs:
+                    - uniswap_v3_eth_usdc_3000_pool
+        "#;
+
+        let processed_content = Playbook::preprocess(yaml).unwrap();
+        assert!(processed_content.contains("chain_rpc_url: https://eth.llamarpc.com"));
+        assert!(processed_content
+            .contains(r#"contract_address: "${{ outputs.ETH_USDC_3000_BPS_POOL_ADDRESS }}""#));
+    }
 }
@@ -0,0 +1,70 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+};
+
+thread_local! {
+    static PLAYBOOK_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Directory of the playbook file being parsed. Relative paths in the playbook, like ABI files and artifacts
+/// directories, are resolved against it rather than the working directory, so a playbook runs the same from
+/// anywhere.
+pub(crate) struct PlaybookDir;
+
+impl PlaybookDir {
+    /// Parse within the directory of the playbook file, relative paths are resolved against it until `parse` returns
+    pub(crate) fn scope<P, F, T>(playbook_file: Option<P>, parse: F) -> T
+    where
+        P: AsRef<Path>,
+        F: FnOnce() -> T,
+    {
+        /// Restore the directory of the outer scope, even if parsing panics
+        struct Restore(Option<PathBuf>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                PLAYBOOK_DIR.with(|dir| *dir.borrow_mut() = self.0.take());
+            }
+        }
+
+        let playbook_dir =
+            playbook_file.and_then(|file| file.as_ref().parent().map(Path::to_path_buf));
+        let _restore = Restore(PLAYBOOK_DIR.with(|dir| dir.replace(playbook_dir)));
+        parse()
+    }
+
+    /// Resolve the path against the directory of the playbook file, absolute paths and paths parsed outside of a
+    /// playbook file are kept as they are
+    pub(crate) fn resolve<P: AsRef<Path>>(path: P) -> PathBuf {
+        let path = path.as_ref();
+        PLAYBOOK_DIR.with(|dir| match dir.borrow().as_ref() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.to_path_buf(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_resolve_paths_against_playbook_dir() {
+        assert_eq!(PlaybookDir::resolve("abi.json"), PathBuf::from("abi.json"));
+
+        let resolved = PlaybookDir::scope(Some("playbooks/uniswap.yaml"), || {
+            (
+                PlaybookDir::resolve("abi/Factory.json"),
+                PlaybookDir::resolve("/abi/Factory.json"),
+                PlaybookDir::scope(None::<&Path>, || PlaybookDir::resolve("abi.json")),
+                PlaybookDir::resolve("abi.json"),
+            )
+        });
+        assert_eq!(resolved.0, PathBuf::from("playbooks/abi/Factory.json"));
+        assert_eq!(resolved.1, PathBuf::from("/abi/Factory.json"));
+        assert_eq!(resolved.2, PathBuf::from("abi.json"));
+        assert_eq!(resolved.3, PathBuf::from("playbooks/abi.json"));
+
+        assert_eq!(PlaybookDir::resolve("abi.json"), PathBuf::from("abi.json"));
+    }
+}
@@ -1,16 +1,84 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
 use serde::Deserialize;
+use thiserror::Error;
 
-use crate::types::VariableName;
+use crate::types::{SolStructError, SolStructs, VariableName};
 
-use super::{RpcProvider, Variable};
+use super::{
+    ArtifactError, Artifacts, ContractConfig, PlaybookDir, RpcProviderConfig, SignerConfig,
+    Variable,
+};
 
 /// Playbook setup that contains predefined variables
 #[derive(Debug, Deserialize)]
+#[serde(try_from = "SetupHelper")]
 pub struct Setup {
     pub variables: Option<HashMap<VariableName, Variable>>,
-    pub rpc_providers: Option<HashMap<VariableName, RpcProvider>>,
+    /// Named RPC providers which can be referenced by name in the `rpc_provider` field of a step, so steps on the
+    /// same chain share a single connection
+    pub rpc_providers: Option<HashMap<VariableName, RpcProviderConfig>>,
+    /// Named accounts which can be referenced by name in the `signer` field of a step
+    pub signers: Option<HashMap<VariableName, SignerConfig>>,
+    /// Named contracts with their ABI, which can be referenced by name in the `contract` field of a step
+    pub contracts: Option<HashMap<VariableName, ContractConfig>>,
+    /// Contracts loaded from the Foundry or Hardhat build artifacts directories listed in `artifacts`
+    pub artifacts: Artifacts,
+    /// Structs declared like `struct Order { address maker; uint256 amount; }`, which can be used as Solidity types
+    /// of function arguments and return values
+    pub structs: SolStructs,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetupHelper {
+    variables: Option<HashMap<VariableName, Variable>>,
+    rpc_providers: Option<HashMap<VariableName, RpcProviderConfig>>,
+    signers: Option<HashMap<VariableName, SignerConfig>>,
+    contracts: Option<HashMap<VariableName, ContractConfig>>,
+    artifacts: Option<Vec<Variable>>,
+    structs: Option<Vec<String>>,
+}
+
+/// Load the artifacts, then the ABI and the bytecode of contracts declared by their artifact name
+impl TryFrom<SetupHelper> for Setup {
+    type Error = SetupError;
+
+    fn try_from(helper: SetupHelper) -> Result<Self, Self::Error> {
+        let directories = helper
+            .artifacts
+            .iter()
+            .flatten()
+            .map(|directory| PlaybookDir::resolve(directory.as_ref()))
+            .collect::<Vec<PathBuf>>();
+        let artifacts = Artifacts::load(&directories)?;
+
+        let mut contracts = helper.contracts;
+        for (contract_name, contract) in contracts.iter_mut().flatten() {
+            contract
+                .link(&artifacts)
+                .map_err(|err| ArtifactError::ContractArtifactError {
+                    contract: contract_name.as_str().to_string(),
+                    source: Box::new(err),
+                })?;
+        }
+
+        Ok(Self {
+            variables: helper.variables,
+            rpc_providers: helper.rpc_providers,
+            signers: helper.signers,
+            contracts,
+            artifacts,
+            structs: SolStructs::from_declarations(&helper.structs.unwrap_or_default())?,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SetupError {
+    #[error(transparent)]
+    ArtifactError(#[from] ArtifactError),
+    #[error(transparent)]
+    SolStructError(#[from] SolStructError),
 }
 
 impl Setup {
@@ -27,11 +95,59 @@ impl Setup {
             None
         }
     }
+
+    /// Get RPC provider config by it's name. Return None if the provider is not defined.
+    pub fn get_rpc_provider<N>(&self, provider_name: N) -> Option<&RpcProviderConfig>
+    where
+        N: AsRef<str>,
+    {
+        if let Some(rpc_providers) = &self.rpc_providers {
+            let provider_name = VariableName::from_str(provider_name.as_ref()).ok()?;
+
+            rpc_providers.get(&provider_name)
+        } else {
+            None
+        }
+    }
+
+    /// Get signer config by it's name. Return None if the signer is not defined.
+    pub fn get_signer<N>(&self, signer_name: N) -> Option<&SignerConfig>
+    where
+        N: AsRef<str>,
+    {
+        if let Some(signers) = &self.signers {
+            let signer_name = VariableName::from_str(signer_name.as_ref()).ok()?;
+
+            signers.get(&signer_name)
+        } else {
+            None
+        }
+    }
+
+    /// Get contract config by it's name, contracts which are not defined in `contracts` are looked up in the
+    /// artifacts. Return None if the contract is not found.
+    pub fn get_contract<N>(&self, contract_name: N) -> Option<&ContractConfig>
+    where
+        N: AsRef<str>,
+    {
+        let contract = self.contracts.as_ref().and_then(|contracts| {
+            let contract_name = VariableName::from_str(contract_name.as_ref()).ok()?;
+
+            contracts.get(&contract_name)
+        });
+        contract.or_else(|| {
+            self.artifacts
+                .get(contract_name.as_ref())
+                .ok()
+                .map(|artifact| &artifact.contract)
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use claims::{assert_none, assert_some};
     use std::env;
 
     #[test]
@@ -62,4 +178,72 @@ mod tests {
 
         let _setup: Setup = serde_yaml::from_str(yaml).unwrap();
     }
+
+    #[test]
+    fn can_deserialize_signers() {
+        env::set_var(
+            "DEPLOYER_PRIVATE_KEY",
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        );
+        env::set_var(
+            "OPERATOR_MNEMONIC",
+            "test test test test test test test test test test test junk",
+        );
+        let yaml = r#"
+            signers:
+                DEPLOYER:
+                    private_key: ${DEPLOYER_PRIVATE_KEY}
+                OPERATOR:
+                    signer_type: Mnemonic
+                    phrase: ${OPERATOR_MNEMONIC}
+                    index: 1
+        "#;
+
+        let setup: Setup = serde_yaml::from_str(yaml).unwrap();
+        assert_some!(setup.get_signer("DEPLOYER"));
+        assert_some!(setup.get_signer("OPERATOR"));
+        assert_none!(setup.get_signer("UNKNOWN"));
+    }
+
+    #[test]
+    fn can_load_contracts_from_artifacts() {
+        let out = std::env::temp_dir().join(format!("tudo-setup-out-{}", std::process::id()));
+        std::fs::create_dir_all(out.join("Factory.sol")).unwrap();
+        std::fs::write(
+            out.join("Factory.sol/Factory.json"),
+            r#"{"abi":[{"type":"function","name":"owner","inputs":[],"outputs":[{"name":"","type":"address"}],"stateMutability":"view"}],"bytecode":{"object":"0x6080"}}"#,
+        )
+        .unwrap();
+
+        let yaml = format!(
+            r#"
+            artifacts: [{}]
+            contracts:
+                FACTORY:
+                    address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                    artifact: Factory
+            "#,
+            out.display()
+        );
+        let setup: Setup = serde_yaml::from_str(&yaml).unwrap();
+
+        let factory = assert_some!(setup.get_contract("FACTORY"));
+        assert!(factory.address.is_some());
+        assert!(factory.bytecode.is_some());
+        assert!(factory.function("owner", 0).is_ok());
+        // contracts which are not declared are looked up by their artifact name
+        let factory = assert_some!(setup.get_contract("Factory"));
+        assert!(factory.address.is_none());
+        assert_none!(setup.get_contract("Router"));
+
+        let yaml = yaml.replace("artifact: Factory", "artifact: Router");
+        let error = serde_yaml::from_str::<Setup>(&yaml).unwrap_err();
+        assert!(
+            error.to_string().contains("contract `FACTORY`"),
+            "{}",
+            error
+        );
+
+        std::fs::remove_dir_all(&out).unwrap();
+    }
 }
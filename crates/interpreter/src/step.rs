@@ -10,3 +10,9 @@ mod step_argument_trait;
 pub use step_argument_trait::*;
 mod step_output;
 pub use step_output::*;
+mod step_context;
+pub use step_context::*;
+mod step_plan;
+pub use step_plan::*;
+mod step_retry;
+pub use step_retry::*;
@@ -1,7 +1,30 @@
-use super::StepArgumentsError;
+use super::{StepArgumentsError, StepContext, StepPlan};
+use crate::playbook::{RpcProviderSource, SignerSource};
 use shared::Step;
 
-/// A StepArgument can turn into a [`Step`]
+/// A StepArgument can turn into a [`Step`]. Named references in the step arguments are resolved against the
+/// playbook setup in the [`StepContext`].
 pub trait StepArgumentTrait {
-    fn to_step(&self) -> Result<Box<dyn Step>, StepArgumentsError>;
+    fn to_step(&self, context: &StepContext) -> Result<Box<dyn Step>, StepArgumentsError>;
+
+    /// RPC provider the step needs, the executor connects it before building the step
+    fn rpc_provider(&self) -> Option<&RpcProviderSource> {
+        None
+    }
+
+    /// Signer of the transaction of the step, the executor builds its wallet before building the step
+    fn signer(&self) -> Option<&SignerSource> {
+        None
+    }
+
+    /// Chain id the transaction of the step is signed for. If the step sends a transaction without a chain id, the
+    /// executor fetches the chain id from the RPC provider before building the step.
+    fn chain_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// Describe what the step will do without building it, arguments referencing step outputs are left unresolved
+    fn plan(&self, _context: &StepContext) -> Result<StepPlan, StepArgumentsError> {
+        Ok(StepPlan::default())
+    }
 }
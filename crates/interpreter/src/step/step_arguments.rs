@@ -1,13 +1,28 @@
 mod call_contract;
 pub use call_contract::*;
+mod send_transaction;
+pub use send_transaction::*;
+mod deploy_contract;
+pub use deploy_contract::*;
+mod function_call;
+pub use function_call::*;
+mod transaction_options;
+pub use transaction_options::*;
 
 use serde::Deserialize;
-use shared::{BlankStep, CallContractBuilderError, Step};
+use shared::{
+    BlankStep, CallContractBuilderError, DeployContractBuilderError, SendTransactionBuilderError,
+    Step,
+};
 use thiserror::Error;
 
-use crate::types::FunctionArgumentError;
+use crate::{
+    alloy_converter::AlloyConverterError,
+    playbook::{RpcProviderError, RpcProviderSource, SignerConfigError, SignerSource},
+    types::{DynSolTypeError, FunctionArgumentError, OutputReferenceError},
+};
 
-use super::StepArgumentTrait;
+use super::{StepArgumentTrait, StepContext, StepPlan};
 
 /// An enum represents all possible step definition
 #[derive(Debug, Clone, Deserialize)]
@@ -15,21 +30,81 @@ use super::StepArgumentTrait;
 pub enum StepArguments {
     BlankStep,
     CallContract(CallContract),
+    SendTransaction(SendTransaction),
+    DeployContract(DeployContract),
 }
 
 impl StepArgumentTrait for StepArguments {
-    fn to_step(&self) -> Result<Box<dyn Step>, StepArgumentsError> {
+    fn to_step(&self, context: &StepContext) -> Result<Box<dyn Step>, StepArgumentsError> {
         match self {
             StepArguments::BlankStep => Ok(Box::<BlankStep>::default()),
-            StepArguments::CallContract(inner) => inner.to_step(),
+            StepArguments::CallContract(inner) => inner.to_step(context),
+            StepArguments::SendTransaction(inner) => inner.to_step(context),
+            StepArguments::DeployContract(inner) => inner.to_step(context),
+        }
+    }
+
+    fn rpc_provider(&self) -> Option<&RpcProviderSource> {
+        match self {
+            StepArguments::BlankStep => None,
+            StepArguments::CallContract(inner) => inner.rpc_provider(),
+            StepArguments::SendTransaction(inner) => inner.rpc_provider(),
+            StepArguments::DeployContract(inner) => inner.rpc_provider(),
+        }
+    }
+
+    fn signer(&self) -> Option<&SignerSource> {
+        match self {
+            StepArguments::BlankStep | StepArguments::CallContract(_) => None,
+            StepArguments::SendTransaction(inner) => inner.signer(),
+            StepArguments::DeployContract(inner) => inner.signer(),
+        }
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        match self {
+            StepArguments::BlankStep | StepArguments::CallContract(_) => None,
+            StepArguments::SendTransaction(inner) => inner.chain_id(),
+            StepArguments::DeployContract(inner) => inner.chain_id(),
+        }
+    }
+
+    fn plan(&self, context: &StepContext) -> Result<StepPlan, StepArgumentsError> {
+        match self {
+            StepArguments::BlankStep => Ok(StepPlan::default()),
+            StepArguments::CallContract(inner) => inner.plan(context),
+            StepArguments::SendTransaction(inner) => inner.plan(context),
+            StepArguments::DeployContract(inner) => inner.plan(context),
         }
     }
 }
 
 #[derive(Error, Debug)]
 pub enum StepArgumentsError {
+    #[error("`chain_id` is not set and the chain id of the RPC provider is unknown")]
+    MissingChainId,
     #[error(transparent)]
     CallContractBuilderError(#[from] CallContractBuilderError),
     #[error(transparent)]
+    SendTransactionBuilderError(#[from] SendTransactionBuilderError),
+    #[error(transparent)]
+    DeployContractBuilderError(#[from] DeployContractBuilderError),
+    #[error(transparent)]
     FunctionArgumentError(#[from] FunctionArgumentError),
+    #[error(transparent)]
+    SignerConfigError(#[from] SignerConfigError),
+    #[error(transparent)]
+    RpcProviderError(#[from] RpcProviderError),
+    #[error(transparent)]
+    OutputReferenceError(#[from] OutputReferenceError),
+    #[error(transparent)]
+    FunctionCallError(#[from] FunctionCallError),
+    #[error(transparent)]
+    DeployContractError(#[from] DeployContractError),
+    #[error(transparent)]
+    AlloyConverterError(#[from] AlloyConverterError),
+    #[error(transparent)]
+    TransactionOptionsError(#[from] TransactionOptionsError),
+    #[error(transparent)]
+    DynSolTypeError(#[from] DynSolTypeError),
 }
@@ -1,31 +1,49 @@
 use alloy_primitives::Address;
 use ethers::{
-    abi::{ParamType, Token},
+    abi::ParamType,
+    providers::Middleware,
     types::{BlockId, Bytes},
 };
 use serde::Deserialize;
-use shared::{utils::build_calldata, CallContractBuilder, Step};
+use serde_value::Value;
+use shared::{CallContractBuilder, Step};
 
 use crate::{
     alloy_converter::AlloyConverter,
-    playbook::RpcProvider,
-    step::StepArgumentTrait,
-    types::{FunctionArgument, FunctionArgumentError, FunctionReturnTypes, FunctionSignature},
+    playbook::{RpcProvider, RpcProviderSource, Setup},
+    step::{StepArgumentTrait, StepContext, StepPlan},
+    types::{
+        ArgumentValue, DynSolTypeError, FunctionArgumentSource, FunctionReturnTypes,
+        FunctionSignature, OutputValues, SolStructs, VariableName,
+    },
 };
 
+use super::{FunctionCall, FunctionCallError, StepArgumentsError};
+
 /// CallContract is a step arguments
+///
+/// The function is either written with `function_signature`, typed `function_arguments` and `function_return_types`,
+/// or looked up by `function` in the ABI of a `contract` defined in `setup.contracts`, with untyped `args`.
 #[derive(Debug, Deserialize, Clone)]
 pub struct CallContract {
-    /// Chain rpc url
-    // pub chain_rpc_url: Url,
+    /// Either a provider name defined in `setup.rpc_providers` or an inline provider with `chain_rpc_url`
     #[serde(flatten)]
-    pub rpc_provider: RpcProvider,
-    /// Contract address in hex string
-    pub contract_address: Address,
+    pub rpc_provider: RpcProviderSource,
+    /// Contract address in hex string, or a reference to an earlier step output. Default to the address of
+    /// `contract`
+    pub contract_address: Option<ArgumentValue<Address>>,
+    /// Name of a contract defined in `setup.contracts`
+    pub contract: Option<VariableName>,
+    /// Function name in the ABI of `contract`, or its full signature to pick one of overloaded functions
+    pub function: Option<String>,
+    /// Arguments of `function`, their Solidity types are inferred from the ABI
+    #[serde(default)]
+    pub args: Vec<Value>,
     /// Function signature example: `setOwner(address)`
-    pub function_signature: FunctionSignature,
-    /// Function arguments
-    pub function_arguments: Vec<FunctionArgument>,
+    pub function_signature: Option<FunctionSignature>,
+    /// Function arguments, values could reference earlier step outputs
+    #[serde(default)]
+    pub function_arguments: Vec<FunctionArgumentSource>,
     /// Optional block
     pub block: Option<BlockId>,
     /// Function return types, could be defined as a single Solidity type or a Solidity tuple
@@ -34,79 +52,182 @@ pub struct CallContract {
 }
 
 impl CallContract {
-    /// Return calldata in [`Bytes`]
-    pub fn calldata(&self) -> Result<Bytes, FunctionArgumentError> {
-        let tokens = self
-            .function_arguments
-            .iter()
-            .map(|arg| arg.to_ethers_abi_token())
-            .collect::<Result<Vec<Token>, FunctionArgumentError>>()?;
-
-        let call_data = build_calldata(self.function_signature.as_ref(), &tokens);
-        Ok(call_data)
+    /// Resolve the called function, looking up `function` in the contract ABI of the playbook setup
+    pub fn function_call(&self, setup: Option<&Setup>) -> Result<FunctionCall, FunctionCallError> {
+        let mut function_call = FunctionCall::resolve(
+            self.function_signature.as_ref(),
+            &self.function_arguments,
+            self.contract.as_ref(),
+            self.function.as_deref(),
+            &self.args,
+            setup,
+        )?
+        .ok_or(FunctionCallError::MissingFunction)?;
+
+        if function_call.return_types.is_none() {
+            let contract = self
+                .contract
+                .as_ref()
+                .and_then(|contract| FunctionCall::contract(contract, setup).ok());
+            function_call.return_types =
+                self.resolve_return_types(&FunctionCall::structs(contract, setup))?;
+        }
+        Ok(function_call)
+    }
+
+    /// Resolve the contract address, which default to the address of `contract` in the playbook setup
+    pub fn contract_address(
+        &self,
+        setup: Option<&Setup>,
+    ) -> Result<ArgumentValue<Address>, FunctionCallError> {
+        FunctionCall::contract_address(
+            self.contract_address.as_ref(),
+            self.contract.as_ref(),
+            setup,
+        )?
+        .ok_or(FunctionCallError::MissingContractAddress)
+    }
+
+    /// Return calldata in [`Bytes`], function arguments referencing step outputs are resolved from `outputs`
+    pub fn calldata(
+        &self,
+        setup: Option<&Setup>,
+        outputs: &OutputValues,
+    ) -> Result<Bytes, StepArgumentsError> {
+        Ok(self.function_call(setup)?.calldata(outputs)?)
     }
 
-    /// Return function return in [`ParamType`] type
-    pub fn as_function_return_param_types(&self) -> Option<Vec<ParamType>> {
+    /// Return the function return types, the structs they name are resolved against the declared structs
+    pub fn resolve_return_types(
+        &self,
+        structs: &SolStructs,
+    ) -> Result<Option<Vec<FunctionReturnTypes>>, FunctionCallError> {
         self.function_return_types
             .as_ref()
             .map(|vec_function_return_types| {
                 vec_function_return_types
                     .iter()
-                    .map(|item| item.as_ethers_param_type())
-                    .collect::<Vec<ParamType>>()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        structs
+                            .resolve(item)
+                            .map_err(|err| FunctionCallError::InvalidReturnType {
+                                function_signature: self
+                                    .function_signature
+                                    .as_ref()
+                                    .map(|signature| signature.as_ref().to_string())
+                                    .unwrap_or_default(),
+                                index,
+                                message: err.to_string(),
+                            })
+                    })
+                    .collect()
             })
+            .transpose()
+    }
+
+    fn build_step<M>(
+        &self,
+        middleware: M,
+        context: &StepContext,
+    ) -> Result<Box<dyn Step>, StepArgumentsError>
+    where
+        M: Middleware + Clone + 'static,
+    {
+        let outputs = context.outputs();
+        let contract_address = self.contract_address(context.setup())?.resolve(outputs)?;
+        let function_call = self.function_call(context.setup())?;
+
+        let call_contract_step = CallContractBuilder::default()
+            .middleware(middleware)
+            .contract_address(AlloyConverter::from_alloy_address(&contract_address))
+            .calldata(function_call.calldata(outputs)?)
+            .block(self.block)
+            .return_data_types(
+                function_call
+                    .return_types
+                    .map(|return_types| {
+                        return_types
+                            .iter()
+                            .map(FunctionReturnTypes::as_ethers_param_type)
+                            .collect::<Result<Vec<ParamType>, DynSolTypeError>>()
+                    })
+                    .transpose()?,
+            )
+            .build()?;
+        Ok(Box::new(call_contract_step))
     }
 }
 
 impl StepArgumentTrait for CallContract {
-    fn to_step(&self) -> Result<Box<dyn Step>, super::StepArgumentsError> {
-        let contract_address = AlloyConverter::from_alloy_address(&self.contract_address);
-        let calldata = self.calldata()?;
-        let return_data_types = self.as_function_return_param_types();
-
-        match &self.rpc_provider {
+    fn to_step(&self, context: &StepContext) -> Result<Box<dyn Step>, StepArgumentsError> {
+        match context.rpc_provider()? {
             RpcProvider::Http(provider)
             | RpcProvider::HttpWithBasicAuth(provider)
             | RpcProvider::HttpWithBearerAuth(provider) => {
-                let call_contract_step = CallContractBuilder::default()
-                    .middleware(provider.clone())
-                    .contract_address(contract_address)
-                    .calldata(calldata)
-                    .block(self.block)
-                    .return_data_types(return_data_types)
-                    .build()?;
-                Ok(Box::new(call_contract_step))
+                self.build_step(provider.clone(), context)
             }
             RpcProvider::Websocket(provider)
             | RpcProvider::WebsocketWithBasicAuth(provider)
             | RpcProvider::WebsocketWithBearerAuth(provider) => {
-                let call_contract_step = CallContractBuilder::default()
-                    .middleware(provider.clone())
-                    .contract_address(contract_address)
-                    .calldata(calldata)
-                    .block(self.block)
-                    .return_data_types(return_data_types)
-                    .build()?;
-                Ok(Box::new(call_contract_step))
-            }
-            RpcProvider::Ipc(provider) => {
-                let call_contract_step = CallContractBuilder::default()
-                    .middleware(provider.clone())
-                    .contract_address(contract_address)
-                    .calldata(calldata)
-                    .block(self.block)
-                    .return_data_types(return_data_types)
-                    .build()?;
-                Ok(Box::new(call_contract_step))
+                self.build_step(provider.clone(), context)
             }
+            RpcProvider::Ipc(provider) => self.build_step(provider.clone(), context),
+            RpcProvider::Quorum(provider) => self.build_step(provider.clone(), context),
+            RpcProvider::Fallback(provider) => self.build_step(provider.clone(), context),
         }
     }
+
+    fn rpc_provider(&self) -> Option<&RpcProviderSource> {
+        Some(&self.rpc_provider)
+    }
+
+    fn plan(&self, context: &StepContext) -> Result<StepPlan, StepArgumentsError> {
+        let contract_address = self.contract_address(context.setup())?;
+        let function_call = self.function_call(context.setup())?;
+
+        let calldata = if function_call.references().next().is_some() {
+            None
+        } else {
+            Some(function_call.calldata(&OutputValues::new())?.to_string())
+        };
+
+        let references = contract_address
+            .reference()
+            .into_iter()
+            .chain(function_call.references())
+            .map(ToString::to_string)
+            .collect();
+
+        Ok(StepPlan {
+            target: Some(contract_address.to_string()),
+            function_signature: Some(function_call.function_signature.as_ref().to_string()),
+            function_selector: Some(function_call.function_signature.as_bytes().to_string()),
+            calldata,
+            return_types: function_call.return_types.as_ref().map(|return_types| {
+                return_types
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+            }),
+            references,
+            ..Default::default()
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        playbook::{RpcProviderConfig, Setup},
+        step::StepContextBuilder,
+        types::VariableName,
+    };
+    use claims::assert_matches;
+    use ethers::{abi::Token, providers::Provider, types::H160};
+    use shared::utils::build_calldata;
+    use std::{str::FromStr, sync::Arc};
 
     #[test]
     fn can_deserialize_call_contract_as_step_arguments() {
@@ -123,6 +244,36 @@ mod tests {
         let _call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
     }
 
+    #[test]
+    fn can_plan_call_contract() {
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+            function_signature: "getPool(address,address,uint24)"
+            function_arguments:
+                - type: address
+                  value: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                - type: address
+                  value: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+                - type: uint24
+                  value: 3000
+            function_return_types: [address]
+        "#;
+
+        let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
+        let step_plan = call_contract_step_argument
+            .plan(&StepContext::default())
+            .unwrap();
+        assert_eq!(
+            step_plan.target.unwrap(),
+            "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+        );
+        assert_eq!(step_plan.function_selector.unwrap(), "0x1698ee82");
+        assert!(step_plan.calldata.unwrap().starts_with("0x1698ee82"));
+        assert_eq!(step_plan.return_types.unwrap(), vec!["address"]);
+        assert!(step_plan.references.is_empty());
+    }
+
     #[test]
     fn can_convert_call_contract_step_argument_to_step() {
         let yaml = r#"
@@ -136,6 +287,281 @@ mod tests {
         "#;
 
         let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
-        let _step = call_contract_step_argument.to_step().unwrap();
+        let step_context = StepContextBuilder::default()
+            .rpc_provider(Some(RpcProvider::Http(
+                Provider::try_from("https://eth.llamarpc.com").unwrap(),
+            )))
+            .build()
+            .unwrap();
+        let _step = call_contract_step_argument.to_step(&step_context).unwrap();
+
+        // the step can not be built before the executor connects its provider
+        assert!(call_contract_step_argument
+            .to_step(&StepContext::default())
+            .is_err());
+    }
+
+    #[test]
+    fn can_resolve_named_rpc_provider_of_call_contract() {
+        let setup = r#"
+            rpc_providers:
+                MAINNET:
+                    chain_rpc_url: "https://eth.llamarpc.com"
+        "#;
+        let setup: Setup = serde_yaml::from_str(setup).unwrap();
+
+        let yaml = r#"
+            rpc_provider: MAINNET
+            contract_address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            function_signature: "balanceOf(address)"
+            function_arguments:
+                - type: address
+                  value: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+            function_return_types: [uint256]
+        "#;
+
+        let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
+        let rpc_provider_source = call_contract_step_argument.rpc_provider().unwrap();
+        let rpc_provider_config = rpc_provider_source.resolve(Some(&setup)).unwrap();
+        assert_eq!(
+            rpc_provider_config,
+            &serde_yaml::from_str::<RpcProviderConfig>(
+                r#"chain_rpc_url: "https://eth.llamarpc.com""#
+            )
+            .unwrap()
+        );
+        assert!(rpc_provider_source.resolve(None).is_err());
+    }
+
+    #[test]
+    fn can_build_call_contract_with_step_output_references() {
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            contract_address: "${{ outputs.POOL_ADDRESS }}"
+            function_signature: "balanceOf(address)"
+            function_arguments:
+                - type: address
+                  value: "${{ outputs.OWNER }}"
+            function_return_types: [uint256]
+        "#;
+
+        let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
+        let mut outputs = OutputValues::new();
+        outputs.insert(
+            VariableName::from_str("POOL_ADDRESS").unwrap(),
+            Token::Address(H160::repeat_byte(1)),
+        );
+
+        let step_context = StepContextBuilder::default()
+            .rpc_provider(Some(RpcProvider::Http(
+                Provider::try_from("https://eth.llamarpc.com").unwrap(),
+            )))
+            .outputs(outputs.clone())
+            .build()
+            .unwrap();
+        // `OWNER` is not saved yet
+        assert!(call_contract_step_argument.to_step(&step_context).is_err());
+
+        let step_plan = call_contract_step_argument
+            .plan(&StepContext::default())
+            .unwrap();
+        assert_eq!(step_plan.target.unwrap(), "${{ outputs.POOL_ADDRESS }}");
+        assert!(step_plan.calldata.is_none());
+        assert_eq!(
+            step_plan.references,
+            vec!["${{ outputs.POOL_ADDRESS }}", "${{ outputs.OWNER }}"]
+        );
+
+        outputs.insert(
+            VariableName::from_str("OWNER").unwrap(),
+            Token::Address(H160::repeat_byte(2)),
+        );
+        let calldata = call_contract_step_argument
+            .calldata(None, &outputs)
+            .unwrap();
+        assert_eq!(
+            calldata,
+            build_calldata(
+                "balanceOf(address)",
+                &[Token::Address(H160::repeat_byte(2))]
+            )
+        );
+    }
+
+    #[test]
+    fn can_call_contract_function_by_name() {
+        let setup = r#"
+            contracts:
+                UNISWAP_V3_FACTORY:
+                    address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                    abi:
+                        - function getPool(address,address,uint24) view returns (address)
+        "#;
+        let setup: Setup = serde_yaml::from_str(setup).unwrap();
+        let step_context = StepContextBuilder::default()
+            .setup(Some(Arc::new(setup)))
+            .build()
+            .unwrap();
+
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            contract: UNISWAP_V3_FACTORY
+            function: getPool
+            args:
+                - "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                - "${{ outputs.WETH }}"
+                - 3000
+        "#;
+
+        let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
+        let step_plan = call_contract_step_argument.plan(&step_context).unwrap();
+        assert_eq!(
+            step_plan.target.unwrap(),
+            "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+        );
+        assert_eq!(
+            step_plan.function_signature.unwrap(),
+            "getPool(address,address,uint24)"
+        );
+        assert_eq!(step_plan.function_selector.unwrap(), "0x1698ee82");
+        assert_eq!(step_plan.return_types.unwrap(), vec!["address"]);
+        assert_eq!(step_plan.references, vec!["${{ outputs.WETH }}"]);
+
+        let mut outputs = OutputValues::new();
+        outputs.insert(
+            VariableName::from_str("WETH").unwrap(),
+            Token::Address(H160::repeat_byte(2)),
+        );
+        assert_eq!(
+            call_contract_step_argument
+                .calldata(step_context.setup(), &outputs)
+                .unwrap(),
+            build_calldata(
+                "getPool(address,address,uint24)",
+                &[
+                    Token::Address(
+                        H160::from_str("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").unwrap()
+                    ),
+                    Token::Address(H160::repeat_byte(2)),
+                    Token::Uint(3000.into()),
+                ]
+            )
+        );
+
+        // the contract must be defined in setup, and the arguments must match the ABI
+        assert_matches!(
+            call_contract_step_argument.function_call(None),
+            Err(FunctionCallError::ContractNotDefined(_))
+        );
+        let mut call_contract_step_argument = call_contract_step_argument;
+        call_contract_step_argument.args.pop();
+        assert_matches!(
+            call_contract_step_argument.function_call(step_context.setup()),
+            Err(FunctionCallError::ArgumentCountMismatch {
+                expected: 3,
+                actual: 2,
+                ..
+            })
+        );
+        call_contract_step_argument
+            .args
+            .push(Value::String("not a number".to_string()));
+        assert_matches!(
+            call_contract_step_argument.function_call(step_context.setup()),
+            Err(FunctionCallError::InvalidArgument { index: 2, .. })
+        );
+    }
+
+    #[test]
+    fn can_call_contract_function_with_struct_arguments() {
+        let setup = r#"
+            structs:
+                - "struct Asset { address token; uint256 amount; }"
+            contracts:
+                SWAP_ROUTER:
+                    address: "0xE592427A0AEce92De3Edee1F18E0157C05861564"
+                    abi:
+                        - struct ExactInputSingleParams { address tokenIn; address tokenOut; uint24 fee; }
+                        - function exactInputSingle(ExactInputSingleParams params) payable returns (uint256 amountOut)
+                        - function quote(ExactInputSingleParams params) view returns (ExactInputSingleParams)
+        "#;
+        let setup: Setup = serde_yaml::from_str(setup).unwrap();
+        let step_context = StepContextBuilder::default()
+            .setup(Some(Arc::new(setup)))
+            .build()
+            .unwrap();
+
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            contract: SWAP_ROUTER
+            function: exactInputSingle
+            args:
+                - tokenIn: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                  tokenOut: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+                  fee: 3000
+        "#;
+        let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
+        let expected_calldata = build_calldata(
+            "exactInputSingle((address,address,uint24))",
+            &[Token::Tuple(vec![
+                Token::Address(
+                    H160::from_str("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48").unwrap(),
+                ),
+                Token::Address(
+                    H160::from_str("0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2").unwrap(),
+                ),
+                Token::Uint(3000.into()),
+            ])],
+        );
+        assert_eq!(
+            call_contract_step_argument
+                .calldata(step_context.setup(), &OutputValues::new())
+                .unwrap(),
+            expected_calldata
+        );
+
+        // returned structs keep their field names
+        let mut quote_step_argument = call_contract_step_argument.clone();
+        quote_step_argument.function = Some("quote".to_string());
+        let return_types = quote_step_argument
+            .function_call(step_context.setup())
+            .unwrap()
+            .return_types
+            .unwrap();
+        assert_matches!(
+            return_types[0].inner_ref(),
+            alloy_dyn_abi::DynSolType::CustomStruct { prop_names, .. } if prop_names[2] == "fee"
+        );
+
+        // a missing field is reported as an invalid argument
+        let mut call_contract_step_argument = call_contract_step_argument;
+        call_contract_step_argument.args = vec![serde_yaml::from_str(
+            r#"{ tokenIn: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" }"#,
+        )
+        .unwrap()];
+        assert_matches!(
+            call_contract_step_argument.function_call(step_context.setup()),
+            Err(FunctionCallError::InvalidArgument { index: 0, .. })
+        );
+
+        // structs declared in setup can be used by typed arguments and return types
+        let yaml = r#"
+            chain_rpc_url: "https://eth.llamarpc.com"
+            contract_address: "0xE592427A0AEce92De3Edee1F18E0157C05861564"
+            function_signature: "deposit((address,uint256))"
+            function_arguments:
+                - type: Asset
+                  value:
+                      token: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                      amount: 1000
+            function_return_types: [Asset]
+        "#;
+        let call_contract_step_argument: CallContract = serde_yaml::from_str(yaml).unwrap();
+        let step_plan = call_contract_step_argument.plan(&step_context).unwrap();
+        assert_eq!(step_plan.return_types.unwrap(), vec!["(address,uint256)"]);
+        assert_matches!(
+            call_contract_step_argument.function_call(None),
+            Err(FunctionCallError::InvalidArgument { index: 0, .. })
+        );
     }
 }
@@ -0,0 +1,447 @@
+use alloy_primitives::{Address, U256};
+use ethers::{
+    abi::Token,
+    prelude::SignerMiddleware,
+    providers::{JsonRpcClient, Provider},
+    signers::{LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, BlockId, Bytes, H256},
+    utils::to_checksum,
+};
+use serde::Deserialize;
+use serde_value::Value;
+use shared::{
+    create2_address, create2_calldata, DeployContractBuilder, Step, DEFAULT_CREATE2_FACTORY,
+};
+use thiserror::Error;
+
+use crate::{
+    alloy_converter::AlloyConverter,
+    playbook::{RpcProvider, RpcProviderSource, Setup, SignerSource},
+    step::{StepArgumentTrait, StepContext, StepPlan},
+    types::{
+        ArgumentValue, FunctionArgumentError, FunctionArgumentSource, OutputValues, VariableName,
+    },
+};
+
+use super::{FunctionCall, FunctionCallError, StepArgumentsError, TransactionOptions};
+
+/// DeployContract is a step arguments which deploys a contract, either by a CREATE transaction or through a CREATE2
+/// factory, then waits for its receipt. The output is the address of the deployed contract.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DeployContract {
+    /// Either a provider name defined in `setup.rpc_providers` or an inline provider with `chain_rpc_url`
+    #[serde(flatten)]
+    pub rpc_provider: RpcProviderSource,
+    /// Account used to sign the transaction, either a signer name defined in `setup.signers` or an inline signer
+    pub signer: SignerSource,
+    /// Chain id used to sign the transaction, fetched from the RPC provider if not set
+    pub chain_id: Option<u64>,
+    /// Creation bytecode in hex string
+    pub bytecode: Option<Bytes>,
+    /// Name of a contract defined in `setup.contracts` or of an artifact, which gives the creation bytecode and the
+    /// constructor
+    pub contract: Option<VariableName>,
+    /// Constructor arguments, their Solidity types are inferred from the ABI of `contract`
+    #[serde(default)]
+    pub args: Vec<Value>,
+    /// Typed constructor arguments, values could reference earlier step outputs
+    #[serde(default)]
+    pub constructor_arguments: Vec<FunctionArgumentSource>,
+    /// Deploy through a CREATE2 factory, which makes the contract address deterministic
+    pub create2: Option<Create2>,
+    /// Amount of wei sent to the constructor
+    pub value: Option<ArgumentValue<U256>>,
+    /// Gas, fees and nonce of the transaction
+    #[serde(flatten)]
+    pub options: TransactionOptions,
+    /// Optional block
+    pub block: Option<BlockId>,
+}
+
+/// Deployment through a CREATE2 factory, which deploys the creation code given after the salt in its calldata
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Create2 {
+    /// 32 bytes salt in hex string
+    pub salt: H256,
+    /// Factory address, default to the deterministic deployment proxy
+    /// `0x4e59b44847b379578588920cA78FbF26c0B4956C`
+    pub factory: Option<Address>,
+}
+
+impl Create2 {
+    /// Return the factory address
+    pub fn factory(&self) -> ethers::types::Address {
+        self.factory
+            .as_ref()
+            .map(AlloyConverter::from_alloy_address)
+            .unwrap_or(DEFAULT_CREATE2_FACTORY)
+    }
+}
+
+impl DeployContract {
+    /// Return the creation bytecode, either written in the step or given by `contract`
+    pub fn bytecode(&self, setup: Option<&Setup>) -> Result<Bytes, DeployContractError> {
+        match (&self.bytecode, &self.contract) {
+            (Some(_), Some(_)) => Err(DeployContractError::ConflictingBytecode),
+            (Some(bytecode), None) => Ok(bytecode.clone()),
+            (None, Some(contract)) => FunctionCall::contract(contract, setup)?
+                .bytecode
+                .clone()
+                .ok_or_else(|| DeployContractError::MissingContractBytecode(contract.clone())),
+            (None, None) => Err(DeployContractError::MissingBytecode),
+        }
+    }
+
+    /// Resolve the constructor arguments. Untyped `args` are coerced into the input types of the constructor in the
+    /// ABI of `contract`.
+    pub fn constructor_arguments(
+        &self,
+        setup: Option<&Setup>,
+    ) -> Result<Vec<FunctionArgumentSource>, DeployContractError> {
+        let contract = self
+            .contract
+            .as_ref()
+            .map(|contract| FunctionCall::contract(contract, setup))
+            .transpose()?;
+        let structs = FunctionCall::structs(contract, setup);
+
+        if !self.constructor_arguments.is_empty() {
+            if !self.args.is_empty() {
+                return Err(DeployContractError::ConflictingArguments);
+            }
+            let signature = Self::constructor_signature(
+                self.constructor_arguments
+                    .iter()
+                    .map(FunctionArgumentSource::solidity_type),
+            );
+            return Ok(FunctionCall::resolve_structs(
+                &signature,
+                &self.constructor_arguments,
+                &structs,
+            )?);
+        }
+
+        match contract {
+            Some(contract) => {
+                let inputs = contract
+                    .abi
+                    .constructor()
+                    .map(|constructor| constructor.inputs.as_slice())
+                    .unwrap_or_default();
+                let signature = Self::constructor_signature(inputs.iter().map(|input| &input.kind));
+                Ok(FunctionCall::typed_arguments(
+                    &signature, inputs, &self.args, &structs,
+                )?)
+            }
+            None if !self.args.is_empty() => Err(DeployContractError::UntypedArguments),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn constructor_signature<T: ToString>(types: impl Iterator<Item = T>) -> String {
+        format!(
+            "constructor({})",
+            types
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>()
+                .join(",")
+        )
+    }
+
+    /// Return the ABI-encoded constructor arguments, arguments referencing step outputs are resolved from `outputs`
+    fn encoded_arguments(
+        constructor_arguments: &[FunctionArgumentSource],
+        outputs: &OutputValues,
+    ) -> Result<Bytes, FunctionArgumentError> {
+        let tokens = constructor_arguments
+            .iter()
+            .map(|arg| arg.resolve(outputs)?.to_ethers_abi_token())
+            .collect::<Result<Vec<Token>, FunctionArgumentError>>()?;
+
+        Ok(ethers::abi::encode(&tokens).into())
+    }
+
+    /// Return the init code, which is the creation bytecode followed by the encoded constructor arguments
+    pub fn init_code(
+        &self,
+        setup: Option<&Setup>,
+        outputs: &OutputValues,
+    ) -> Result<Bytes, StepArgumentsError> {
+        let bytecode = self.bytecode(setup)?;
+        let encoded_arguments =
+            Self::encoded_arguments(&self.constructor_arguments(setup)?, outputs)?;
+
+        Ok([bytecode.as_ref(), encoded_arguments.as_ref()]
+            .concat()
+            .into())
+    }
+
+    /// Build the [`TypedTransaction`] which deploys the contract. Return with it the address of a contract deployed
+    /// through a CREATE2 factory.
+    pub fn typed_transaction(
+        &self,
+        setup: Option<&Setup>,
+        outputs: &OutputValues,
+    ) -> Result<(TypedTransaction, Option<ethers::types::Address>), StepArgumentsError> {
+        let init_code = self.init_code(setup, outputs)?;
+        let value = match &self.value {
+            Some(value) => AlloyConverter::from_alloy_uint(&value.resolve(outputs)?, U256::BITS)?,
+            None => Default::default(),
+        };
+
+        let (to, data, create2_address) = match &self.create2 {
+            Some(create2) => {
+                let factory = create2.factory();
+                (
+                    Some(factory),
+                    create2_calldata(create2.salt, &init_code),
+                    Some(create2_address(factory, create2.salt, &init_code)),
+                )
+            }
+            None => (None, init_code, None),
+        };
+
+        Ok((
+            self.options
+                .typed_transaction(to, value, data, self.chain_id)?,
+            create2_address,
+        ))
+    }
+
+    fn build_step<P>(
+        &self,
+        provider: Provider<P>,
+        wallet: LocalWallet,
+        context: &StepContext,
+    ) -> Result<Box<dyn Step>, StepArgumentsError>
+    where
+        P: JsonRpcClient + Clone + 'static,
+    {
+        let chain_id = self
+            .chain_id
+            .or(context.chain_id())
+            .ok_or(StepArgumentsError::MissingChainId)?;
+        let wallet = wallet.with_chain_id(chain_id);
+        let (mut tx_request, create2_address) =
+            self.typed_transaction(context.setup(), context.outputs())?;
+        tx_request.set_chain_id(chain_id);
+
+        let deploy_contract_step = DeployContractBuilder::default()
+            .signer_middleware(SignerMiddleware::new(provider, wallet))
+            .tx_request(tx_request)
+            .create2_address(create2_address)
+            .block(self.block)
+            .deadline(context.deadline())
+            .cancellation(context.cancellation().clone())
+            .nonce_manager(context.nonce_manager())
+            .on_sent(context.on_tx_sent())
+            .build()?;
+        Ok(Box::new(deploy_contract_step))
+    }
+}
+
+impl StepArgumentTrait for DeployContract {
+    fn to_step(&self, context: &StepContext) -> Result<Box<dyn Step>, StepArgumentsError> {
+        let wallet = context.wallet(&self.signer)?;
+
+        match context.rpc_provider()? {
+            RpcProvider::Http(provider)
+            | RpcProvider::HttpWithBasicAuth(provider)
+            | RpcProvider::HttpWithBearerAuth(provider) => {
+                self.build_step(provider.clone(), wallet, context)
+            }
+            RpcProvider::Websocket(provider)
+            | RpcProvider::WebsocketWithBasicAuth(provider)
+            | RpcProvider::WebsocketWithBearerAuth(provider) => {
+                self.build_step(provider.clone(), wallet, context)
+            }
+            RpcProvider::Ipc(provider) => self.build_step(provider.clone(), wallet, context),
+            RpcProvider::Quorum(provider) => self.build_step(provider.clone(), wallet, context),
+            RpcProvider::Fallback(provider) => self.build_step(provider.clone(), wallet, context),
+        }
+    }
+
+    fn rpc_provider(&self) -> Option<&RpcProviderSource> {
+        Some(&self.rpc_provider)
+    }
+
+    fn signer(&self) -> Option<&SignerSource> {
+        Some(&self.signer)
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    fn plan(&self, context: &StepContext) -> Result<StepPlan, StepArgumentsError> {
+        let bytecode = self.bytecode(context.setup())?;
+        let constructor_arguments = self.constructor_arguments(context.setup())?;
+
+        let references = self
+            .value
+            .as_ref()
+            .and_then(|value| value.reference())
+            .into_iter()
+            .chain(
+                constructor_arguments
+                    .iter()
+                    .filter_map(|arg| arg.reference()),
+            )
+            .map(ToString::to_string)
+            .collect::<Vec<String>>();
+
+        // the init code and the CREATE2 address are only known at run time if some arguments reference outputs
+        let encoded_arguments = if constructor_arguments
+            .iter()
+            .any(|arg| arg.reference().is_some())
+        {
+            None
+        } else {
+            Some(Self::encoded_arguments(
+                &constructor_arguments,
+                &OutputValues::new(),
+            )?)
+        };
+        let deployed_address = match (&self.create2, &encoded_arguments) {
+            (Some(create2), Some(encoded_arguments)) => {
+                let init_code: Bytes = [bytecode.as_ref(), encoded_arguments.as_ref()]
+                    .concat()
+                    .into();
+                Some(to_checksum(
+                    &create2_address(create2.factory(), create2.salt, &init_code),
+                    None,
+                ))
+            }
+            _ => None,
+        };
+
+        Ok(StepPlan {
+            target: self
+                .create2
+                .as_ref()
+                .map(|create2| to_checksum(&create2.factory(), None)),
+            value: self.value.as_ref().map(ToString::to_string),
+            function_signature: Some(Self::constructor_signature(
+                constructor_arguments.iter().map(|arg| arg.solidity_type()),
+            )),
+            calldata: encoded_arguments.map(|encoded_arguments| encoded_arguments.to_string()),
+            deployed_address,
+            references,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DeployContractError {
+    #[error("either `bytecode` or `contract` is required")]
+    MissingBytecode,
+    #[error("`bytecode` cannot be used with `contract`, which gives the creation bytecode")]
+    ConflictingBytecode,
+    #[error("contract `{}` has no creation bytecode, load it from an artifact", .0.as_str())]
+    MissingContractBytecode(VariableName),
+    #[error("`args` cannot be used with `constructor_arguments`")]
+    ConflictingArguments,
+    #[error(
+        "`args` requires `contract`, arguments of `bytecode` are typed `constructor_arguments`"
+    )]
+    UntypedArguments,
+    #[error(transparent)]
+    FunctionCallError(#[from] FunctionCallError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::step::StepContextBuilder;
+    use claims::assert_matches;
+    use std::sync::Arc;
+
+    #[test]
+    fn can_plan_create_and_create2_deployments() {
+        let yaml = r#"
+            rpc_providers:
+                LOCAL:
+                    chain_rpc_url: http://localhost:8545
+            contracts:
+                TOKEN:
+                    abi:
+                        - constructor(string name, uint8 decimals)
+                    bytecode: "0x6080"
+        "#;
+        let setup: Setup = serde_yaml::from_str(yaml).unwrap();
+        let context = StepContextBuilder::default()
+            .setup(Some(Arc::new(setup)))
+            .build()
+            .unwrap();
+
+        let yaml = r#"
+            rpc_provider: LOCAL
+            signer:
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            contract: TOKEN
+            args: ["Token", 18]
+        "#;
+        let deploy_contract: DeployContract = serde_yaml::from_str(yaml).unwrap();
+        let (tx, create2_address) = deploy_contract
+            .typed_transaction(context.setup(), &OutputValues::new())
+            .unwrap();
+        assert_eq!(tx.to(), None);
+        assert_eq!(create2_address, None);
+        assert_eq!(&tx.data().unwrap()[..2], &[0x60, 0x80]);
+
+        let step_plan = deploy_contract.plan(&context).unwrap();
+        assert_eq!(
+            step_plan.function_signature.as_deref(),
+            Some("constructor(string,uint8)")
+        );
+        assert_eq!(step_plan.target, None);
+        assert_eq!(step_plan.deployed_address, None);
+
+        let yaml = r#"
+            rpc_provider: LOCAL
+            signer:
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            bytecode: "0x00"
+            create2:
+                salt: "0x0000000000000000000000000000000000000000000000000000000000000000"
+                factory: "0x0000000000000000000000000000000000000000"
+            gas: "1000000"
+        "#;
+        let deploy_contract: DeployContract = serde_yaml::from_str(yaml).unwrap();
+        let (tx, create2_address) = deploy_contract
+            .typed_transaction(context.setup(), &OutputValues::new())
+            .unwrap();
+        assert_eq!(tx.gas(), Some(&ethers::types::U256::from(1000000)));
+        assert_eq!(tx.data().unwrap().len(), 33);
+
+        // example 0 of EIP-1014
+        let step_plan = deploy_contract.plan(&context).unwrap();
+        assert_eq!(
+            step_plan.deployed_address.as_deref(),
+            Some("0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38")
+        );
+        assert_eq!(
+            create2_address.map(|address| to_checksum(&address, None)),
+            step_plan.deployed_address
+        );
+
+        let yaml = r#"
+            rpc_provider: LOCAL
+            signer:
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            contract: TOKEN
+            args: ["Token"]
+        "#;
+        let deploy_contract: DeployContract = serde_yaml::from_str(yaml).unwrap();
+        assert_matches!(
+            deploy_contract.plan(&context),
+            Err(StepArgumentsError::DeployContractError(
+                DeployContractError::FunctionCallError(
+                    FunctionCallError::ArgumentCountMismatch { .. }
+                )
+            ))
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use alloy_primitives::Address;
+use ethers::{
+    abi::{Param, Token},
+    types::Bytes,
+};
+use serde_value::Value;
+use shared::utils::build_calldata;
+use thiserror::Error;
+
+use crate::{
+    playbook::{ContractConfig, ContractConfigError, Setup},
+    types::{
+        ArgumentValue, DynSolTypeWrapper, FunctionArgumentError, FunctionArgumentSource,
+        FunctionSignature, OutputReference, OutputValues, SolStructs, VariableName,
+    },
+};
+
+/// Function called by a step, either written with `function_signature` and typed `function_arguments`, or looked up
+/// by `function` in the ABI of a contract defined in `setup.contracts` with untyped `args`
+#[derive(Debug, Clone)]
+pub struct FunctionCall {
+    pub function_signature: FunctionSignature,
+    pub function_arguments: Vec<FunctionArgumentSource>,
+    /// Return types of the function, which are known if the function is looked up in a contract ABI
+    pub return_types: Option<Vec<DynSolTypeWrapper>>,
+}
+
+impl FunctionCall {
+    /// Resolve the function called by step arguments. Return None if the step calls no function.
+    pub fn resolve(
+        function_signature: Option<&FunctionSignature>,
+        function_arguments: &[FunctionArgumentSource],
+        contract: Option<&VariableName>,
+        function: Option<&str>,
+        args: &[Value],
+        setup: Option<&Setup>,
+    ) -> Result<Option<Self>, FunctionCallError> {
+        match (function_signature, function) {
+            (Some(_), Some(_)) => Err(FunctionCallError::ConflictingFunction),
+            (Some(function_signature), None) => {
+                if !args.is_empty() {
+                    return Err(FunctionCallError::UntypedArguments);
+                }
+                // the contract only gives the address and its structs, an undefined contract is reported by the
+                // address lookup
+                let contract = contract.and_then(|contract| Self::contract(contract, setup).ok());
+                Ok(Some(Self {
+                    function_signature: function_signature.clone(),
+                    function_arguments: Self::resolve_structs(
+                        function_signature.as_ref(),
+                        function_arguments,
+                        &Self::structs(contract, setup),
+                    )?,
+                    return_types: None,
+                }))
+            }
+            (None, Some(function)) => {
+                if !function_arguments.is_empty() {
+                    return Err(FunctionCallError::ConflictingFunction);
+                }
+                let contract =
+                    Self::contract(contract.ok_or(FunctionCallError::MissingContract)?, setup)?;
+                Self::from_abi(
+                    contract,
+                    function,
+                    args,
+                    &Self::structs(Some(contract), setup),
+                )
+                .map(Some)
+            }
+            (None, None) if !args.is_empty() || !function_arguments.is_empty() => {
+                Err(FunctionCallError::MissingFunction)
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Look up the function in the contract ABI, the arguments are coerced into the Solidity types of its inputs
+    pub fn from_abi(
+        contract: &ContractConfig,
+        function: &str,
+        args: &[Value],
+        structs: &SolStructs,
+    ) -> Result<Self, FunctionCallError> {
+        let abi_function = contract.function(function, args.len())?;
+        let function_signature = ContractConfig::signature(abi_function);
+
+        let function_arguments =
+            Self::typed_arguments(&function_signature, &abi_function.inputs, args, structs)?;
+        let return_types = abi_function
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(index, output)| {
+                structs
+                    .param_type(output)
+                    .map_err(|err| FunctionCallError::InvalidReturnType {
+                        function_signature: function_signature.clone(),
+                        index,
+                        message: err.to_string(),
+                    })
+            })
+            .collect::<Result<Vec<DynSolTypeWrapper>, FunctionCallError>>()?;
+
+        Ok(Self {
+            function_signature: FunctionSignature::from(function_signature.as_str()),
+            function_arguments,
+            return_types: Some(return_types),
+        })
+    }
+
+    /// Return the structs declared in the contract ABI and in setup
+    pub fn structs(contract: Option<&ContractConfig>, setup: Option<&Setup>) -> SolStructs {
+        let mut structs = contract
+            .map(|contract| contract.structs.clone())
+            .unwrap_or_default();
+        if let Some(setup) = setup {
+            structs.extend(&setup.structs);
+        }
+        structs
+    }
+
+    /// Resolve the structs named in the Solidity types of typed arguments
+    pub fn resolve_structs(
+        function_signature: &str,
+        function_arguments: &[FunctionArgumentSource],
+        structs: &SolStructs,
+    ) -> Result<Vec<FunctionArgumentSource>, FunctionCallError> {
+        function_arguments
+            .iter()
+            .enumerate()
+            .map(|(index, argument)| {
+                argument.resolve_structs(structs).map_err(|err| {
+                    FunctionCallError::InvalidArgument {
+                        function_signature: function_signature.to_string(),
+                        index,
+                        message: err.to_string(),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Coerce untyped arguments into the Solidity types of the function or constructor inputs in an ABI. A struct
+    /// argument is written as a map keyed by field name.
+    pub fn typed_arguments(
+        function_signature: &str,
+        inputs: &[Param],
+        args: &[Value],
+        structs: &SolStructs,
+    ) -> Result<Vec<FunctionArgumentSource>, FunctionCallError> {
+        if inputs.len() != args.len() {
+            return Err(FunctionCallError::ArgumentCountMismatch {
+                function_signature: function_signature.to_string(),
+                expected: inputs.len(),
+                actual: args.len(),
+            });
+        }
+
+        inputs
+            .iter()
+            .zip(args)
+            .enumerate()
+            .map(|(index, (input, arg))| {
+                let invalid_argument = |message: String| FunctionCallError::InvalidArgument {
+                    function_signature: function_signature.to_string(),
+                    index,
+                    message,
+                };
+                let solidity_type = structs
+                    .param_type(input)
+                    .map_err(|err| invalid_argument(err.to_string()))?;
+                FunctionArgumentSource::from_value(solidity_type, arg.clone())
+                    .map_err(|err| invalid_argument(err.to_string()))
+            })
+            .collect()
+    }
+
+    /// Get the contract config by its name in the playbook setup
+    pub fn contract<'a>(
+        contract: &VariableName,
+        setup: Option<&'a Setup>,
+    ) -> Result<&'a ContractConfig, FunctionCallError> {
+        setup
+            .and_then(|setup| setup.get_contract(contract.as_str()))
+            .ok_or_else(|| FunctionCallError::ContractNotDefined(contract.clone()))
+    }
+
+    /// Return the address written in the step, or else the address of its contract defined in setup
+    pub fn contract_address(
+        address: Option<&ArgumentValue<Address>>,
+        contract: Option<&VariableName>,
+        setup: Option<&Setup>,
+    ) -> Result<Option<ArgumentValue<Address>>, FunctionCallError> {
+        match (address, contract) {
+            (Some(address), _) => Ok(Some(address.clone())),
+            (None, Some(contract)) => Ok(Self::contract(contract, setup)?
+                .address
+                .map(ArgumentValue::Value)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Return calldata in [`Bytes`], function arguments referencing step outputs are resolved from `outputs`
+    pub fn calldata(&self, outputs: &OutputValues) -> Result<Bytes, FunctionArgumentError> {
+        let tokens = self
+            .function_arguments
+            .iter()
+            .map(|arg| arg.resolve(outputs)?.to_ethers_abi_token())
+            .collect::<Result<Vec<Token>, FunctionArgumentError>>()?;
+
+        Ok(build_calldata(self.function_signature.as_ref(), &tokens))
+    }
+
+    /// Return the output references of the function arguments, which are only known at run time
+    pub fn references(&self) -> impl Iterator<Item = &OutputReference> {
+        self.function_arguments
+            .iter()
+            .filter_map(|arg| arg.reference())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum FunctionCallError {
+    #[error("either `function_signature` or `function` is required")]
+    MissingFunction,
+    #[error("`function_signature` and `function_arguments` cannot be used with `function`")]
+    ConflictingFunction,
+    #[error("`args` requires `function`, arguments of `function_signature` are typed `function_arguments`")]
+    UntypedArguments,
+    #[error("`function` requires `contract`, the name of a contract defined in setup")]
+    MissingContract,
+    #[error("contract `{}` is not defined in setup contracts or artifacts", .0.as_str())]
+    ContractNotDefined(VariableName),
+    #[error(
+        "missing the address, write it in the step or as the `address` of the contract in setup"
+    )]
+    MissingContractAddress,
+    #[error("function `{function_signature}` takes {expected} arguments, got {actual}")]
+    ArgumentCountMismatch {
+        function_signature: String,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("invalid argument {index} of function `{function_signature}`: {message}")]
+    InvalidArgument {
+        function_signature: String,
+        index: usize,
+        message: String,
+    },
+    #[error("invalid return type {index} of function `{function_signature}`: {message}")]
+    InvalidReturnType {
+        function_signature: String,
+        index: usize,
+        message: String,
+    },
+    #[error(transparent)]
+    ContractConfigError(#[from] ContractConfigError),
+}
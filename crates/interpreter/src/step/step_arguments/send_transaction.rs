@@ -0,0 +1,340 @@
+use alloy_primitives::{Address, U256};
+use ethers::{
+    prelude::SignerMiddleware,
+    providers::{JsonRpcClient, Provider},
+    signers::{LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, BlockId, Bytes},
+};
+use serde::Deserialize;
+use serde_value::Value;
+use shared::{SendTransactionBuilder, Step};
+
+use crate::{
+    alloy_converter::AlloyConverter,
+    playbook::{RpcProvider, RpcProviderSource, Setup, SignerSource},
+    step::{StepArgumentTrait, StepContext, StepPlan},
+    types::{ArgumentValue, FunctionArgumentSource, FunctionSignature, OutputValues, VariableName},
+};
+
+use super::{FunctionCall, FunctionCallError, StepArgumentsError, TransactionOptions};
+
+/// SendTransaction is a step arguments which signs and sends a transaction, then waits for its receipt
+#[derive(Debug, Deserialize, Clone)]
+pub struct SendTransaction {
+    /// Either a provider name defined in `setup.rpc_providers` or an inline provider with `chain_rpc_url`
+    #[serde(flatten)]
+    pub rpc_provider: RpcProviderSource,
+    /// Account used to sign the transaction, either a signer name defined in `setup.signers` or an inline signer
+    pub signer: SignerSource,
+    /// Chain id used to sign the transaction, fetched from the RPC provider if not set
+    pub chain_id: Option<u64>,
+    /// Recipient address in hex string, or a reference to an earlier step output. Default to the address of
+    /// `contract`
+    pub to: Option<ArgumentValue<Address>>,
+    /// Amount of wei sent with the transaction
+    pub value: Option<ArgumentValue<U256>>,
+    /// Name of a contract defined in `setup.contracts`
+    pub contract: Option<VariableName>,
+    /// Function name in the ABI of `contract`, or its full signature to pick one of overloaded functions
+    pub function: Option<String>,
+    /// Arguments of `function`, their Solidity types are inferred from the ABI
+    #[serde(default)]
+    pub args: Vec<Value>,
+    /// Function signature example: `setOwner(address)`. Leave it empty to send a plain transfer
+    pub function_signature: Option<FunctionSignature>,
+    /// Function arguments, values could reference earlier step outputs
+    #[serde(default)]
+    pub function_arguments: Vec<FunctionArgumentSource>,
+    /// Gas, fees and nonce of the transaction
+    #[serde(flatten)]
+    pub options: TransactionOptions,
+    /// Optional block
+    pub block: Option<BlockId>,
+}
+
+impl SendTransaction {
+    /// Resolve the called function, looking up `function` in the contract ABI of the playbook setup. Return None for
+    /// a plain transfer.
+    pub fn function_call(
+        &self,
+        setup: Option<&Setup>,
+    ) -> Result<Option<FunctionCall>, FunctionCallError> {
+        FunctionCall::resolve(
+            self.function_signature.as_ref(),
+            &self.function_arguments,
+            self.contract.as_ref(),
+            self.function.as_deref(),
+            &self.args,
+            setup,
+        )
+    }
+
+    /// Resolve the recipient address, which default to the address of `contract` in the playbook setup
+    pub fn to(&self, setup: Option<&Setup>) -> Result<ArgumentValue<Address>, FunctionCallError> {
+        FunctionCall::contract_address(self.to.as_ref(), self.contract.as_ref(), setup)?
+            .ok_or(FunctionCallError::MissingContractAddress)
+    }
+
+    /// Return calldata in [`Bytes`], empty if the transaction calls no function
+    pub fn calldata(
+        &self,
+        setup: Option<&Setup>,
+        outputs: &OutputValues,
+    ) -> Result<Bytes, StepArgumentsError> {
+        match self.function_call(setup)? {
+            Some(function_call) => Ok(function_call.calldata(outputs)?),
+            None => Ok(Bytes::default()),
+        }
+    }
+
+    /// Build the [`TypedTransaction`] to send, its chain id is left unset if `chain_id` is not set
+    pub fn typed_transaction(
+        &self,
+        setup: Option<&Setup>,
+        outputs: &OutputValues,
+    ) -> Result<TypedTransaction, StepArgumentsError> {
+        let to = AlloyConverter::from_alloy_address(&self.to(setup)?.resolve(outputs)?);
+        let value = match &self.value {
+            Some(value) => AlloyConverter::from_alloy_uint(&value.resolve(outputs)?, U256::BITS)?,
+            None => Default::default(),
+        };
+        let calldata = self.calldata(setup, outputs)?;
+
+        Ok(self
+            .options
+            .typed_transaction(Some(to), value, calldata, self.chain_id)?)
+    }
+
+    fn build_step<P>(
+        &self,
+        provider: Provider<P>,
+        wallet: LocalWallet,
+        context: &StepContext,
+    ) -> Result<Box<dyn Step>, StepArgumentsError>
+    where
+        P: JsonRpcClient + Clone + 'static,
+    {
+        let chain_id = self
+            .chain_id
+            .or(context.chain_id())
+            .ok_or(StepArgumentsError::MissingChainId)?;
+        let wallet = wallet.with_chain_id(chain_id);
+        let mut tx_request = self.typed_transaction(context.setup(), context.outputs())?;
+        tx_request.set_chain_id(chain_id);
+
+        let send_transaction_step = SendTransactionBuilder::default()
+            .signer_middleware(SignerMiddleware::new(provider, wallet))
+            .tx_request(tx_request)
+            .block(self.block)
+            .deadline(context.deadline())
+            .cancellation(context.cancellation().clone())
+            .nonce_manager(context.nonce_manager())
+            .on_sent(context.on_tx_sent())
+            .build()?;
+        Ok(Box::new(send_transaction_step))
+    }
+}
+
+impl StepArgumentTrait for SendTransaction {
+    fn to_step(&self, context: &StepContext) -> Result<Box<dyn Step>, StepArgumentsError> {
+        let wallet = context.wallet(&self.signer)?;
+
+        match context.rpc_provider()? {
+            RpcProvider::Http(provider)
+            | RpcProvider::HttpWithBasicAuth(provider)
+            | RpcProvider::HttpWithBearerAuth(provider) => {
+                self.build_step(provider.clone(), wallet, context)
+            }
+            RpcProvider::Websocket(provider)
+            | RpcProvider::WebsocketWithBasicAuth(provider)
+            | RpcProvider::WebsocketWithBearerAuth(provider) => {
+                self.build_step(provider.clone(), wallet, context)
+            }
+            RpcProvider::Ipc(provider) => self.build_step(provider.clone(), wallet, context),
+            RpcProvider::Quorum(provider) => self.build_step(provider.clone(), wallet, context),
+            RpcProvider::Fallback(provider) => self.build_step(provider.clone(), wallet, context),
+        }
+    }
+
+    fn rpc_provider(&self) -> Option<&RpcProviderSource> {
+        Some(&self.rpc_provider)
+    }
+
+    fn signer(&self) -> Option<&SignerSource> {
+        Some(&self.signer)
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    fn plan(&self, context: &StepContext) -> Result<StepPlan, StepArgumentsError> {
+        let to = self.to(context.setup())?;
+        let function_call = self.function_call(context.setup())?;
+
+        let has_argument_references = function_call
+            .as_ref()
+            .is_some_and(|function_call| function_call.references().next().is_some());
+        let calldata = match &function_call {
+            Some(function_call) if !has_argument_references => {
+                Some(function_call.calldata(&OutputValues::new())?.to_string())
+            }
+            Some(_) => None,
+            None => Some(Bytes::default().to_string()),
+        };
+
+        let references = to
+            .reference()
+            .into_iter()
+            .chain(self.value.as_ref().and_then(|value| value.reference()))
+            .chain(function_call.iter().flat_map(FunctionCall::references))
+            .map(ToString::to_string)
+            .collect();
+
+        Ok(StepPlan {
+            target: Some(to.to_string()),
+            value: self.value.as_ref().map(ToString::to_string),
+            function_signature: function_call
+                .as_ref()
+                .map(|function_call| function_call.function_signature.as_ref().to_string()),
+            function_selector: function_call
+                .as_ref()
+                .map(|function_call| function_call.function_signature.as_bytes().to_string()),
+            calldata,
+            references,
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{playbook::Setup, step::StepContextBuilder};
+    use claims::assert_matches;
+    use ethers::providers::Http;
+    use std::sync::Arc;
+
+    fn local_rpc_provider() -> RpcProvider {
+        RpcProvider::Http(Provider::<Http>::try_from("http://localhost:8545").unwrap())
+    }
+
+    #[test]
+    fn can_deserialize_send_transaction_as_step_arguments() {
+        let yaml = r#"
+            chain_rpc_url: "http://localhost:8545"
+            signer:
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            chain_id: 31337
+            to: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+            function_signature: "transfer(address,uint256)"
+            function_arguments:
+                - type: address
+                  value: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+                - type: uint256
+                  value: 1000
+            gas: "100000"
+            max_fee_per_gas: "30000000000"
+            max_priority_fee_per_gas: "1000000000"
+        "#;
+
+        let send_transaction: SendTransaction = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(send_transaction.chain_id, Some(31337));
+
+        let tx = send_transaction
+            .typed_transaction(None, &OutputValues::new())
+            .unwrap();
+        assert_matches!(tx, TypedTransaction::Eip1559(_));
+        assert_eq!(tx.gas(), Some(&ethers::types::U256::from(100000)));
+        assert_eq!(
+            tx.data().unwrap(),
+            &send_transaction
+                .calldata(None, &OutputValues::new())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn can_build_legacy_transfer_transaction() {
+        let yaml = r#"
+            chain_rpc_url: "http://localhost:8545"
+            signer:
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+            value: "1000000000000000000"
+            gas_price: "20000000000"
+        "#;
+
+        let send_transaction: SendTransaction = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(send_transaction.chain_id, None);
+
+        let tx = send_transaction
+            .typed_transaction(None, &OutputValues::new())
+            .unwrap();
+        assert_matches!(tx, TypedTransaction::Legacy(_));
+        assert_eq!(tx.value(), Some(&ethers::types::U256::exp10(18)));
+        assert!(tx.data().unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_convert_send_transaction_step_argument_to_step() {
+        let yaml = r#"
+            chain_rpc_url: "http://localhost:8545"
+            signer:
+                private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+            value: "1000"
+        "#;
+
+        let send_transaction: SendTransaction = serde_yaml::from_str(yaml).unwrap();
+        let step_context = StepContextBuilder::default()
+            .rpc_provider(Some(local_rpc_provider()))
+            .chain_id(Some(31337))
+            .build()
+            .unwrap();
+        let _step = send_transaction.to_step(&step_context).unwrap();
+
+        // the chain id is neither set in the step nor fetched from the RPC provider
+        let step_context = StepContextBuilder::default()
+            .rpc_provider(Some(local_rpc_provider()))
+            .build()
+            .unwrap();
+        assert_matches!(
+            send_transaction.to_step(&step_context).err(),
+            Some(StepArgumentsError::MissingChainId)
+        );
+    }
+
+    #[test]
+    fn can_resolve_named_signer_from_setup() {
+        let setup = r#"
+            signers:
+                DEPLOYER:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        "#;
+        let setup: Setup = serde_yaml::from_str(setup).unwrap();
+
+        let yaml = r#"
+            chain_rpc_url: "http://localhost:8545"
+            signer: DEPLOYER
+            to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+            value: "1000"
+        "#;
+
+        let send_transaction: SendTransaction = serde_yaml::from_str(yaml).unwrap();
+        let step_context = StepContextBuilder::default()
+            .setup(Some(Arc::new(setup)))
+            .rpc_provider(Some(local_rpc_provider()))
+            .chain_id(Some(31337))
+            .build()
+            .unwrap();
+        let _step = send_transaction.to_step(&step_context).unwrap();
+
+        let step_context = StepContextBuilder::default()
+            .rpc_provider(Some(local_rpc_provider()))
+            .chain_id(Some(31337))
+            .build()
+            .unwrap();
+        assert!(send_transaction.to_step(&step_context).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+use alloy_primitives::U256;
+use ethers::types::{
+    transaction::eip2718::TypedTransaction, Address, Bytes, Eip1559TransactionRequest,
+    TransactionRequest,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::alloy_converter::{AlloyConverter, AlloyConverterError};
+
+/// Gas, fees and nonce of a transaction sent by a step
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TransactionOptions {
+    /// Gas limit
+    pub gas: Option<U256>,
+    /// Gas price of a legacy transaction
+    pub gas_price: Option<U256>,
+    /// Max fee per gas, setting it turns the transaction into an EIP-1559 transaction
+    pub max_fee_per_gas: Option<U256>,
+    /// Max priority fee per gas, setting it turns the transaction into an EIP-1559 transaction
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Transaction nonce, fetched from the chain if not set
+    pub nonce: Option<U256>,
+}
+
+impl TransactionOptions {
+    /// Check the options can build a transaction. `gas_price` cannot be set with the fees of an EIP-1559
+    /// transaction, which would ignore it.
+    pub fn check(&self) -> Result<(), TransactionOptionsError> {
+        if self.gas_price.is_some()
+            && (self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some())
+        {
+            return Err(TransactionOptionsError::ConflictingGasPrice);
+        }
+        Ok(())
+    }
+
+    /// Build the [`TypedTransaction`]. The transaction is an EIP-1559 transaction if `max_fee_per_gas` or
+    /// `max_priority_fee_per_gas` is set, otherwise it is a legacy transaction. A transaction without recipient
+    /// deploys its data as contract creation code.
+    pub fn typed_transaction(
+        &self,
+        to: Option<Address>,
+        value: ethers::types::U256,
+        data: Bytes,
+        chain_id: Option<u64>,
+    ) -> Result<TypedTransaction, TransactionOptionsError> {
+        self.check()?;
+
+        let mut tx: TypedTransaction =
+            if self.max_fee_per_gas.is_some() || self.max_priority_fee_per_gas.is_some() {
+                let mut tx_request = Eip1559TransactionRequest::new().value(value).data(data);
+
+                if let Some(to) = to {
+                    tx_request = tx_request.to(to);
+                }
+                if let Some(max_fee_per_gas) = &self.max_fee_per_gas {
+                    tx_request = tx_request.max_fee_per_gas(AlloyConverter::from_alloy_uint(
+                        max_fee_per_gas,
+                        U256::BITS,
+                    )?);
+                }
+                if let Some(max_priority_fee_per_gas) = &self.max_priority_fee_per_gas {
+                    tx_request = tx_request.max_priority_fee_per_gas(
+                        AlloyConverter::from_alloy_uint(max_priority_fee_per_gas, U256::BITS)?,
+                    );
+                }
+                tx_request.into()
+            } else {
+                let mut tx_request = TransactionRequest::new().value(value).data(data);
+
+                if let Some(to) = to {
+                    tx_request = tx_request.to(to);
+                }
+                if let Some(gas_price) = &self.gas_price {
+                    tx_request = tx_request
+                        .gas_price(AlloyConverter::from_alloy_uint(gas_price, U256::BITS)?);
+                }
+                tx_request.into()
+            };
+
+        if let Some(gas) = &self.gas {
+            tx.set_gas(AlloyConverter::from_alloy_uint(gas, U256::BITS)?);
+        }
+        if let Some(nonce) = &self.nonce {
+            tx.set_nonce(AlloyConverter::from_alloy_uint(nonce, U256::BITS)?);
+        }
+        if let Some(chain_id) = chain_id {
+            tx.set_chain_id(chain_id);
+        }
+
+        Ok(tx)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TransactionOptionsError {
+    #[error(
+        "`gas_price` cannot be used with `max_fee_per_gas` or `max_priority_fee_per_gas`, which set the fees of an \
+         EIP-1559 transaction"
+    )]
+    ConflictingGasPrice,
+    #[error(transparent)]
+    AlloyConverterError(#[from] AlloyConverterError),
+}
@@ -1,27 +1,94 @@
+use std::time::Duration;
+
 use serde::Deserialize;
 use serde_valid::Validate;
 use shared::Step;
 use thiserror::Error;
 
-use crate::step::CallContract;
+use crate::{
+    playbook::{RpcProviderSource, Setup, SignerSource},
+    step::{CallContract, DeployContract, SendTransaction},
+    types::FunctionReturnTypes,
+};
 
 use super::{
-    StepArgumentTrait, StepArguments, StepArgumentsError, StepName, StepOutput, StepTypes,
+    StepArgumentTrait, StepArguments, StepArgumentsError, StepContext, StepName, StepOutput,
+    StepPlan, StepRetry, StepTypes,
 };
 
 /// Step definition
 #[derive(Debug, Validate, Clone)]
 pub struct StepConfig {
     pub step_type: StepTypes,
+    /// Machine name used in reports and logs, it must match the name pattern
     pub name: StepName,
+    /// Human-readable title displayed next to the name, e.g. `Get ETH/USDC 0.3% fee pool address`
+    pub title: Option<String>,
     pub description: Option<String>,
     pub arguments: StepArguments,
     pub output: Option<StepOutput>,
+    /// Retry the step on transient failures, the step is executed once if not set
+    pub retry: Option<StepRetry>,
+    /// Maximum execution time of the step in milliseconds including retries, unlimited if not set
+    pub timeout_ms: Option<u64>,
 }
 
 impl StepConfig {
-    pub fn to_step(&self) -> Result<Box<dyn Step>, StepConfigError> {
-        self.arguments.to_step().map_err(StepConfigError::from)
+    /// Title to display for the step, which is the name if the step has no title
+    pub fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(self.name.as_str())
+    }
+
+    /// Maximum execution time of the step including retries
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Build the executable [`Step`], resolving named references against the playbook setup
+    pub fn to_step(&self, context: &StepContext) -> Result<Box<dyn Step>, StepConfigError> {
+        self.arguments
+            .to_step(context)
+            .map_err(StepConfigError::from)
+    }
+
+    /// RPC provider the step needs to be connected before building it
+    pub fn rpc_provider(&self) -> Option<&RpcProviderSource> {
+        self.arguments.rpc_provider()
+    }
+
+    /// Signer of the transaction of the step, if it sends one
+    pub fn signer(&self) -> Option<&SignerSource> {
+        self.arguments.signer()
+    }
+
+    /// Chain id the transaction of the step is signed for, if it is set in the step
+    pub fn chain_id(&self) -> Option<u64> {
+        self.arguments.chain_id()
+    }
+
+    /// Return types of the called function, which name the fields of returned structs. Return None if the step calls
+    /// no function or its return types are unknown.
+    pub fn return_types(&self, setup: Option<&Setup>) -> Option<Vec<FunctionReturnTypes>> {
+        match &self.arguments {
+            StepArguments::CallContract(call_contract) => call_contract
+                .function_call(setup)
+                .ok()
+                .and_then(|function_call| function_call.return_types),
+            _ => None,
+        }
+    }
+
+    /// Describe what the step will do, without connecting to its RPC provider
+    pub fn plan(&self, context: &StepContext) -> Result<StepPlan, StepConfigError> {
+        let mut step_plan = self.arguments.plan(context)?;
+
+        if let Some(rpc_provider_source) = self.rpc_provider() {
+            let rpc_provider_config = rpc_provider_source
+                .resolve(context.setup())
+                .map_err(StepArgumentsError::from)?;
+            step_plan.rpc_provider = Some(rpc_provider_config.to_string());
+        }
+        Ok(step_plan)
     }
 }
 
@@ -35,17 +102,28 @@ impl<'de> Deserialize<'de> for StepConfig {
             #[serde(alias = "type")]
             step_type: StepTypes,
             name: Option<StepName>,
+            title: Option<String>,
             description: Option<String>,
             arguments: Option<serde_value::Value>,
             output: Option<serde_value::Value>,
+            retry: Option<StepRetry>,
+            timeout_ms: Option<u64>,
         }
 
         let helper = StepConfigHelper::deserialize(deserializer)?;
 
-        // give step a randomize name if user doesn't specific the step name
-        let name = helper
-            .name
-            .unwrap_or(StepName::random_with_prefix(helper.step_type.to_string()));
+        // a step sending a transaction is recorded in the run state by its name, so the name must be the same in the
+        // resumed run. Other steps are given a random name if it is not set.
+        let name = match helper.name {
+            Some(name) => name,
+            None if helper.step_type.sends_transaction() => {
+                return Err(serde::de::Error::custom(format!(
+                    "missing field `name`, a {} step must be named so a resumed run finds its transaction",
+                    helper.step_type
+                )));
+            }
+            None => StepName::random_with_prefix(helper.step_type.to_string()),
+        };
 
         let step_arguments = match helper.step_type {
             StepTypes::BlankStep => StepArguments::BlankStep,
@@ -58,6 +136,24 @@ impl<'de> Deserialize<'de> for StepConfig {
                     .map(StepArguments::CallContract)
                     .map_err(serde::de::Error::custom)?
             }
+            StepTypes::SendTransaction => {
+                let arguments = helper
+                    .arguments
+                    .ok_or(serde::de::Error::custom("missing field `arguments`"))?;
+
+                SendTransaction::deserialize(arguments)
+                    .map(StepArguments::SendTransaction)
+                    .map_err(serde::de::Error::custom)?
+            }
+            StepTypes::DeployContract => {
+                let arguments = helper
+                    .arguments
+                    .ok_or(serde::de::Error::custom("missing field `arguments`"))?;
+
+                DeployContract::deserialize(arguments)
+                    .map(StepArguments::DeployContract)
+                    .map_err(serde::de::Error::custom)?
+            }
         };
 
         let step_output = match helper.step_type {
@@ -67,15 +163,23 @@ impl<'de> Deserialize<'de> for StepConfig {
                     .ok_or(serde::de::Error::custom("missing field `output`"))?;
                 Some(StepOutput::deserialize(output).map_err(serde::de::Error::custom)?)
             }
+            StepTypes::SendTransaction | StepTypes::DeployContract => helper
+                .output
+                .map(StepOutput::deserialize)
+                .transpose()
+                .map_err(serde::de::Error::custom)?,
             _ => None,
         };
 
         Ok(Self {
             step_type: helper.step_type,
             name,
+            title: helper.title,
             description: helper.description,
             arguments: step_arguments,
             output: step_output,
+            retry: helper.retry,
+            timeout_ms: helper.timeout_ms,
         })
     }
 }
@@ -113,6 +217,75 @@ mod tests {
         let _step_configs: StepConfig = serde_yaml::from_str(content).unwrap();
     }
 
+    #[test]
+    fn can_parse_send_transaction_step_config() {
+        let content = r#"
+            type: SendTransaction
+            name: "Transfer_USDC"
+            arguments:
+                chain_rpc_url: "http://localhost:8545"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                chain_id: 31337
+                to: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+                function_signature: "transfer(address,uint256)"
+                function_arguments:
+                    - type: address
+                      value: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+                    - type: uint256
+                      value: 1000
+        "#;
+
+        let step_config: StepConfig = serde_yaml::from_str(content).unwrap();
+        assert_eq!(step_config.step_type, StepTypes::SendTransaction);
+        assert!(step_config.output.is_none());
+    }
+
+    #[test]
+    fn cannot_parse_unnamed_transaction_step() {
+        let content = r#"
+            type: SendTransaction
+            arguments:
+                chain_rpc_url: "http://localhost:8545"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                to: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
+        "#;
+        let error = serde_yaml::from_str::<StepConfig>(content).unwrap_err();
+        assert!(error.to_string().contains("missing field `name`"));
+
+        let content = r#"
+            type: BlankStep
+        "#;
+        let step_config: StepConfig = serde_yaml::from_str(content).unwrap();
+        assert!(step_config.name.starts_with("BlankStep_"));
+    }
+
+    #[test]
+    fn can_parse_step_title() {
+        let content = r#"
+            type: BlankStep
+            name: get_pool
+            title: "Get ETH/USDC 0.3% fee pool address"
+        "#;
+        let step_config: StepConfig = serde_yaml::from_str(content).unwrap();
+        assert_eq!(
+            step_config.display_title(),
+            "Get ETH/USDC 0.3% fee pool address"
+        );
+
+        let step_config: StepConfig =
+            serde_yaml::from_str("type: BlankStep\nname: get_pool").unwrap();
+        assert_eq!(step_config.display_title(), "get_pool");
+
+        // titles are not allowed in names
+        let content = r#"
+            type: BlankStep
+            name: "Get ETH/USDC 0.3% fee pool address"
+        "#;
+        assert_err!(serde_yaml::from_str::<StepConfig>(content));
+    }
+
     #[test]
     fn should_return_error_when_parse_invalid_step_config() {
         let yaml = r#"
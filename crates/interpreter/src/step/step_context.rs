@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use derive_builder::Builder;
+use ethers::signers::LocalWallet;
+use shared::{NonceManager, TransactionSentHook};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    playbook::{RpcProvider, RpcProviderError, Setup, SignerConfigError, SignerSource},
+    types::OutputValues,
+};
+
+/// Runtime context which a step is built with. It holds the playbook setup to resolve named references, the RPC
+/// provider connected by the executor if the step needs one along with its chain id, the signer built by the executor
+/// and its nonce manager, the hook recording sent transactions, the outputs saved by earlier steps, the deadline of
+/// the step and the cancellation token of the run.
+#[derive(Debug, Clone, Default, Builder)]
+pub struct StepContext {
+    #[builder(default)]
+    setup: Option<Arc<Setup>>,
+    #[builder(default)]
+    rpc_provider: Option<RpcProvider>,
+    #[builder(default)]
+    chain_id: Option<u64>,
+    #[builder(default)]
+    wallet: Option<LocalWallet>,
+    #[builder(default)]
+    nonce_manager: Option<Arc<NonceManager>>,
+    #[builder(default)]
+    on_tx_sent: Option<TransactionSentHook>,
+    #[builder(default)]
+    outputs: OutputValues,
+    #[builder(default)]
+    deadline: Option<Instant>,
+    #[builder(default)]
+    cancellation: CancellationToken,
+}
+
+impl StepContext {
+    /// Get playbook setup
+    pub fn setup(&self) -> Option<&Setup> {
+        self.setup.as_deref()
+    }
+
+    /// Get the connected RPC provider. Return error if the executor has not connected a provider for the step.
+    pub fn rpc_provider(&self) -> Result<&RpcProvider, RpcProviderError> {
+        self.rpc_provider
+            .as_ref()
+            .ok_or(RpcProviderError::RpcProviderNotConnected)
+    }
+
+    /// Get the chain id of the connected RPC provider, which the executor fetches for steps sending a transaction
+    /// without a chain id
+    pub fn chain_id(&self) -> Option<u64> {
+        self.chain_id
+    }
+
+    /// Get the wallet of the signer, which is the wallet built by the executor if any. Otherwise it is built from the
+    /// signer config.
+    pub fn wallet(&self, signer: &SignerSource) -> Result<LocalWallet, SignerConfigError> {
+        match &self.wallet {
+            Some(wallet) => Ok(wallet.clone()),
+            None => signer.resolve(self.setup())?.wallet(),
+        }
+    }
+
+    /// Get the nonce manager of the signer on the chain, shared with the other steps signing with it
+    pub fn nonce_manager(&self) -> Option<Arc<NonceManager>> {
+        self.nonce_manager.clone()
+    }
+
+    /// Get the hook called with the hash of the transaction sent by the step
+    pub fn on_tx_sent(&self) -> Option<TransactionSentHook> {
+        self.on_tx_sent.clone()
+    }
+
+    /// Get outputs saved by earlier steps
+    pub fn outputs(&self) -> &OutputValues {
+        &self.outputs
+    }
+
+    /// Get the instant the step times out at, if any
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Get the token which is cancelled once the run is interrupted
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+}
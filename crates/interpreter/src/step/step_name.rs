@@ -1,10 +1,11 @@
-use derive_more::{Deref, From};
-use serde::Deserialize;
+use derive_more::Deref;
 use serde_valid::Validate;
 use uuid::Uuid;
 
+use crate::types::impl_name;
+
 /// StepName can only contains alphanumeric, `-`, `_` characters, up to a maximum of 200 characters.
-#[derive(Debug, Clone, Deref, Deserialize, From, Validate, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Deref, Validate, Eq, PartialEq, Hash)]
 pub struct StepName(#[validate(pattern = r#"^[a-zA-Z0-9][a-zA-Z0-9_-]{1,199}$"#)] String);
 
 impl StepName {
@@ -15,3 +16,25 @@ impl StepName {
         Self(randomize_with_prefix)
     }
 }
+
+impl_name!(StepName, "step name");
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn can_parse_step_name() {
+        assert_ok!(StepName::from_str("get_pool-1"));
+        assert_ok!(serde_yaml::from_str::<StepName>("get_pool"));
+        assert_ok!(StepName::from_str(
+            StepName::random_with_prefix("CallContract").as_str()
+        ));
+
+        assert_err!(StepName::from_str("Get ETH/USDC 0.3% fee pool address"));
+        assert_err!(serde_yaml::from_str::<StepName>("\"get pool\""));
+    }
+}
@@ -1,4 +1,7 @@
+use ethers::abi::Token;
 use serde::Deserialize;
+use shared::{CallContractOutput, SendTransactionOutput};
+use thiserror::Error;
 
 use crate::types::VariableName;
 
@@ -7,3 +10,56 @@ pub struct StepOutput {
     /// Save step output with a variable name
     pub save_as: VariableName,
 }
+
+impl StepOutput {
+    /// Return the value to save from the output of an executed step. Decoded return data with a single value is
+    /// saved as is, multiple values are saved as a tuple. Sent transactions save their transaction hash, deployments
+    /// save the address of the deployed contract.
+    pub fn value_of(&self, step_output: &shared::StepOutput) -> Result<Token, StepOutputError> {
+        match step_output {
+            shared::StepOutput::CallContractOutput(CallContractOutput::Tokens(tokens)) => {
+                match tokens.as_slice() {
+                    [token] => Ok(token.clone()),
+                    tokens => Ok(Token::Tuple(tokens.to_vec())),
+                }
+            }
+            shared::StepOutput::CallContractOutput(CallContractOutput::Bytes(bytes)) => {
+                Ok(Token::Bytes(bytes.to_vec()))
+            }
+            shared::StepOutput::SendTransactionOutput(
+                SendTransactionOutput::TransactionReceipt(Some(receipt)),
+            ) => Ok(Token::FixedBytes(
+                receipt.transaction_hash.as_bytes().to_vec(),
+            )),
+            shared::StepOutput::DeployContractOutput(output) => Ok(Token::Address(output.address)),
+            _ => Err(StepOutputError::NoOutputValue(self.save_as.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StepOutputError {
+    #[error("step has no output value to save as {:#?}", .0)]
+    NoOutputValue(VariableName),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_matches};
+    use ethers::types::{H160, U256};
+
+    #[test]
+    fn can_get_step_output_value() {
+        let step_output: StepOutput = serde_yaml::from_str("save_as: POOL_ADDRESS").unwrap();
+
+        let output = CallContractOutput::Tokens(vec![Token::Address(H160::repeat_byte(1))]).into();
+        assert_matches!(step_output.value_of(&output), Ok(Token::Address(_)));
+
+        let output =
+            CallContractOutput::Tokens(vec![Token::Uint(U256::from(1)), Token::Bool(true)]).into();
+        assert_matches!(step_output.value_of(&output), Ok(Token::Tuple(_)));
+
+        assert_err!(step_output.value_of(&shared::StepOutput::None));
+    }
+}
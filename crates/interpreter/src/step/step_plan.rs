@@ -0,0 +1,31 @@
+use serde::Serialize;
+
+/// What a step will do, described without connecting to any RPC provider
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StepPlan {
+    /// RPC provider type and host
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rpc_provider: Option<String>,
+    /// Target contract or recipient address, or the output reference it is resolved from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Amount of wei sent with the transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_selector: Option<String>,
+    /// ABI-encoded calldata, or the encoded constructor arguments of a deployment. Not set if some arguments reference
+    /// outputs which are only known at run time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calldata: Option<String>,
+    /// Address of a contract deployed with CREATE2, which is known before sending the transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployed_address: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_types: Option<Vec<String>>,
+    /// Output references resolved at run time
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+}
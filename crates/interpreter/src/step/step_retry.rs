@@ -0,0 +1,105 @@
+use std::{num::NonZeroU32, time::Duration};
+
+use ethers::core::rand::{thread_rng, Rng};
+use serde::Deserialize;
+
+/// Retry policy of a step, only transient failures like transport errors or rate limits are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepRetry {
+    /// Maximum number of attempts, including the first one
+    #[serde(default = "StepRetry::default_attempts")]
+    pub attempts: NonZeroU32,
+    /// Delay before the first retry in milliseconds, it doubles after every retry
+    #[serde(default = "StepRetry::default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Maximum delay between two attempts in milliseconds
+    #[serde(default = "StepRetry::default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Randomize every delay between half and the full delay, so parallel jobs don't retry at the same time
+    #[serde(default = "StepRetry::default_jitter")]
+    pub jitter: bool,
+}
+
+impl StepRetry {
+    fn default_attempts() -> NonZeroU32 {
+        NonZeroU32::new(3).unwrap()
+    }
+
+    fn default_backoff_ms() -> u64 {
+        1000
+    }
+
+    fn default_max_backoff_ms() -> u64 {
+        30_000
+    }
+
+    fn default_jitter() -> bool {
+        true
+    }
+
+    /// Delay before the given retry, starting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff_ms = self
+            .backoff_ms
+            .saturating_mul(2u64.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_backoff_ms);
+
+        let backoff_ms = if self.jitter && backoff_ms > 0 {
+            thread_rng().gen_range(backoff_ms / 2..=backoff_ms)
+        } else {
+            backoff_ms
+        };
+        Duration::from_millis(backoff_ms)
+    }
+}
+
+impl Default for StepRetry {
+    fn default() -> Self {
+        Self {
+            attempts: Self::default_attempts(),
+            backoff_ms: Self::default_backoff_ms(),
+            max_backoff_ms: Self::default_max_backoff_ms(),
+            jitter: Self::default_jitter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_compute_exponential_backoff() {
+        let step_retry: StepRetry = serde_yaml::from_str(
+            r#"
+            attempts: 5
+            backoff_ms: 500
+            max_backoff_ms: 3000
+            jitter: false
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(step_retry.attempts.get(), 5);
+        let backoffs = (1..=5)
+            .map(|retry| step_retry.backoff(retry).as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(backoffs, vec![500, 1000, 2000, 3000, 3000]);
+
+        let step_retry = StepRetry {
+            jitter: true,
+            ..step_retry
+        };
+        for retry in 1..=5 {
+            let backoff = step_retry.backoff(retry).as_millis();
+            assert!((250..=3000).contains(&backoff));
+        }
+
+        assert_eq!(
+            serde_yaml::from_str::<StepRetry>("{}").unwrap(),
+            StepRetry::default()
+        );
+        assert!(serde_yaml::from_str::<StepRetry>("attempts: 0").is_err());
+    }
+}
@@ -6,6 +6,15 @@ use serde::{Deserialize, Serialize};
 pub enum StepTypes {
     BlankStep,
     CallContract,
+    SendTransaction,
+    DeployContract,
+}
+
+impl StepTypes {
+    /// Whether the step signs and sends a transaction
+    pub fn sends_transaction(&self) -> bool {
+        matches!(self, StepTypes::SendTransaction | StepTypes::DeployContract)
+    }
 }
 
 #[cfg(test)]
@@ -16,8 +25,10 @@ mod tests {
     fn can_deserialize_step_types() {
         let content = r#"
             - CallContract
+            - SendTransaction
+            - DeployContract
         "#;
 
-        let _step_types: Vec<StepTypes> = serde_yaml::from_str(&content).unwrap();
+        let _step_types: Vec<StepTypes> = serde_yaml::from_str(content).unwrap();
     }
 }
@@ -8,5 +8,13 @@ mod dyn_sol_type_wrapper;
 pub use dyn_sol_type_wrapper::*;
 mod function_return_types;
 pub use function_return_types::*;
+mod invalid_name_error;
+pub use invalid_name_error::*;
 mod variable_name;
 pub use variable_name::*;
+mod output_reference;
+pub use output_reference::*;
+mod argument_value;
+pub use argument_value::*;
+mod sol_structs;
+pub use sol_structs::*;
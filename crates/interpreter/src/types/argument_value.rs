@@ -0,0 +1,145 @@
+use std::str::FromStr;
+
+use alloy_primitives::{Address, U256};
+use ethers::abi::Token;
+use serde::{Deserialize, Deserializer};
+use serde_value::Value;
+
+use crate::alloy_converter::AlloyConverter;
+
+use super::{OutputReference, OutputReferenceError, OutputValues};
+
+/// A step argument which is either a value written in the playbook, or a reference to the output of an earlier step
+/// in format `${{ outputs.VARIABLE_NAME }}`, resolved right before the step is built
+#[derive(Debug, Clone)]
+pub enum ArgumentValue<T> {
+    Value(T),
+    Reference(OutputReference),
+}
+
+impl<T> ArgumentValue<T>
+where
+    T: FromOutputToken + Clone,
+{
+    /// Return the value, resolving the output reference against saved outputs
+    pub fn resolve(&self, outputs: &OutputValues) -> Result<T, OutputReferenceError> {
+        match self {
+            ArgumentValue::Value(value) => Ok(value.clone()),
+            ArgumentValue::Reference(reference) => {
+                T::from_output_token(reference.resolve(outputs)?)
+            }
+        }
+    }
+}
+
+impl<T> ArgumentValue<T> {
+    /// Return the output reference if the value is only known at run time
+    pub fn reference(&self) -> Option<&OutputReference> {
+        match self {
+            ArgumentValue::Value(_) => None,
+            ArgumentValue::Reference(reference) => Some(reference),
+        }
+    }
+}
+
+impl<T> std::fmt::Display for ArgumentValue<T>
+where
+    T: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgumentValue::Value(value) => write!(f, "{}", value),
+            ArgumentValue::Reference(reference) => write!(f, "{}", reference),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for ArgumentValue<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = Value::deserialize(deserializer)?;
+
+        match &value {
+            Value::String(reference) if OutputReference::is_reference(reference) => {
+                OutputReference::from_str(reference)
+                    .map(ArgumentValue::Reference)
+                    .map_err(serde::de::Error::custom)
+            }
+            _ => T::deserialize(value)
+                .map(ArgumentValue::Value)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Types which can be read from a saved step output
+pub trait FromOutputToken: Sized {
+    fn from_output_token(token: Token) -> Result<Self, OutputReferenceError>;
+}
+
+impl FromOutputToken for Address {
+    fn from_output_token(token: Token) -> Result<Self, OutputReferenceError> {
+        match token {
+            Token::Address(address) => Ok(AlloyConverter::to_alloy_address(&address)),
+            other => Err(OutputReferenceError::TypeMismatch(
+                alloy_dyn_abi::DynSolType::Address,
+                other,
+            )),
+        }
+    }
+}
+
+impl FromOutputToken for U256 {
+    fn from_output_token(token: Token) -> Result<Self, OutputReferenceError> {
+        match token {
+            Token::Uint(value) => Ok(AlloyConverter::to_alloy_uint(&value)),
+            other => Err(OutputReferenceError::TypeMismatch(
+                alloy_dyn_abi::DynSolType::Uint(256),
+                other,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VariableName;
+    use claims::assert_matches;
+    use ethers::types::H160;
+
+    #[test]
+    fn can_deserialize_and_resolve_argument_value() {
+        let value: ArgumentValue<Address> =
+            serde_yaml::from_str(r#""0x1F98431c8aD98523631AE4a59f267346ea31F984""#).unwrap();
+        assert_matches!(value, ArgumentValue::Value(_));
+
+        let reference: ArgumentValue<Address> =
+            serde_yaml::from_str(r#""${{ outputs.POOL_ADDRESS }}""#).unwrap();
+        assert_matches!(reference, ArgumentValue::Reference(_));
+
+        let mut outputs = OutputValues::new();
+        outputs.insert(
+            VariableName::from_str("POOL_ADDRESS").unwrap(),
+            Token::Address(H160::repeat_byte(1)),
+        );
+        assert_eq!(
+            reference.resolve(&outputs).unwrap(),
+            Address::repeat_byte(1)
+        );
+
+        outputs.insert(
+            VariableName::from_str("POOL_ADDRESS").unwrap(),
+            Token::Bool(true),
+        );
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::TypeMismatch(_, _))
+        );
+    }
+}
@@ -1,8 +1,14 @@
-use alloy_dyn_abi::DynSolType;
+use alloy_dyn_abi::{DynSolType, DynSolValue};
+use alloy_primitives::B256;
 use derive_more::{Deref, From};
-use ethers::abi::ParamType;
+use ethers::abi::{ParamType, Token};
 use serde::{Deserialize, Deserializer};
 use serde_value::Value;
+use thiserror::Error;
+
+use crate::alloy_converter::AlloyConverter;
+
+use super::OutputReferenceError;
 
 /// Wrapper type which implements deserialization for [`DynSolType`]
 #[derive(Debug, Clone, Deref, From)]
@@ -15,6 +21,33 @@ impl Default for DynSolTypeWrapper {
     }
 }
 
+/// Display the Solidity type name, e.g. `uint256` or `(address,bool)`. Structs which are not resolved yet are
+/// displayed by their name.
+impl std::fmt::Display for DynSolTypeWrapper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn type_name(sol_type: &DynSolType) -> String {
+            match sol_type {
+                DynSolType::CustomStruct { name, tuple, .. } if tuple.is_empty() => name.clone(),
+                DynSolType::Array(item_type) => format!("{}[]", type_name(item_type)),
+                DynSolType::FixedArray(item_type, size) => {
+                    format!("{}[{}]", type_name(item_type), size)
+                }
+                // a struct is named by the tuple of its fields, as in function signatures
+                DynSolType::Tuple(tuple) | DynSolType::CustomStruct { tuple, .. } => format!(
+                    "({})",
+                    tuple
+                        .iter()
+                        .map(type_name)
+                        .collect::<Vec<String>>()
+                        .join(",")
+                ),
+                sol_type => sol_type.sol_type_name().to_string(),
+            }
+        }
+        write!(f, "{}", type_name(&self.0))
+    }
+}
+
 impl<'de> Deserialize<'de> for DynSolTypeWrapper {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -23,11 +56,7 @@ impl<'de> Deserialize<'de> for DynSolTypeWrapper {
         let value = Value::deserialize(deserializer)?;
 
         if let Value::String(value) = value {
-            let sol_type: DynSolType = value.parse().map_err(|e| {
-                serde::de::Error::custom(format!("cannot parse Solidity types, error {:#?}", e))
-            })?;
-
-            Ok(Self(sol_type))
+            Self::parse(&value).map_err(serde::de::Error::custom)
         } else {
             Err(serde::de::Error::custom(
                 "expected a string to respresent Solidity types",
@@ -37,56 +66,259 @@ impl<'de> Deserialize<'de> for DynSolTypeWrapper {
 }
 
 impl DynSolTypeWrapper {
+    /// Parse a Solidity type. A name which is not a Solidity type, optionally followed by array suffixes like
+    /// `Order[]`, is parsed as a struct which is resolved later against the declared structs.
+    pub fn parse(solidity_type: &str) -> Result<Self, String> {
+        DynSolType::parse(solidity_type).map(Self).or_else(|err| {
+            Self::parse_struct(solidity_type)
+                .ok_or_else(|| format!("cannot parse Solidity type `{}`: {}", solidity_type, err))
+        })
+    }
+
+    /// Parse an unresolved struct, which is a [`DynSolType::CustomStruct`] without fields since Solidity structs have
+    /// at least one field
+    fn parse_struct(solidity_type: &str) -> Option<Self> {
+        let (name, mut suffixes) = match solidity_type.find('[') {
+            Some(index) => solidity_type.split_at(index),
+            None => (solidity_type, ""),
+        };
+        let mut chars = name.chars();
+        let is_identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+        if !is_identifier {
+            return None;
+        }
+
+        let mut sol_type = DynSolType::CustomStruct {
+            name: name.to_string(),
+            prop_names: vec![],
+            tuple: vec![],
+        };
+        while !suffixes.is_empty() {
+            let (size, rest) = suffixes.strip_prefix('[')?.split_once(']')?;
+            sol_type = match size {
+                "" => DynSolType::Array(Box::new(sol_type)),
+                size => DynSolType::FixedArray(Box::new(sol_type), size.parse().ok()?),
+            };
+            suffixes = rest;
+        }
+        Some(Self(sol_type))
+    }
+
+    /// Return the name of the first struct which is not resolved yet, if any
+    pub fn unresolved_struct(&self) -> Option<&str> {
+        Self::find_unresolved_struct(&self.0)
+    }
+
+    fn find_unresolved_struct(sol_type: &DynSolType) -> Option<&str> {
+        match sol_type {
+            DynSolType::CustomStruct { name, tuple, .. } if tuple.is_empty() => Some(name),
+            DynSolType::CustomStruct { tuple, .. } | DynSolType::Tuple(tuple) => {
+                tuple.iter().find_map(Self::find_unresolved_struct)
+            }
+            DynSolType::Array(item_type) | DynSolType::FixedArray(item_type, _) => {
+                Self::find_unresolved_struct(item_type)
+            }
+            _ => None,
+        }
+    }
+
     /// Return inner [`DynSolType`] reference
     pub fn inner_ref(&self) -> &DynSolType {
         &self.0
     }
 
-    fn to_ethers_param_type(sol_type: &DynSolType) -> ParamType {
+    fn to_ethers_param_type(sol_type: &DynSolType) -> Result<ParamType, DynSolTypeError> {
         match sol_type {
-            DynSolType::Address => ParamType::Address,
-            DynSolType::Bool => ParamType::Bool,
-            DynSolType::Int(size) => ParamType::Int(*size),
-            DynSolType::Uint(size) => ParamType::Uint(*size),
-            DynSolType::FixedBytes(size) => ParamType::FixedBytes(*size),
-            DynSolType::Bytes => ParamType::Bytes,
-            DynSolType::String => ParamType::String,
-            DynSolType::Array(root_type) => {
-                ParamType::Array(Box::new(Self::to_ethers_param_type(root_type)))
+            DynSolType::Address => Ok(ParamType::Address),
+            DynSolType::Bool => Ok(ParamType::Bool),
+            DynSolType::Int(size) => Ok(ParamType::Int(*size)),
+            DynSolType::Uint(size) => Ok(ParamType::Uint(*size)),
+            DynSolType::FixedBytes(size) => Ok(ParamType::FixedBytes(*size)),
+            DynSolType::Bytes => Ok(ParamType::Bytes),
+            DynSolType::String => Ok(ParamType::String),
+            DynSolType::Array(root_type) => Ok(ParamType::Array(Box::new(
+                Self::to_ethers_param_type(root_type)?,
+            ))),
+            DynSolType::FixedArray(root_type, size) => Ok(ParamType::FixedArray(
+                Box::new(Self::to_ethers_param_type(root_type)?),
+                *size,
+            )),
+            DynSolType::Tuple(vec_param_type) => vec_param_type
+                .iter()
+                .map(Self::to_ethers_param_type)
+                .collect::<Result<Vec<ParamType>, DynSolTypeError>>()
+                .map(ParamType::Tuple),
+            DynSolType::CustomStruct { name, tuple, .. } if tuple.is_empty() => {
+                Err(DynSolTypeError::UnresolvedStruct(name.clone()))
             }
-            DynSolType::FixedArray(root_type, size) => {
-                ParamType::FixedArray(Box::new(Self::to_ethers_param_type(root_type)), *size)
-            }
-            DynSolType::Tuple(vec_param_type) => ParamType::Tuple(
-                vec_param_type
-                    .iter()
-                    .map(Self::to_ethers_param_type)
-                    .collect::<Vec<ParamType>>(),
-            ),
-            DynSolType::CustomStruct {
-                name: _,
-                prop_names: _,
-                tuple: _,
-            } => panic!("cannot convert DynSolType::CustomSutrct into ethers ParamType"),
+            // a struct is encoded as the tuple of its fields
+            DynSolType::CustomStruct { tuple, .. } => tuple
+                .iter()
+                .map(Self::to_ethers_param_type)
+                .collect::<Result<Vec<ParamType>, DynSolTypeError>>()
+                .map(ParamType::Tuple),
             #[allow(unreachable_patterns)]
-            _ => {
-                panic!("unsupported DynSolType")
-            }
+            sol_type => Err(DynSolTypeError::NotSupportedType(
+                sol_type.sol_type_name().to_string(),
+            )),
         }
     }
 
     /// Convert inner [`DynSolType`] into [`ParamType`]
-    pub fn as_ethers_param_type(&self) -> ParamType {
+    pub fn as_ethers_param_type(&self) -> Result<ParamType, DynSolTypeError> {
         Self::to_ethers_param_type(&self.0)
     }
+
+    /// Coerce an ethers-rs [`Token`], e.g. a saved step output, into a [`DynSolValue`] of the inner Solidity type.
+    /// Integers are checked against the size of the Solidity type.
+    pub fn coerce_token(&self, token: &Token) -> Result<DynSolValue, OutputReferenceError> {
+        Self::coerce_token_into(&self.0, token)
+    }
+
+    fn coerce_token_into(
+        sol_type: &DynSolType,
+        token: &Token,
+    ) -> Result<DynSolValue, OutputReferenceError> {
+        let type_mismatch = || OutputReferenceError::TypeMismatch(sol_type.clone(), token.clone());
+
+        match (sol_type, token) {
+            (DynSolType::Address, Token::Address(address)) => Ok(DynSolValue::Address(
+                AlloyConverter::to_alloy_address(address),
+            )),
+            (DynSolType::Bool, Token::Bool(value)) => Ok(DynSolValue::Bool(*value)),
+            (DynSolType::Uint(size), Token::Uint(value)) if value.bits() <= *size => Ok(
+                DynSolValue::Uint(AlloyConverter::to_alloy_uint(value), *size),
+            ),
+            (DynSolType::Int(size), Token::Int(raw)) => {
+                // two's complement value fits in `size` bits if the bits other than the sign bit fit in `size - 1`
+                let magnitude = if raw.bit(255) { !*raw } else { *raw };
+                if magnitude.bits() < *size {
+                    let int_value = ethers::types::I256::from_raw(*raw);
+                    Ok(DynSolValue::Int(
+                        AlloyConverter::to_alloy_int(&int_value),
+                        *size,
+                    ))
+                } else {
+                    Err(type_mismatch())
+                }
+            }
+            (DynSolType::FixedBytes(size), Token::FixedBytes(value)) if value.len() == *size => {
+                let mut word = [0u8; 32];
+                word[..value.len()].copy_from_slice(value);
+                Ok(DynSolValue::FixedBytes(B256::from(word), *size))
+            }
+            (DynSolType::Bytes, Token::Bytes(value)) => Ok(DynSolValue::Bytes(value.clone())),
+            (DynSolType::String, Token::String(value)) => Ok(DynSolValue::String(value.clone())),
+            (DynSolType::Array(item_type), Token::Array(items)) => items
+                .iter()
+                .map(|item| Self::coerce_token_into(item_type, item))
+                .collect::<Result<Vec<DynSolValue>, OutputReferenceError>>()
+                .map(DynSolValue::Array),
+            (DynSolType::FixedArray(item_type, size), Token::FixedArray(items))
+                if items.len() == *size =>
+            {
+                items
+                    .iter()
+                    .map(|item| Self::coerce_token_into(item_type, item))
+                    .collect::<Result<Vec<DynSolValue>, OutputReferenceError>>()
+                    .map(DynSolValue::FixedArray)
+            }
+            (DynSolType::Tuple(item_types), Token::Tuple(items))
+                if items.len() == item_types.len() =>
+            {
+                item_types
+                    .iter()
+                    .zip(items)
+                    .map(|(item_type, item)| Self::coerce_token_into(item_type, item))
+                    .collect::<Result<Vec<DynSolValue>, OutputReferenceError>>()
+                    .map(DynSolValue::Tuple)
+            }
+            (
+                DynSolType::CustomStruct {
+                    name,
+                    prop_names,
+                    tuple,
+                },
+                Token::Tuple(items),
+            ) if items.len() == tuple.len() => tuple
+                .iter()
+                .zip(items)
+                .map(|(item_type, item)| Self::coerce_token_into(item_type, item))
+                .collect::<Result<Vec<DynSolValue>, OutputReferenceError>>()
+                .map(|tuple| DynSolValue::CustomStruct {
+                    name: name.clone(),
+                    prop_names: prop_names.clone(),
+                    tuple,
+                }),
+            _ => Err(type_mismatch()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DynSolTypeError {
+    #[error("Solidity type `{}` is not supported", .0)]
+    NotSupportedType(String),
+    #[error("struct `{}` is not resolved against the declared structs", .0)]
+    UnresolvedStruct(String),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use claims::assert_ok;
+    use claims::{assert_err, assert_matches, assert_ok};
     use proptest::prelude::*;
 
+    #[test]
+    fn can_convert_into_ethers_param_type() {
+        let sol_type = DynSolTypeWrapper::parse("(address,uint24)[]").unwrap();
+        assert_eq!(
+            sol_type.as_ethers_param_type().unwrap(),
+            ParamType::Array(Box::new(ParamType::Tuple(vec![
+                ParamType::Address,
+                ParamType::Uint(24)
+            ])))
+        );
+
+        // a struct which is not resolved has no known fields
+        let sol_type = DynSolTypeWrapper::parse("Order[2]").unwrap();
+        assert_matches!(
+            sol_type.as_ethers_param_type(),
+            Err(DynSolTypeError::UnresolvedStruct(name)) if name == "Order"
+        );
+    }
+
+    #[test]
+    fn can_coerce_token() {
+        let sol_type: DynSolTypeWrapper = serde_yaml::from_str("(address, uint24, int24)").unwrap();
+        let token = Token::Tuple(vec![
+            Token::Address(ethers::types::H160::repeat_byte(1)),
+            Token::Uint(ethers::types::U256::from(3000)),
+            Token::Int(ethers::types::I256::from(-887272).into_raw()),
+        ]);
+
+        let value = sol_type.coerce_token(&token).unwrap();
+        assert_matches!(value, DynSolValue::Tuple(_));
+        assert_eq!(
+            value.as_tuple().unwrap()[2],
+            DynSolValue::Int(alloy_primitives::I256::try_from(-887272).unwrap(), 24)
+        );
+
+        // value does not fit in the declared size
+        let sol_type: DynSolTypeWrapper = serde_yaml::from_str("uint8").unwrap();
+        assert_err!(sol_type.coerce_token(&Token::Uint(ethers::types::U256::from(256))));
+
+        let sol_type: DynSolTypeWrapper = serde_yaml::from_str("int8").unwrap();
+        assert_ok!(sol_type.coerce_token(&Token::Int(ethers::types::I256::from(-128).into_raw())));
+        assert_err!(sol_type.coerce_token(&Token::Int(ethers::types::I256::from(-129).into_raw())));
+
+        let sol_type: DynSolTypeWrapper = serde_yaml::from_str("address").unwrap();
+        assert_err!(sol_type.coerce_token(&Token::Bool(true)));
+    }
+
     fn deserialize_dyn_sol_type_strategy() -> impl Strategy<Value = String> {
         let valid_cases = vec![
             "address",
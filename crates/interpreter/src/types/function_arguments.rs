@@ -4,9 +4,12 @@ use serde::{Deserialize, Deserializer};
 use serde_value::Value;
 use thiserror::Error;
 
-use crate::alloy_converter::AlloyConverter;
+use crate::alloy_converter::{AlloyConverter, AlloyConverterError};
 
-use super::DynSolTypeWrapper;
+use super::{
+    DynSolTypeWrapper, OutputReference, OutputReferenceError, OutputValues, SolStructError,
+    SolStructs,
+};
 
 /// Solidity function argument, which contains a Solidity type specifier and a Solidity value
 #[derive(Debug, Clone)]
@@ -30,22 +33,33 @@ impl<'de> Deserialize<'de> for FunctionArgument {
 
         let helper = FunctionArgumentHelper::deserialize(deserializer)?;
 
-        let solidity_value =
-            serde_json::to_value(helper.solidity_value).map_err(serde::de::Error::custom)?;
+        Self::new(helper.solidity_type, helper.solidity_value).map_err(serde::de::Error::custom)
+    }
+}
+
+impl FunctionArgument {
+    /// Coerce the value into the Solidity type, a struct is written as a map keyed by field name
+    pub fn new(
+        solidity_type: DynSolTypeWrapper,
+        solidity_value: Value,
+    ) -> Result<Self, FunctionArgumentError> {
+        let invalid_value = |message: String| FunctionArgumentError::InvalidValue {
+            solidity_type: solidity_type.to_string(),
+            message,
+        };
 
-        let solidity_value: DynSolValue = helper
-            .solidity_type
-            .coerce(&solidity_value)
-            .map_err(serde::de::Error::custom)?;
+        let json_value =
+            serde_json::to_value(solidity_value).map_err(|err| invalid_value(err.to_string()))?;
+        let solidity_value: DynSolValue = solidity_type
+            .coerce(&json_value)
+            .map_err(|err| invalid_value(err.to_string()))?;
 
         Ok(Self {
-            solidity_type: helper.solidity_type,
+            solidity_type,
             solidity_value,
         })
     }
-}
 
-impl FunctionArgument {
     /// Return Solidity type [`DynSolType`]
     pub fn get_sol_type(&self) -> &DynSolType {
         self.solidity_type.inner_ref()
@@ -65,16 +79,12 @@ impl FunctionArgument {
                 AlloyConverter::from_alloy_address(address),
             )),
             DynSolValue::Bool(value) => Ok(ethers::abi::Token::Bool(*value)),
-            DynSolValue::Int(value, size) => {
-                let int_value = AlloyConverter::from_alloy_int(value, *size);
-                let uint_value: ethers::types::U256 = int_value
-                    .try_into()
-                    .map_err(|_e| FunctionArgumentError::ConvertFromUintToIntError(int_value))?;
-
-                Ok(ethers::abi::Token::Int(uint_value))
-            }
+            // the token holds the two's complement representation of the integer
+            DynSolValue::Int(value, size) => Ok(ethers::abi::Token::Int(
+                AlloyConverter::from_alloy_int(value, *size)?.into_raw(),
+            )),
             DynSolValue::Uint(value, size) => Ok(ethers::abi::Token::Uint(
-                AlloyConverter::from_alloy_uint(value, *size),
+                AlloyConverter::from_alloy_uint(value, *size)?,
             )),
             DynSolValue::FixedBytes(value, _size) => Ok(ethers::abi::Token::FixedBytes(
                 AlloyConverter::from_alloy_fixed_bytes(value).to_vec(),
@@ -107,17 +117,18 @@ impl FunctionArgument {
                         .collect();
                 Ok(ethers::abi::Token::Tuple(converted_array?))
             }
-            custom_struct @ DynSolValue::CustomStruct {
-                name: _,
-                prop_names: _,
-                tuple: _,
-            } => Err(FunctionArgumentError::NotSupportedType(
-                custom_struct.clone(),
-            )),
-            #[allow(unreachable_patterns)]
-            _ => {
-                panic!("unsupported DynSolValue");
+            // a struct is encoded as the tuple of its fields
+            DynSolValue::CustomStruct { tuple, .. } => {
+                let converted_array: Result<Vec<ethers::abi::Token>, FunctionArgumentError> = tuple
+                    .iter()
+                    .map(Self::dyn_sol_value_to_ethers_abi_token)
+                    .collect();
+                Ok(ethers::abi::Token::Tuple(converted_array?))
             }
+            #[allow(unreachable_patterns)]
+            solidity_value => Err(FunctionArgumentError::NotSupportedType(
+                solidity_value.clone(),
+            )),
         }
     }
 
@@ -140,14 +151,161 @@ impl FunctionArgument {
     // }
 }
 
+/// Function argument written in step arguments. The value is either given in the playbook, or a reference to the
+/// output of an earlier step, e.g. `${{ outputs.POOL_ADDRESS }}`, which is coerced into the declared Solidity type
+/// when the step is built.
+#[derive(Debug, Clone)]
+pub enum FunctionArgumentSource {
+    Value(FunctionArgument),
+    Reference {
+        solidity_type: DynSolTypeWrapper,
+        reference: OutputReference,
+    },
+    /// Value of a struct type, which is coerced once the struct is resolved against the structs declared in setup or
+    /// in the contract ABI
+    Struct {
+        solidity_type: DynSolTypeWrapper,
+        value: Value,
+    },
+}
+
+impl FunctionArgumentSource {
+    /// Build the argument of the Solidity type from a value written in the playbook, which is either a value or a
+    /// reference to an earlier step output
+    pub fn from_value(
+        solidity_type: DynSolTypeWrapper,
+        value: Value,
+    ) -> Result<Self, FunctionArgumentError> {
+        match value {
+            Value::String(reference) if OutputReference::is_reference(&reference) => {
+                let reference =
+                    OutputReference::deserialize(Value::String(reference)).map_err(|err| {
+                        FunctionArgumentError::InvalidValue {
+                            solidity_type: solidity_type.to_string(),
+                            message: err.to_string(),
+                        }
+                    })?;
+                Ok(FunctionArgumentSource::Reference {
+                    solidity_type,
+                    reference,
+                })
+            }
+            value if solidity_type.unresolved_struct().is_some() => {
+                Ok(FunctionArgumentSource::Struct {
+                    solidity_type,
+                    value,
+                })
+            }
+            value => FunctionArgument::new(solidity_type, value).map(FunctionArgumentSource::Value),
+        }
+    }
+
+    /// Return the output reference if the argument value is only known at run time
+    pub fn reference(&self) -> Option<&OutputReference> {
+        match self {
+            FunctionArgumentSource::Value(_) | FunctionArgumentSource::Struct { .. } => None,
+            FunctionArgumentSource::Reference { reference, .. } => Some(reference),
+        }
+    }
+
+    /// Return the declared Solidity type
+    pub fn solidity_type(&self) -> &DynSolTypeWrapper {
+        match self {
+            FunctionArgumentSource::Value(function_argument) => &function_argument.solidity_type,
+            FunctionArgumentSource::Reference { solidity_type, .. }
+            | FunctionArgumentSource::Struct { solidity_type, .. } => solidity_type,
+        }
+    }
+
+    /// Resolve the structs named in the Solidity type against the declared structs, and coerce the value of a struct
+    /// type
+    pub fn resolve_structs(&self, structs: &SolStructs) -> Result<Self, FunctionArgumentError> {
+        match self {
+            FunctionArgumentSource::Value(_) => Ok(self.clone()),
+            FunctionArgumentSource::Reference {
+                solidity_type,
+                reference,
+            } => Ok(FunctionArgumentSource::Reference {
+                solidity_type: structs.resolve(solidity_type)?,
+                reference: reference.clone(),
+            }),
+            FunctionArgumentSource::Struct {
+                solidity_type,
+                value,
+            } => Self::from_value(structs.resolve(solidity_type)?, value.clone()),
+        }
+    }
+
+    /// Return the function argument, resolving the output reference against saved outputs
+    pub fn resolve(
+        &self,
+        outputs: &OutputValues,
+    ) -> Result<FunctionArgument, FunctionArgumentError> {
+        match self {
+            FunctionArgumentSource::Value(function_argument) => Ok(function_argument.clone()),
+            FunctionArgumentSource::Reference {
+                solidity_type,
+                reference,
+            } => {
+                let token = reference.resolve(outputs)?;
+                let solidity_value = solidity_type.coerce_token(&token)?;
+
+                Ok(FunctionArgument {
+                    solidity_type: solidity_type.clone(),
+                    solidity_value,
+                })
+            }
+            FunctionArgumentSource::Struct { solidity_type, .. } => {
+                Err(FunctionArgumentError::UnresolvedStruct(
+                    solidity_type
+                        .unresolved_struct()
+                        .unwrap_or_default()
+                        .to_string(),
+                ))
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for FunctionArgumentSource {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct FunctionArgumentSourceHelper {
+            #[serde(alias = "type")]
+            solidity_type: DynSolTypeWrapper,
+            #[serde(alias = "value")]
+            solidity_value: Value,
+        }
+
+        let helper = FunctionArgumentSourceHelper::deserialize(deserializer)?;
+
+        Self::from_value(helper.solidity_type, helper.solidity_value)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum FunctionArgumentError {
     #[error("convert DynSolValue to ethers::abi::Token error {:#?}", .0)]
     ConvertToEthersAbiTokenError(DynSolValue),
-    #[error("convert from I256 value {:#?} to U256 error", .0)]
-    ConvertFromUintToIntError(ethers::types::I256),
+    #[error(transparent)]
+    AlloyConverterError(#[from] AlloyConverterError),
     #[error("not supported type: {:#?}", .0)]
     NotSupportedType(DynSolValue),
+    #[error("invalid value of type `{solidity_type}`: {message}")]
+    InvalidValue {
+        solidity_type: String,
+        message: String,
+    },
+    #[error("struct `{}` is not resolved against the declared structs", .0)]
+    UnresolvedStruct(String),
+    #[error(transparent)]
+    SolStructError(#[from] SolStructError),
+    #[error(transparent)]
+    OutputReferenceError(#[from] OutputReferenceError),
 }
 
 #[cfg(test)]
@@ -252,6 +410,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_convert_int_function_arguments_to_ethers_abi_token() {
+        let content = r#"
+            - type: int24
+              value: -887272
+            - type: int8
+              value: 127
+        "#;
+
+        let function_arguments: Vec<FunctionArgument> = serde_yaml::from_str(content).unwrap();
+        assert_eq!(
+            function_arguments[0].to_ethers_abi_token().unwrap(),
+            Token::Int(ethers::types::I256::from(-887272).into_raw())
+        );
+        assert_eq!(
+            function_arguments[1].to_ethers_abi_token().unwrap(),
+            Token::Int(127.into())
+        );
+    }
+
+    #[test]
+    fn can_resolve_function_argument_reference() {
+        use super::FunctionArgumentSource;
+        use crate::types::{OutputValues, VariableName};
+
+        let content = r#"
+            - type: address
+              value: ${{ outputs.POOL_ADDRESS }}
+            - type: uint24
+              value: 3000
+        "#;
+
+        let function_arguments: Vec<FunctionArgumentSource> =
+            serde_yaml::from_str(content).unwrap();
+        assert_matches!(
+            &function_arguments[0],
+            FunctionArgumentSource::Reference { .. }
+        );
+        assert_matches!(&function_arguments[1], FunctionArgumentSource::Value(_));
+
+        let mut outputs = OutputValues::new();
+        // unresolved reference
+        assert!(function_arguments[0].resolve(&outputs).is_err());
+
+        outputs.insert(
+            VariableName::from_str("POOL_ADDRESS").unwrap(),
+            Token::Address(H160::repeat_byte(1)),
+        );
+        let function_argument = function_arguments[0].resolve(&outputs).unwrap();
+        assert_eq!(
+            function_argument.to_ethers_abi_token().unwrap(),
+            Token::Address(H160::repeat_byte(1))
+        );
+    }
+
     proptest! {
         #[test]
         fn can_encode_address_function_arguments(address in DynSolValue::type_strategy(&DynSolType::Address)) {
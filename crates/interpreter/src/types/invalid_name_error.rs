@@ -0,0 +1,48 @@
+use thiserror::Error;
+
+/// Error when a job, step, workflow or variable name doesn't match the name pattern `^[a-zA-Z0-9][a-zA-Z0-9_-]{1,199}$`
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+#[error("invalid {kind} `{name}`, must start with an alphanumeric character, followed by 1 to 199 alphanumeric, `_` or `-` characters")]
+pub struct InvalidNameError {
+    /// Kind of the name, e.g. `job name`
+    pub kind: &'static str,
+    pub name: String,
+}
+
+impl InvalidNameError {
+    pub fn new<N: Into<String>>(kind: &'static str, name: N) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+        }
+    }
+}
+
+/// Implement `FromStr` and `Deserialize` for a name type, both checking that the name matches its validated pattern.
+/// The kind names the type in [`InvalidNameError`], e.g. `job name`.
+macro_rules! impl_name {
+    ($name:ty, $kind:literal) => {
+        impl std::str::FromStr for $name {
+            type Err = $crate::types::InvalidNameError;
+
+            /// Parse the name, which must match the name pattern
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let name = Self(s.to_string());
+                serde_valid::Validate::validate(&name)
+                    .map_err(|_| $crate::types::InvalidNameError::new($kind, s))?;
+                Ok(name)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let name = <String as serde::Deserialize>::deserialize(deserializer)?;
+                <Self as std::str::FromStr>::from_str(&name).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+pub(crate) use impl_name;
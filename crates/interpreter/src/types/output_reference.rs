@@ -0,0 +1,299 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use alloy_dyn_abi::DynSolType;
+use ethers::abi::Token;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use crate::job::JobName;
+
+use super::VariableName;
+
+lazy_static! {
+    static ref OUTPUT_REFERENCE_REGEX: Regex = Regex::new(
+        r"^\$\{\{\s*(?:jobs\.([a-zA-Z0-9][a-zA-Z0-9_-]{1,199})\.)?outputs\.([a-zA-Z0-9][a-zA-Z0-9_-]{1,199})(?:\[(\d+)\])?\s*\}\}$"
+    )
+    .unwrap();
+}
+
+/// Saved step outputs, indexed by the `save_as` variable name of the step
+pub type SavedOutputs = HashMap<VariableName, Token>;
+
+/// Outputs which a step can reference: outputs saved by earlier steps of its job, and outputs of the ancestor jobs
+/// of its job in the workflow DAG
+#[derive(Debug, Clone, Default)]
+pub struct OutputValues {
+    saved: SavedOutputs,
+    jobs: HashMap<JobName, SavedOutputs>,
+}
+
+impl OutputValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create output values which can reference outputs of the given ancestor jobs
+    pub fn with_job_outputs(jobs: HashMap<JobName, SavedOutputs>) -> Self {
+        Self {
+            saved: SavedOutputs::new(),
+            jobs,
+        }
+    }
+
+    /// Save an output of the current job
+    pub fn insert(&mut self, name: VariableName, value: Token) -> Option<Token> {
+        self.saved.insert(name, value)
+    }
+
+    /// Whether the current job saved an output with the name
+    pub fn contains_key(&self, name: &VariableName) -> bool {
+        self.saved.contains_key(name)
+    }
+
+    /// Get an output saved by the current job
+    pub fn get(&self, name: &VariableName) -> Option<&Token> {
+        self.saved.get(name)
+    }
+
+    /// Get outputs saved by the current job
+    pub fn saved(&self) -> &SavedOutputs {
+        &self.saved
+    }
+
+    /// Get outputs of an ancestor job
+    pub fn job_outputs(&self, job_name: &JobName) -> Option<&SavedOutputs> {
+        self.jobs.get(job_name)
+    }
+}
+
+/// OutputReference refers to the saved output of an earlier step in format `${{ outputs.VARIABLE_NAME }}`, or to an
+/// output of an ancestor job declared through `depends_on` in format `${{ jobs.JOB_NAME.outputs.VARIABLE_NAME }}`.
+/// An item of an output with multiple values is referred by its index, e.g. `${{ outputs.VARIABLE_NAME[0] }}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputReference {
+    pub job: Option<JobName>,
+    pub name: VariableName,
+    pub index: Option<usize>,
+}
+
+impl OutputReference {
+    /// Whether the string uses the output reference syntax
+    pub fn is_reference<S: AsRef<str>>(value: S) -> bool {
+        value.as_ref().trim_start().starts_with("${{")
+    }
+
+    /// Get the referenced value from saved outputs. A job output can only be resolved if the job is an ancestor of
+    /// the current job.
+    pub fn resolve(&self, outputs: &OutputValues) -> Result<Token, OutputReferenceError> {
+        let value = match &self.job {
+            Some(job_name) => outputs
+                .job_outputs(job_name)
+                .ok_or(OutputReferenceError::JobNotAncestor(job_name.clone()))?
+                .get(&self.name)
+                .ok_or(OutputReferenceError::JobOutputNotFound(
+                    job_name.clone(),
+                    self.name.clone(),
+                ))?,
+            None => outputs
+                .get(&self.name)
+                .ok_or(OutputReferenceError::OutputNotFound(self.name.clone()))?,
+        };
+
+        match (self.index, value) {
+            (None, value) => Ok(value.clone()),
+            (Some(index), Token::Tuple(items))
+            | (Some(index), Token::Array(items))
+            | (Some(index), Token::FixedArray(items)) => items
+                .get(index)
+                .cloned()
+                .ok_or(OutputReferenceError::IndexOutOfBounds(self.to_string())),
+            (Some(_), _) => Err(OutputReferenceError::NotIndexable(self.to_string())),
+        }
+    }
+}
+
+impl FromStr for OutputReference {
+    type Err = OutputReferenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let captures = OUTPUT_REFERENCE_REGEX
+            .captures(s.trim())
+            .ok_or(OutputReferenceError::InvalidReference(s.to_string()))?;
+
+        let job = captures
+            .get(1)
+            .map(|job_name| JobName::from_str(job_name.as_str()))
+            .transpose()
+            .map_err(|_| OutputReferenceError::InvalidReference(s.to_string()))?;
+        let name = VariableName::from_str(&captures[2])
+            .map_err(|_| OutputReferenceError::InvalidReference(s.to_string()))?;
+        let index = captures
+            .get(3)
+            .map(|index| index.as_str().parse::<usize>())
+            .transpose()
+            .map_err(|_| OutputReferenceError::InvalidReference(s.to_string()))?;
+
+        Ok(Self { job, name, index })
+    }
+}
+
+impl Display for OutputReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${{{{ ")?;
+        if let Some(job_name) = &self.job {
+            write!(f, "jobs.{}.", job_name.as_str())?;
+        }
+        write!(f, "outputs.{}", self.name.as_str())?;
+        if let Some(index) = self.index {
+            write!(f, "[{}]", index)?;
+        }
+        write!(f, " }}}}")
+    }
+}
+
+impl<'de> Deserialize<'de> for OutputReference {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        OutputReference::from_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OutputReferenceError {
+    #[error(
+        "invalid output reference {:#?}, expected format `${{{{ outputs.VARIABLE_NAME }}}}` or `${{{{ jobs.JOB_NAME.outputs.VARIABLE_NAME }}}}`",
+        .0
+    )]
+    InvalidReference(String),
+    #[error("output {:#?} is not saved by any earlier step", .0)]
+    OutputNotFound(VariableName),
+    #[error("job {:#?} is not an ancestor job declared through `depends_on`, or it has not finished", .0)]
+    JobNotAncestor(JobName),
+    #[error("output {:#?} is not saved by job {:#?}", .1, .0)]
+    JobOutputNotFound(JobName, VariableName),
+    #[error("index of output reference {} is out of bounds", .0)]
+    IndexOutOfBounds(String),
+    #[error("output of reference {} does not have multiple values", .0)]
+    NotIndexable(String),
+    #[error("cannot coerce output value {:#?} into Solidity type {:#?}", .1, .0)]
+    TypeMismatch(DynSolType, Token),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_matches};
+    use ethers::types::{H160, U256};
+
+    #[test]
+    fn can_parse_output_reference() {
+        let reference = OutputReference::from_str("${{ outputs.POOL_ADDRESS }}").unwrap();
+        assert_eq!(reference.name.as_str(), "POOL_ADDRESS");
+        assert_eq!(reference.index, None);
+
+        let reference = OutputReference::from_str("${{outputs.SLOT0[1]}}").unwrap();
+        assert_eq!(reference.name.as_str(), "SLOT0");
+        assert_eq!(reference.index, Some(1));
+        assert_eq!(reference.to_string(), "${{ outputs.SLOT0[1] }}");
+
+        let reference =
+            OutputReference::from_str("${{ jobs.discover_pools.outputs.SLOT0[0] }}").unwrap();
+        assert_eq!(reference.job.as_deref().unwrap(), "discover_pools");
+        assert_eq!(reference.name.as_str(), "SLOT0");
+        assert_eq!(reference.index, Some(0));
+        assert_eq!(
+            reference.to_string(),
+            "${{ jobs.discover_pools.outputs.SLOT0[0] }}"
+        );
+
+        assert_err!(OutputReference::from_str("${{ POOL_ADDRESS }}"));
+        assert_err!(OutputReference::from_str(
+            "${{ jobs.outputs.POOL_ADDRESS }}"
+        ));
+        assert_err!(OutputReference::from_str("${{ outputs.POOL ADDRESS }}"));
+        assert!(!OutputReference::is_reference(
+            "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+        ));
+    }
+
+    #[test]
+    fn can_resolve_output_reference() {
+        let mut outputs = OutputValues::new();
+        outputs.insert(
+            VariableName::from_str("POOL_ADDRESS").unwrap(),
+            Token::Address(H160::repeat_byte(1)),
+        );
+        outputs.insert(
+            VariableName::from_str("SLOT0").unwrap(),
+            Token::Tuple(vec![Token::Uint(U256::from(1)), Token::Bool(true)]),
+        );
+
+        let reference = OutputReference::from_str("${{ outputs.POOL_ADDRESS }}").unwrap();
+        assert_matches!(reference.resolve(&outputs), Ok(Token::Address(_)));
+
+        let reference = OutputReference::from_str("${{ outputs.SLOT0[1] }}").unwrap();
+        assert_matches!(reference.resolve(&outputs), Ok(Token::Bool(true)));
+
+        let reference = OutputReference::from_str("${{ outputs.SLOT0[2] }}").unwrap();
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::IndexOutOfBounds(_))
+        );
+
+        let reference = OutputReference::from_str("${{ outputs.POOL_ADDRESS[0] }}").unwrap();
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::NotIndexable(_))
+        );
+
+        let reference = OutputReference::from_str("${{ outputs.UNKNOWN }}").unwrap();
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::OutputNotFound(_))
+        );
+    }
+
+    #[test]
+    fn can_resolve_job_output_reference() {
+        let mut discover_pools_outputs = SavedOutputs::new();
+        discover_pools_outputs.insert(
+            VariableName::from_str("POOL_ADDRESS").unwrap(),
+            Token::Address(H160::repeat_byte(1)),
+        );
+        let mut jobs = HashMap::new();
+        jobs.insert(
+            JobName::from_str("discover_pools").unwrap(),
+            discover_pools_outputs,
+        );
+        let outputs = OutputValues::with_job_outputs(jobs);
+
+        let reference =
+            OutputReference::from_str("${{ jobs.discover_pools.outputs.POOL_ADDRESS }}").unwrap();
+        assert_matches!(reference.resolve(&outputs), Ok(Token::Address(_)));
+
+        // job outputs are not saved outputs of the current job
+        let reference = OutputReference::from_str("${{ outputs.POOL_ADDRESS }}").unwrap();
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::OutputNotFound(_))
+        );
+
+        let reference =
+            OutputReference::from_str("${{ jobs.discover_pools.outputs.UNKNOWN }}").unwrap();
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::JobOutputNotFound(_, _))
+        );
+
+        let reference = OutputReference::from_str("${{ jobs.swap.outputs.POOL_ADDRESS }}").unwrap();
+        assert_matches!(
+            reference.resolve(&outputs),
+            Err(OutputReferenceError::JobNotAncestor(_))
+        );
+    }
+}
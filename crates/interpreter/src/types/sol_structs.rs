@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use alloy_dyn_abi::DynSolType;
+use ethers::abi::{Param, ParamType};
+use thiserror::Error;
+
+use super::DynSolTypeWrapper;
+
+/// Maximum nesting depth of struct fields, so a recursive struct fails instead of overflowing the stack
+const MAX_STRUCT_DEPTH: usize = 32;
+
+/// Solidity structs indexed by name, which are declared in setup or in a contract ABI. Arguments of a struct type are
+/// written as maps keyed by field name, and returned structs are decoded into such maps.
+#[derive(Debug, Clone, Default)]
+pub struct SolStructs {
+    structs: HashMap<String, SolStruct>,
+}
+
+/// Fields of a Solidity struct, their types may name other structs
+#[derive(Debug, Clone)]
+pub struct SolStruct {
+    pub prop_names: Vec<String>,
+    pub types: Vec<DynSolType>,
+}
+
+impl SolStructs {
+    /// Parse struct declarations like `struct Order { address maker; uint256 amount; }`
+    pub fn from_declarations<S: AsRef<str>>(declarations: &[S]) -> Result<Self, SolStructError> {
+        let mut structs = Self::default();
+        for declaration in declarations {
+            let (name, sol_struct) = Self::parse_declaration(declaration.as_ref())?;
+            if structs.structs.insert(name.clone(), sol_struct).is_some() {
+                return Err(SolStructError::DuplicateStruct(name));
+            }
+        }
+        Ok(structs)
+    }
+
+    fn parse_declaration(declaration: &str) -> Result<(String, SolStruct), SolStructError> {
+        let invalid = || SolStructError::InvalidDeclaration(declaration.to_string());
+
+        let (name, fields) = declaration
+            .trim()
+            .strip_prefix("struct")
+            .and_then(|declaration| declaration.split_once('{'))
+            .ok_or_else(invalid)?;
+        let fields = fields.trim().strip_suffix('}').ok_or_else(invalid)?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+
+        let mut sol_struct = SolStruct {
+            prop_names: vec![],
+            types: vec![],
+        };
+        for field in fields
+            .split(';')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+        {
+            let (field_type, field_name) =
+                field.rsplit_once(char::is_whitespace).ok_or_else(invalid)?;
+            let field_type = DynSolTypeWrapper::parse(field_type.trim()).map_err(|message| {
+                SolStructError::InvalidFieldType {
+                    name: name.to_string(),
+                    field: field_name.to_string(),
+                    message,
+                }
+            })?;
+            sol_struct.prop_names.push(field_name.to_string());
+            sol_struct.types.push(field_type.inner_ref().clone());
+        }
+        if sol_struct.prop_names.is_empty() {
+            return Err(invalid());
+        }
+        Ok((name.to_string(), sol_struct))
+    }
+
+    /// Collect the structs of a JSON ABI, which are the tuple parameters with an `internalType` like
+    /// `struct ISwapRouter.ExactInputSingleParams`
+    pub fn from_json_abi(abi: &serde_json::Value) -> Self {
+        let mut structs = Self::default();
+        for item in abi.as_array().into_iter().flatten() {
+            for params in ["inputs", "outputs"] {
+                for param in item
+                    .get(params)
+                    .and_then(|p| p.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    structs.collect_json_param(param);
+                }
+            }
+        }
+        structs
+    }
+
+    /// Collect the structs of a JSON ABI parameter and return its type, which names its struct if any
+    fn collect_json_param(&mut self, param: &serde_json::Value) -> Option<String> {
+        let kind = param.get("type")?.as_str()?;
+        let Some(components) = param.get("components").and_then(|c| c.as_array()) else {
+            return Some(kind.to_string());
+        };
+
+        let suffix = kind.strip_prefix("tuple").unwrap_or_default();
+        let field_types = components
+            .iter()
+            .map(|component| self.collect_json_param(component))
+            .collect::<Option<Vec<String>>>()?;
+
+        let internal_type = param
+            .get("internalType")
+            .and_then(|internal_type| internal_type.as_str());
+        match internal_type.and_then(Self::struct_name) {
+            Some(name) => {
+                let sol_struct = SolStruct {
+                    prop_names: components
+                        .iter()
+                        .map(|component| {
+                            component
+                                .get("name")
+                                .and_then(|name| name.as_str())
+                                .unwrap_or_default()
+                                .to_string()
+                        })
+                        .collect(),
+                    types: field_types
+                        .iter()
+                        .map(|field_type| {
+                            DynSolTypeWrapper::parse(field_type)
+                                .ok()
+                                .map(|field_type| field_type.inner_ref().clone())
+                        })
+                        .collect::<Option<Vec<DynSolType>>>()?,
+                };
+                self.structs.insert(name.to_string(), sol_struct);
+                Some(format!("{}{}", name, suffix))
+            }
+            None => Some(format!("({}){}", field_types.join(","), suffix)),
+        }
+    }
+
+    /// Return the struct name of an internal type like `struct ISwapRouter.ExactInputSingleParams[]`
+    fn struct_name(internal_type: &str) -> Option<&str> {
+        let qualified_name = internal_type.strip_prefix("struct ")?;
+        let qualified_name = qualified_name
+            .split_once('[')
+            .map_or(qualified_name, |(name, _)| name);
+        qualified_name.rsplit('.').next()
+    }
+
+    /// Return the internal type of a parameter of the struct, e.g. `struct Order[]` for an array of `Order`
+    pub fn internal_type(name: &str, kind: &ParamType) -> String {
+        fn array_suffix(kind: &ParamType) -> String {
+            match kind {
+                ParamType::Array(item_kind) => format!("{}[]", array_suffix(item_kind)),
+                ParamType::FixedArray(item_kind, size) => {
+                    format!("{}[{}]", array_suffix(item_kind), size)
+                }
+                _ => String::new(),
+            }
+        }
+        format!("struct {}{}", name, array_suffix(kind))
+    }
+
+    /// Add the structs which are not declared yet
+    pub fn extend(&mut self, other: &Self) {
+        for (name, sol_struct) in &other.structs {
+            self.structs
+                .entry(name.clone())
+                .or_insert_with(|| sol_struct.clone());
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SolStruct> {
+        self.structs.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.structs.is_empty()
+    }
+
+    /// Return the Solidity type of an ABI parameter, which names its struct if the struct is declared
+    pub fn param_type(&self, param: &Param) -> Result<DynSolTypeWrapper, SolStructError> {
+        let internal_type = param.internal_type.as_deref().unwrap_or_default();
+        let solidity_type = match Self::struct_name(internal_type) {
+            Some(name) if self.structs.contains_key(name) => {
+                Self::internal_type(name, &param.kind)["struct ".len()..].to_string()
+            }
+            _ => param.kind.to_string(),
+        };
+        let solidity_type = DynSolTypeWrapper::parse(&solidity_type)
+            .map_err(|message| SolStructError::InvalidType(solidity_type, message))?;
+        self.resolve(&solidity_type)
+    }
+
+    /// Replace the structs named in the Solidity type by their fields
+    pub fn resolve(
+        &self,
+        solidity_type: &DynSolTypeWrapper,
+    ) -> Result<DynSolTypeWrapper, SolStructError> {
+        self.resolve_type(solidity_type.inner_ref(), 0)
+            .map(DynSolTypeWrapper::from)
+    }
+
+    fn resolve_type(
+        &self,
+        sol_type: &DynSolType,
+        depth: usize,
+    ) -> Result<DynSolType, SolStructError> {
+        match sol_type {
+            DynSolType::CustomStruct { name, tuple, .. } if tuple.is_empty() => {
+                if depth > MAX_STRUCT_DEPTH {
+                    return Err(SolStructError::RecursiveStruct(name.clone()));
+                }
+                let sol_struct = self
+                    .structs
+                    .get(name)
+                    .ok_or_else(|| SolStructError::StructNotDeclared(name.clone()))?;
+                Ok(DynSolType::CustomStruct {
+                    name: name.clone(),
+                    prop_names: sol_struct.prop_names.clone(),
+                    tuple: sol_struct
+                        .types
+                        .iter()
+                        .map(|field_type| self.resolve_type(field_type, depth + 1))
+                        .collect::<Result<Vec<DynSolType>, SolStructError>>()?,
+                })
+            }
+            DynSolType::CustomStruct {
+                name,
+                prop_names,
+                tuple,
+            } => Ok(DynSolType::CustomStruct {
+                name: name.clone(),
+                prop_names: prop_names.clone(),
+                tuple: tuple
+                    .iter()
+                    .map(|field_type| self.resolve_type(field_type, depth))
+                    .collect::<Result<Vec<DynSolType>, SolStructError>>()?,
+            }),
+            DynSolType::Tuple(tuple) => tuple
+                .iter()
+                .map(|item_type| self.resolve_type(item_type, depth))
+                .collect::<Result<Vec<DynSolType>, SolStructError>>()
+                .map(DynSolType::Tuple),
+            DynSolType::Array(item_type) => Ok(DynSolType::Array(Box::new(
+                self.resolve_type(item_type, depth)?,
+            ))),
+            DynSolType::FixedArray(item_type, size) => Ok(DynSolType::FixedArray(
+                Box::new(self.resolve_type(item_type, depth)?),
+                *size,
+            )),
+            sol_type => Ok(sol_type.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SolStructError {
+    #[error("invalid struct declaration `{}`, expected `struct Name {{ type field; ... }}`", .0)]
+    InvalidDeclaration(String),
+    #[error("struct `{}` is declared twice", .0)]
+    DuplicateStruct(String),
+    #[error("invalid type of field `{field}` of struct `{name}`: {message}")]
+    InvalidFieldType {
+        name: String,
+        field: String,
+        message: String,
+    },
+    #[error("invalid Solidity type `{}`: {}", .0, .1)]
+    InvalidType(String, String),
+    #[error("struct `{}` is not declared in setup or in the contract ABI", .0)]
+    StructNotDeclared(String),
+    #[error("struct `{}` contains itself", .0)]
+    RecursiveStruct(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_matches;
+
+    #[test]
+    fn can_resolve_nested_structs() {
+        let structs = SolStructs::from_declarations(&[
+            "struct Order { address maker; Asset[2] assets; }",
+            "struct Asset { address token; uint256 amount; }",
+        ])
+        .unwrap();
+
+        let sol_type = structs
+            .resolve(&DynSolTypeWrapper::parse("Order[]").unwrap())
+            .unwrap();
+        assert_eq!(sol_type.to_string(), "(address,(address,uint256)[2])[]");
+        assert_eq!(sol_type.unresolved_struct(), None);
+
+        assert_matches!(
+            structs.resolve(&DynSolTypeWrapper::parse("Pool").unwrap()),
+            Err(SolStructError::StructNotDeclared(name)) if name == "Pool"
+        );
+        assert_matches!(
+            SolStructs::from_declarations(&["struct Node { Node next; }"])
+                .unwrap()
+                .resolve(&DynSolTypeWrapper::parse("Node").unwrap()),
+            Err(SolStructError::RecursiveStruct(_))
+        );
+        assert_matches!(
+            SolStructs::from_declarations(&["struct Empty {}"]),
+            Err(SolStructError::InvalidDeclaration(_))
+        );
+    }
+
+    #[test]
+    fn can_collect_structs_of_json_abi() {
+        let abi = serde_json::json!([{
+            "type": "function",
+            "name": "exactInputSingle",
+            "inputs": [{
+                "name": "params",
+                "type": "tuple",
+                "internalType": "struct ISwapRouter.ExactInputSingleParams",
+                "components": [
+                    {"name": "tokenIn", "type": "address", "internalType": "address"},
+                    {"name": "fee", "type": "uint24", "internalType": "uint24"}
+                ]
+            }],
+            "outputs": [{"name": "amountOut", "type": "uint256", "internalType": "uint256"}],
+            "stateMutability": "payable"
+        }]);
+        let structs = SolStructs::from_json_abi(&abi);
+
+        let sol_struct = structs.get("ExactInputSingleParams").unwrap();
+        assert_eq!(sol_struct.prop_names, vec!["tokenIn", "fee"]);
+        assert_eq!(
+            sol_struct.types,
+            vec![DynSolType::Address, DynSolType::Uint(24)]
+        );
+    }
+}
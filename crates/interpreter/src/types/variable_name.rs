@@ -1,7 +1,37 @@
-use derive_more::{Deref, FromStr};
-use serde::{Deserialize, Serialize};
+use derive_more::Deref;
+use serde::Serialize;
 use serde_valid::Validate;
 
+use super::impl_name;
+
 /// VariableName can only contains alphanumeric, `-`, `_` characters, up to a maximum of 200 characters.
-#[derive(Debug, Deref, Deserialize, Serialize, Validate, Eq, PartialEq, Hash, Clone, FromStr)]
+#[derive(Debug, Deref, Serialize, Validate, Eq, PartialEq, Hash, Clone)]
 pub struct VariableName(#[validate(pattern = r#"^[a-zA-Z0-9][a-zA-Z0-9_-]{1,199}$"#)] String);
+
+impl_name!(VariableName, "variable name");
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::types::InvalidNameError;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn cannot_parse_invalid_variable_name() {
+        assert_ok!(VariableName::from_str("UNISWAP_V3_FACTORY"));
+        assert_ok!(serde_yaml::from_str::<VariableName>("pool-address"));
+
+        for variable_name in ["a", "POOL ADDRESS", "_POOL", "{{POOL}}"] {
+            assert_err!(VariableName::from_str(variable_name));
+            assert_err!(serde_yaml::from_str::<VariableName>(&format!(
+                "\"{}\"",
+                variable_name
+            )));
+        }
+
+        let error = VariableName::from_str("_POOL").unwrap_err();
+        assert_eq!(error, InvalidNameError::new("variable name", "_POOL"));
+    }
+}
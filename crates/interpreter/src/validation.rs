@@ -0,0 +1,6 @@
+mod diagnostic;
+pub use diagnostic::*;
+mod yaml_locator;
+pub use yaml_locator::*;
+mod playbook_validator;
+pub use playbook_validator::*;
@@ -0,0 +1,39 @@
+use std::{fmt::Display, path::PathBuf};
+
+use serde::Serialize;
+
+use super::Location;
+
+/// A problem found when validating a playbook, located in the playbook source
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// Playbook file, `None` when validating a string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    /// Path of the invalid value, e.g. `jobs.get_pool.steps[0].name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub message: String,
+}
+
+/// Display in `file:line:column: message (at path)` format
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, "{}:{}:", location.line, location.column)?;
+        }
+        if self.file.is_some() || self.location.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "{}", self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " (at {})", path)?;
+        }
+        Ok(())
+    }
+}
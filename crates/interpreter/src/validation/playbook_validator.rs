@@ -0,0 +1,1198 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_yaml::Value;
+use thiserror::Error;
+
+use crate::{
+    job::JobName,
+    playbook::{
+        ContractConfig, Playbook, PlaybookDir, PlaybookError, RpcProviderConfig, RpcProviderError,
+        Setup, SignerConfig, SignerConfigError, Variable, Version,
+    },
+    step::{
+        DeployContractError, FunctionCall, FunctionCallError, StepArguments, StepConfig, StepName,
+    },
+    types::{
+        DynSolTypeWrapper, FunctionArgumentSource, InvalidNameError, OutputReference, VariableName,
+    },
+    workflow::{JobConfigHelper, WorkflowName},
+};
+
+use super::{Diagnostic, Location, YamlLocator, YamlPath};
+
+#[derive(Debug, Error)]
+pub enum PlaybookValidationError {
+    #[error(transparent)]
+    PlaybookError(Box<PlaybookError>),
+    #[error("playbook is not valid, found {} problems", .0.len())]
+    InvalidPlaybook(Vec<Diagnostic>),
+}
+
+impl From<PlaybookError> for PlaybookValidationError {
+    fn from(error: PlaybookError) -> Self {
+        Self::PlaybookError(Box::new(error))
+    }
+}
+
+/// Outputs saved by the steps of a job, and the references of its steps to outputs of other jobs
+#[derive(Debug, Default)]
+struct JobSummary {
+    outputs: HashSet<String>,
+    job_references: Vec<(YamlPath, OutputReference)>,
+}
+
+/// A job listed in a workflow, with the jobs it depends on
+struct WorkflowJob {
+    name: String,
+    depends_on: Vec<String>,
+    path: YamlPath,
+}
+
+/// PlaybookValidator runs every check on a playbook without executing it, and reports all problems at once instead
+/// of stopping at the first one
+pub struct PlaybookValidator {
+    file: Option<PathBuf>,
+    locator: YamlLocator,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl PlaybookValidator {
+    /// Validate a playbook file
+    pub fn validate_file<P: AsRef<Path>>(file_path: P) -> std::io::Result<Vec<Diagnostic>> {
+        let content = std::fs::read_to_string(&file_path)?;
+
+        Ok(Self::validate(
+            Some(file_path.as_ref().to_path_buf()),
+            &content,
+        ))
+    }
+
+    /// Check a playbook file before it is executed. A file that cannot be read or is not YAML fails with the
+    /// [`PlaybookError`], any other problem fails with all diagnostics of the playbook.
+    pub fn check_file<P: AsRef<Path>>(file_path: P) -> Result<(), PlaybookValidationError> {
+        let content = std::fs::read_to_string(&file_path).map_err(PlaybookError::from)?;
+        serde_yaml::from_str::<Value>(&content).map_err(PlaybookError::from)?;
+
+        let diagnostics = Self::validate(Some(file_path.as_ref().to_path_buf()), &content);
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(PlaybookValidationError::InvalidPlaybook(diagnostics))
+        }
+    }
+
+    /// Validate playbook content, diagnostics are not attached to a file
+    pub fn validate_str<S: AsRef<str>>(content: S) -> Vec<Diagnostic> {
+        Self::validate(None, content.as_ref())
+    }
+
+    fn validate(file: Option<PathBuf>, content: &str) -> Vec<Diagnostic> {
+        let mut validator = Self {
+            file,
+            locator: YamlLocator::new(content),
+            diagnostics: vec![],
+        };
+        PlaybookDir::scope(validator.file.clone(), || validator.check_playbook(content));
+
+        let mut diagnostics = validator.diagnostics;
+        diagnostics.sort_by_key(|diagnostic| {
+            diagnostic
+                .location
+                .map(|location| (location.line, location.column))
+        });
+        diagnostics
+    }
+
+    fn check_playbook(&mut self, content: &str) {
+        let raw_playbook: Value = match serde_yaml::from_str(content) {
+            Ok(raw_playbook) => raw_playbook,
+            Err(error) => return self.report_yaml_error(&error),
+        };
+
+        // variables must be valid before they can be rendered into the playbook
+        let setup = self.check_setup(&raw_playbook);
+        if !self.diagnostics.is_empty() {
+            return;
+        }
+
+        let content = match Playbook::preprocess(content) {
+            Ok(content) => content,
+            Err(error) => return self.report_preprocess_error(&error),
+        };
+        let playbook: Value = match serde_yaml::from_str(&content) {
+            Ok(playbook) => playbook,
+            Err(error) => return self.report_yaml_error(&error),
+        };
+        if !playbook.is_mapping() {
+            return self.report(&YamlPath::root(), "expected a mapping of playbook fields");
+        }
+
+        self.check_version(&playbook);
+        let jobs = self.check_jobs(&playbook, setup.as_ref());
+        self.check_workflows(&playbook, &jobs);
+
+        // anything the granular checks missed
+        if self.diagnostics.is_empty() {
+            if let Err(error) = serde_yaml::from_str::<Playbook>(&content) {
+                self.report_yaml_error(&error);
+            }
+        }
+    }
+
+    fn check_setup(&mut self, playbook: &Value) -> Option<Setup> {
+        let setup = playbook.get("setup").filter(|setup| !setup.is_null())?;
+        let path = YamlPath::root().key("setup");
+        let diagnostics_count = self.diagnostics.len();
+
+        self.check_setup_entries::<Variable>(setup, &path, "variables");
+        self.check_setup_entries::<RpcProviderConfig>(setup, &path, "rpc_providers");
+        self.check_setup_entries::<SignerConfig>(setup, &path, "signers");
+        self.check_setup_entries::<ContractConfig>(setup, &path, "contracts");
+
+        if self.diagnostics.len() > diagnostics_count {
+            return None;
+        }
+        match Setup::deserialize(setup) {
+            Ok(setup) => Some(setup),
+            Err(error) => {
+                self.report(&path, error);
+                None
+            }
+        }
+    }
+
+    fn check_setup_entries<T: DeserializeOwned>(
+        &mut self,
+        setup: &Value,
+        path: &YamlPath,
+        section: &str,
+    ) {
+        let Some(entries) = setup.get(section).filter(|entries| !entries.is_null()) else {
+            return;
+        };
+        let path = path.key(section);
+
+        for (name, value) in self.mapping_entries(entries, &path) {
+            let entry_path = path.key(&name);
+
+            self.check_name::<VariableName>(&entry_path, &name);
+            if let Err(error) = T::deserialize(value) {
+                self.report(&entry_path, error);
+            }
+        }
+    }
+
+    fn check_version(&mut self, playbook: &Value) {
+        match playbook.get("version") {
+            Some(version) => {
+                if let Err(error) = Version::deserialize(version) {
+                    self.report(
+                        &YamlPath::root().key("version"),
+                        format!("unsupported playbook version: {}", error),
+                    );
+                }
+            }
+            None => self.report(&YamlPath::root(), "missing field `version`"),
+        }
+    }
+
+    fn check_jobs(
+        &mut self,
+        playbook: &Value,
+        setup: Option<&Setup>,
+    ) -> HashMap<String, JobSummary> {
+        let mut jobs = HashMap::new();
+        let path = YamlPath::root().key("jobs");
+
+        let Some(jobs_value) = playbook.get("jobs") else {
+            self.report(&YamlPath::root(), "missing field `jobs`");
+            return jobs;
+        };
+
+        for (job_name, job) in self.mapping_entries(jobs_value, &path) {
+            let job_path = path.key(&job_name);
+
+            self.check_name::<JobName>(&job_path, &job_name);
+            let job_summary = self.check_job(job, &job_path, setup);
+            jobs.insert(job_name, job_summary);
+        }
+
+        for job_summary in jobs.values() {
+            for (path, reference) in &job_summary.job_references {
+                let Some(job_name) = &reference.job else {
+                    continue;
+                };
+                match jobs.get(job_name.as_str()) {
+                    None => self.report(
+                        path,
+                        format!(
+                            "`{}` references job `{}` which is not defined",
+                            reference,
+                            job_name.as_str()
+                        ),
+                    ),
+                    Some(referenced_job)
+                        if !referenced_job.outputs.contains(reference.name.as_str()) =>
+                    {
+                        self.report(
+                            path,
+                            format!(
+                                "`{}` references output `{}` which is not saved by job `{}`",
+                                reference,
+                                reference.name.as_str(),
+                                job_name.as_str()
+                            ),
+                        )
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        jobs
+    }
+
+    fn check_job(&mut self, job: &Value, path: &YamlPath, setup: Option<&Setup>) -> JobSummary {
+        let mut job_summary = JobSummary::default();
+        let steps_path = path.key("steps");
+
+        let Some(steps) = job.get("steps") else {
+            self.report(path, "missing field `steps`");
+            return job_summary;
+        };
+        let Some(steps) = steps.as_sequence() else {
+            self.report(&steps_path, "expected a list of steps");
+            return job_summary;
+        };
+
+        let mut step_names = HashSet::new();
+        for (index, step) in steps.iter().enumerate() {
+            let step_path = steps_path.index(index);
+            let diagnostics_count = self.diagnostics.len();
+
+            if let Some(name) = step.get("name") {
+                let name_path = step_path.key("name");
+                match name.as_str() {
+                    Some(name) => {
+                        self.check_name::<StepName>(&name_path, name);
+                        if !step_names.insert(name.to_string()) {
+                            self.report(
+                                &name_path,
+                                format!("duplicate step name `{}` in job", name),
+                            );
+                        }
+                    }
+                    None => self.report(&name_path, "expected a string as step name"),
+                }
+            }
+
+            let save_as = step.get("output").and_then(|output| output.get("save_as"));
+            let save_as_path = step_path.key("output").key("save_as");
+            if let Some(save_as) = save_as {
+                match save_as.as_str() {
+                    Some(save_as) => self.check_name::<VariableName>(&save_as_path, save_as),
+                    None => self.report(&save_as_path, "expected a string as output name"),
+                }
+            }
+
+            self.check_step(step, &step_path, setup, diagnostics_count, &mut job_summary);
+
+            // outputs can only be referenced by later steps
+            if let Some(save_as) = save_as.and_then(Value::as_str) {
+                if !job_summary.outputs.insert(save_as.to_string()) {
+                    self.report(
+                        &save_as_path,
+                        format!(
+                            "output `{}` is already saved by an earlier step of the job",
+                            save_as
+                        ),
+                    );
+                }
+            }
+        }
+
+        job_summary
+    }
+
+    fn check_step(
+        &mut self,
+        step: &Value,
+        path: &YamlPath,
+        setup: Option<&Setup>,
+        diagnostics_count: usize,
+        job_summary: &mut JobSummary,
+    ) {
+        let arguments_path = path.key("arguments");
+
+        // check each argument, so all Solidity type and value mismatches of a step are reported
+        if let Some(arguments) = step.get("arguments") {
+            for key in ["function_arguments", "constructor_arguments"] {
+                let function_arguments = arguments.get(key).and_then(Value::as_sequence);
+                for (index, function_argument) in
+                    function_arguments.into_iter().flatten().enumerate()
+                {
+                    if let Err(error) = FunctionArgumentSource::deserialize(function_argument) {
+                        self.report(
+                            &arguments_path.key(key).index(index),
+                            format!("invalid function argument: {}", error),
+                        );
+                    }
+                }
+            }
+
+            let function_return_types = arguments
+                .get("function_return_types")
+                .and_then(Value::as_sequence);
+            for (index, return_type) in function_return_types.into_iter().flatten().enumerate() {
+                if let Err(error) = DynSolTypeWrapper::deserialize(return_type) {
+                    self.report(
+                        &arguments_path.key("function_return_types").index(index),
+                        format!("invalid function return type: {}", error),
+                    );
+                }
+            }
+        }
+        if self.diagnostics.len() > diagnostics_count {
+            return;
+        }
+
+        let step_config = match StepConfig::deserialize(step) {
+            Ok(step_config) => step_config,
+            Err(error) => return self.report(path, error),
+        };
+
+        if let Some(rpc_provider) = step_config.rpc_provider() {
+            if let Err(RpcProviderError::RpcProviderNotDefined(name)) = rpc_provider.resolve(setup)
+            {
+                self.report(
+                    &arguments_path.key("rpc_provider"),
+                    format!("rpc provider `{}` is not defined in setup", name.as_str()),
+                );
+            }
+        }
+
+        let mut references = vec![];
+        match &step_config.arguments {
+            StepArguments::BlankStep => {}
+            StepArguments::CallContract(call_contract) => {
+                match call_contract.contract_address(setup) {
+                    Ok(contract_address) => references.push((
+                        arguments_path.key("contract_address"),
+                        contract_address.reference().cloned(),
+                    )),
+                    // an undefined contract is reported once, when its function is looked up
+                    Err(FunctionCallError::ContractNotDefined(_))
+                        if call_contract.function.is_some() => {}
+                    Err(error) => self.report_function_call_error(
+                        &arguments_path,
+                        call_contract.function.is_some(),
+                        &error,
+                    ),
+                }
+                match call_contract.function_call(setup) {
+                    Ok(function_call) => Self::function_argument_references(
+                        &function_call,
+                        call_contract.function.is_some(),
+                        &arguments_path,
+                        &mut references,
+                    ),
+                    Err(error) => self.report_function_call_error(
+                        &arguments_path,
+                        call_contract.function.is_some(),
+                        &error,
+                    ),
+                }
+            }
+            StepArguments::SendTransaction(send_transaction) => {
+                if let Err(error) = send_transaction.options.check() {
+                    self.report(&arguments_path.key("gas_price"), error);
+                }
+                if let Err(SignerConfigError::SignerNotDefined(name)) =
+                    send_transaction.signer.resolve(setup)
+                {
+                    self.report(
+                        &arguments_path.key("signer"),
+                        format!("signer `{}` is not defined in setup", name.as_str()),
+                    );
+                }
+
+                match send_transaction.to(setup) {
+                    Ok(to) => references.push((arguments_path.key("to"), to.reference().cloned())),
+                    Err(FunctionCallError::ContractNotDefined(_))
+                        if send_transaction.function.is_some() => {}
+                    Err(error) => self.report_function_call_error(
+                        &arguments_path,
+                        send_transaction.function.is_some(),
+                        &error,
+                    ),
+                }
+                references.push((
+                    arguments_path.key("value"),
+                    send_transaction
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.reference())
+                        .cloned(),
+                ));
+                match send_transaction.function_call(setup) {
+                    Ok(Some(function_call)) => Self::function_argument_references(
+                        &function_call,
+                        send_transaction.function.is_some(),
+                        &arguments_path,
+                        &mut references,
+                    ),
+                    Ok(None) => {}
+                    Err(error) => self.report_function_call_error(
+                        &arguments_path,
+                        send_transaction.function.is_some(),
+                        &error,
+                    ),
+                }
+            }
+            StepArguments::DeployContract(deploy_contract) => {
+                if let Err(error) = deploy_contract.options.check() {
+                    self.report(&arguments_path.key("gas_price"), error);
+                }
+                if let Err(SignerConfigError::SignerNotDefined(name)) =
+                    deploy_contract.signer.resolve(setup)
+                {
+                    self.report(
+                        &arguments_path.key("signer"),
+                        format!("signer `{}` is not defined in setup", name.as_str()),
+                    );
+                }
+
+                match deploy_contract.bytecode(setup) {
+                    Ok(_) => {}
+                    // an undefined contract is reported once, when its constructor is looked up
+                    Err(DeployContractError::FunctionCallError(
+                        FunctionCallError::ContractNotDefined(_),
+                    )) => {}
+                    Err(error) => self.report_deploy_contract_error(
+                        &arguments_path,
+                        deploy_contract.constructor_arguments.is_empty(),
+                        &error,
+                    ),
+                }
+                references.push((
+                    arguments_path.key("value"),
+                    deploy_contract
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.reference())
+                        .cloned(),
+                ));
+                match deploy_contract.constructor_arguments(setup) {
+                    Ok(constructor_arguments) => {
+                        for (index, argument) in constructor_arguments.iter().enumerate() {
+                            let path = if deploy_contract.args.is_empty() {
+                                arguments_path
+                                    .key("constructor_arguments")
+                                    .index(index)
+                                    .key("value")
+                            } else {
+                                arguments_path.key("args").index(index)
+                            };
+                            references.push((path, argument.reference().cloned()));
+                        }
+                    }
+                    Err(error) => self.report_deploy_contract_error(
+                        &arguments_path,
+                        deploy_contract.constructor_arguments.is_empty(),
+                        &error,
+                    ),
+                }
+            }
+        }
+
+        for (path, reference) in references {
+            match reference {
+                Some(reference) if reference.job.is_some() => {
+                    job_summary.job_references.push((path, reference));
+                }
+                Some(reference) if !job_summary.outputs.contains(reference.name.as_str()) => self.report(
+                    &path,
+                    format!(
+                        "`{}` references output `{}` which is not saved by an earlier step of the job",
+                        reference,
+                        reference.name.as_str()
+                    ),
+                ),
+                _ => {}
+            }
+        }
+    }
+
+    /// Collect the references of function arguments, which are either untyped `args` of a function looked up in a
+    /// contract ABI or typed `function_arguments`
+    fn function_argument_references(
+        function_call: &FunctionCall,
+        is_abi_call: bool,
+        arguments_path: &YamlPath,
+        references: &mut Vec<(YamlPath, Option<OutputReference>)>,
+    ) {
+        for (index, function_argument) in function_call.function_arguments.iter().enumerate() {
+            let path = if is_abi_call {
+                arguments_path.key("args").index(index)
+            } else {
+                arguments_path
+                    .key("function_arguments")
+                    .index(index)
+                    .key("value")
+            };
+            references.push((path, function_argument.reference().cloned()));
+        }
+    }
+
+    fn report_function_call_error(
+        &mut self,
+        arguments_path: &YamlPath,
+        is_abi_call: bool,
+        error: &FunctionCallError,
+    ) {
+        let path = match error {
+            FunctionCallError::ContractNotDefined(_) => arguments_path.key("contract"),
+            FunctionCallError::InvalidArgument { index, .. } if is_abi_call => {
+                arguments_path.key("args").index(*index)
+            }
+            FunctionCallError::InvalidArgument { index, .. } => {
+                arguments_path.key("function_arguments").index(*index)
+            }
+            FunctionCallError::InvalidReturnType { index, .. } if !is_abi_call => {
+                arguments_path.key("function_return_types").index(*index)
+            }
+            FunctionCallError::ArgumentCountMismatch { .. }
+            | FunctionCallError::ContractConfigError(_) => arguments_path.key("function"),
+            _ => arguments_path.clone(),
+        };
+        self.report(&path, error);
+    }
+
+    fn report_deploy_contract_error(
+        &mut self,
+        arguments_path: &YamlPath,
+        is_abi_call: bool,
+        error: &DeployContractError,
+    ) {
+        let path = match error {
+            DeployContractError::FunctionCallError(FunctionCallError::ContractNotDefined(_))
+            | DeployContractError::MissingContractBytecode(_) => arguments_path.key("contract"),
+            DeployContractError::FunctionCallError(FunctionCallError::InvalidArgument {
+                index,
+                ..
+            }) if is_abi_call => arguments_path.key("args").index(*index),
+            DeployContractError::FunctionCallError(FunctionCallError::InvalidArgument {
+                index,
+                ..
+            }) => arguments_path.key("constructor_arguments").index(*index),
+            DeployContractError::FunctionCallError(FunctionCallError::ArgumentCountMismatch {
+                ..
+            }) => arguments_path.key("args"),
+            _ => arguments_path.clone(),
+        };
+        self.report(&path, error);
+    }
+
+    fn check_workflows(&mut self, playbook: &Value, jobs: &HashMap<String, JobSummary>) {
+        let path = YamlPath::root().key("workflows");
+
+        let Some(workflows) = playbook.get("workflows") else {
+            return self.report(&YamlPath::root(), "missing field `workflows`");
+        };
+
+        for (workflow_name, workflow) in self.mapping_entries(workflows, &path) {
+            let workflow_path = path.key(&workflow_name);
+
+            self.check_name::<WorkflowName>(&workflow_path, &workflow_name);
+            self.check_workflow(&workflow_name, workflow, &workflow_path, jobs);
+        }
+    }
+
+    fn check_workflow(
+        &mut self,
+        workflow_name: &str,
+        workflow: &Value,
+        path: &YamlPath,
+        jobs: &HashMap<String, JobSummary>,
+    ) {
+        let jobs_path = path.key("jobs");
+
+        let Some(entries) = workflow.get("jobs").and_then(Value::as_sequence) else {
+            return self.report(path, "expected a list of `jobs` in workflow");
+        };
+
+        let mut workflow_jobs: Vec<WorkflowJob> = vec![];
+        for (index, entry) in entries.iter().enumerate() {
+            let entry_path = jobs_path.index(index);
+
+            let (name, depends_on) = match entry {
+                Value::String(name) => (name.clone(), vec![]),
+                Value::Mapping(_) => match JobConfigHelper::deserialize(entry) {
+                    Ok(helper) => (
+                        helper.name.to_string(),
+                        helper
+                            .depends_on
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|job_name| job_name.to_string())
+                            .collect(),
+                    ),
+                    Err(error) => {
+                        self.report(&entry_path, error);
+                        continue;
+                    }
+                },
+                _ => {
+                    self.report(
+                        &entry_path,
+                        "expected a job name, or a mapping with `name` and `depends_on`",
+                    );
+                    continue;
+                }
+            };
+
+            if !jobs.contains_key(&name) {
+                self.report(
+                    &entry_path,
+                    format!("job `{}` is not defined in `jobs`", name),
+                );
+            }
+            if workflow_jobs
+                .iter()
+                .any(|workflow_job| workflow_job.name == name)
+            {
+                self.report(
+                    &entry_path,
+                    format!(
+                        "job `{}` is listed more than once in workflow `{}`",
+                        name, workflow_name
+                    ),
+                );
+                continue;
+            }
+
+            workflow_jobs.push(WorkflowJob {
+                name,
+                depends_on,
+                path: entry_path,
+            });
+        }
+
+        let listed_jobs: HashSet<&str> = workflow_jobs
+            .iter()
+            .map(|workflow_job| workflow_job.name.as_str())
+            .collect();
+        for workflow_job in &workflow_jobs {
+            for (index, dependency) in workflow_job.depends_on.iter().enumerate() {
+                if !listed_jobs.contains(dependency.as_str()) {
+                    self.report(
+                        &workflow_job.path.key("depends_on").index(index),
+                        format!(
+                            "job `{}` depends on `{}` which is not a job of workflow `{}`",
+                            workflow_job.name, dependency, workflow_name
+                        ),
+                    );
+                }
+            }
+        }
+
+        let dependencies: HashMap<&str, Vec<&str>> = workflow_jobs
+            .iter()
+            .map(|workflow_job| {
+                let depends_on = workflow_job
+                    .depends_on
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|dependency| listed_jobs.contains(dependency))
+                    .collect();
+                (workflow_job.name.as_str(), depends_on)
+            })
+            .collect();
+
+        if let Some(cycle) = Self::find_cycle(&workflow_jobs, &dependencies) {
+            return self.report(
+                &jobs_path,
+                format!(
+                    "jobs of workflow `{}` depend on each other in a cycle: {}",
+                    workflow_name,
+                    cycle.join(" -> ")
+                ),
+            );
+        }
+
+        // jobs can only reference outputs of their ancestors
+        for workflow_job in &workflow_jobs {
+            let Some(job_summary) = jobs.get(&workflow_job.name) else {
+                continue;
+            };
+            let ancestors = Self::ancestors(&workflow_job.name, &dependencies);
+
+            for (path, reference) in &job_summary.job_references {
+                let Some(job_name) = &reference.job else {
+                    continue;
+                };
+                if jobs.contains_key(job_name.as_str()) && !ancestors.contains(job_name.as_str()) {
+                    self.report(
+                        path,
+                        format!(
+                            "job `{}` is not an ancestor of job `{}` in workflow `{}`, add it to `depends_on`",
+                            job_name.as_str(),
+                            workflow_job.name,
+                            workflow_name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Find a dependency cycle with depth first search, e.g. `a -> b -> a` when job `a` depends on `b` and `b`
+    /// depends on `a`
+    fn find_cycle(
+        workflow_jobs: &[WorkflowJob],
+        dependencies: &HashMap<&str, Vec<&str>>,
+    ) -> Option<Vec<String>> {
+        fn visit<'a>(
+            job_name: &'a str,
+            dependencies: &HashMap<&'a str, Vec<&'a str>>,
+            visited: &mut HashSet<&'a str>,
+            stack: &mut Vec<&'a str>,
+        ) -> Option<Vec<String>> {
+            if let Some(start) = stack.iter().position(|name| *name == job_name) {
+                let mut cycle: Vec<String> =
+                    stack[start..].iter().map(|name| name.to_string()).collect();
+                cycle.push(job_name.to_string());
+                return Some(cycle);
+            }
+            if !visited.insert(job_name) {
+                return None;
+            }
+
+            stack.push(job_name);
+            for dependency in dependencies.get(job_name).into_iter().flatten() {
+                if let Some(cycle) = visit(dependency, dependencies, visited, stack) {
+                    return Some(cycle);
+                }
+            }
+            stack.pop();
+            None
+        }
+
+        let mut visited = HashSet::new();
+        workflow_jobs.iter().find_map(|workflow_job| {
+            visit(&workflow_job.name, dependencies, &mut visited, &mut vec![])
+        })
+    }
+
+    fn ancestors<'a>(
+        job_name: &str,
+        dependencies: &HashMap<&'a str, Vec<&'a str>>,
+    ) -> HashSet<&'a str> {
+        let mut ancestors = HashSet::new();
+        let mut queue: Vec<&str> = dependencies.get(job_name).cloned().unwrap_or_default();
+
+        while let Some(ancestor) = queue.pop() {
+            if ancestors.insert(ancestor) {
+                queue.extend(dependencies.get(ancestor).into_iter().flatten());
+            }
+        }
+        ancestors
+    }
+
+    /// Check the name against the name pattern
+    fn check_name<T>(&mut self, path: &YamlPath, name: &str)
+    where
+        T: FromStr<Err = InvalidNameError>,
+    {
+        if let Err(error) = T::from_str(name) {
+            self.report(path, error);
+        }
+    }
+
+    /// Return entries of a mapping with string keys
+    fn mapping_entries<'a>(
+        &mut self,
+        value: &'a Value,
+        path: &YamlPath,
+    ) -> Vec<(String, &'a Value)> {
+        let Some(mapping) = value.as_mapping() else {
+            self.report(path, "expected a mapping");
+            return vec![];
+        };
+
+        mapping
+            .iter()
+            .filter_map(|(key, value)| match key.as_str() {
+                Some(key) => Some((key.to_string(), value)),
+                None => {
+                    self.report(path, format!("expected a string as key, found {:?}", key));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn report<M: Display>(&mut self, path: &YamlPath, message: M) {
+        let location = self.locator.locate(path);
+        let path = (!path.is_root()).then(|| path.to_string());
+
+        self.push(location, path, message);
+    }
+
+    fn report_yaml_error(&mut self, error: &serde_yaml::Error) {
+        let location = error.location().map(|location| Location {
+            line: location.line(),
+            column: location.column(),
+        });
+
+        self.push(location, None, error);
+    }
+
+    fn report_preprocess_error(&mut self, error: &PlaybookError) {
+        let (line, column) = match error {
+            PlaybookError::HandlebarsTemplateError(error) => (error.line_no, error.column_no),
+            PlaybookError::HandlebarsRenderError(error) => (error.line_no, error.column_no),
+            PlaybookError::SerdeYamlError(error) => return self.report_yaml_error(error),
+            PlaybookError::IoError(_) => (None, None),
+        };
+        let location = line.map(|line| Location {
+            line,
+            column: column.unwrap_or(1),
+        });
+
+        self.push(location, None, error);
+    }
+
+    fn push<M: Display>(&mut self, location: Option<Location>, path: Option<String>, message: M) {
+        self.diagnostics.push(Diagnostic {
+            file: self.file.clone(),
+            location,
+            path,
+            message: message.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_matches, assert_some};
+
+    fn find<'a>(diagnostics: &'a [Diagnostic], path: &str) -> &'a Diagnostic {
+        assert_some!(
+            diagnostics
+                .iter()
+                .find(|diagnostic| diagnostic.path.as_deref() == Some(path)),
+            "no diagnostic at {}, diagnostics: {:#?}",
+            path,
+            diagnostics
+        )
+    }
+
+    #[test]
+    fn can_validate_valid_playbook() {
+        let yaml = r#"
+version: "1"
+setup:
+    variables:
+        UNISWAP_V3_FACTORY: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+jobs:
+    get_pool:
+        steps:
+          - type: CallContract
+            name: get_pool
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract_address: {{UNISWAP_V3_FACTORY}}
+                function_signature: "getPool(address,address,uint24)"
+                function_arguments:
+                    - type: address
+                      value: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                    - type: address
+                      value: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2"
+                    - type: uint24
+                      value: 3000
+                function_return_types: [address]
+            output:
+                save_as: POOL_ADDRESS
+    get_slot0:
+        steps:
+          - type: CallContract
+            name: get_slot0
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract_address: "${{ jobs.get_pool.outputs.POOL_ADDRESS }}"
+                function_signature: "slot0()"
+                function_arguments: []
+                function_return_types: [uint160, int24, uint16, uint16, uint16, uint8, bool]
+            output:
+                save_as: SLOT0
+workflows:
+    workflow_1:
+        jobs:
+        - get_pool
+        - name: get_slot0
+          depends_on: [get_pool]
+"#;
+
+        let diagnostics = PlaybookValidator::validate_str(yaml);
+        assert!(diagnostics.is_empty(), "{:#?}", diagnostics);
+    }
+
+    #[test]
+    fn can_report_all_problems_with_locations() {
+        let yaml = r#"
+version: "1"
+jobs:
+    get_pool:
+        steps:
+          - type: CallContract
+            name: get pool
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract_address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+                function_signature: "getPool(address,uint24)"
+                function_arguments:
+                    - type: address
+                      value: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                    - type: uint8
+                      value: 3000
+                function_return_types: [address]
+            output:
+                save_as: POOL_ADDRESS
+          - type: CallContract
+            name: get_slot0
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract_address: "${{ outputs.UNKNOWN }}"
+                function_signature: "slot0()"
+                function_arguments: []
+                function_return_types: [uint160]
+            output:
+                save_as: SLOT0
+    job_a:
+        steps:
+          - type: BlankStep
+            name: blank_step
+          - type: BlankStep
+            name: blank_step
+    job_b:
+        steps:
+          - type: BlankStep
+workflows:
+    workflow_1:
+        jobs:
+        - get_pool
+        - unknown_job
+        - name: job_a
+          depends_on: [job_b]
+        - name: job_b
+          depends_on: [job_a]
+"#;
+
+        let diagnostics = PlaybookValidator::validate_str(yaml);
+
+        let argument = find(
+            &diagnostics,
+            "jobs.get_pool.steps[0].arguments.function_arguments[1]",
+        );
+        assert_eq!(
+            argument.location,
+            Some(Location {
+                line: 15,
+                column: 21
+            })
+        );
+
+        let step_name = find(&diagnostics, "jobs.get_pool.steps[0].name");
+        assert_eq!(
+            step_name.location,
+            Some(Location {
+                line: 7,
+                column: 13
+            })
+        );
+        assert!(step_name.message.starts_with("invalid step name"));
+
+        let duplicate_step_name = find(&diagnostics, "jobs.job_a.steps[1].name");
+        assert!(duplicate_step_name
+            .message
+            .starts_with("duplicate step name"));
+
+        let reference = find(
+            &diagnostics,
+            "jobs.get_pool.steps[1].arguments.contract_address",
+        );
+        assert!(reference.message.contains("not saved by an earlier step"));
+
+        let unknown_job = find(&diagnostics, "workflows.workflow_1.jobs[1]");
+        assert_eq!(
+            unknown_job.location,
+            Some(Location {
+                line: 43,
+                column: 9
+            })
+        );
+
+        let cycle = find(&diagnostics, "workflows.workflow_1.jobs");
+        assert!(
+            cycle.message.ends_with("job_a -> job_b -> job_a"),
+            "{}",
+            cycle.message
+        );
+
+        // diagnostics are ordered by location
+        let lines: Vec<usize> = diagnostics
+            .iter()
+            .filter_map(|diagnostic| diagnostic.location.map(|location| location.line))
+            .collect();
+        assert!(lines.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn can_report_undefined_variables_and_yaml_errors() {
+        let yaml = "version: \"1\"\nsetup:\n    variables:\n        FOO: bar\njobs:\n    job_a:\n        steps:\n          - type: CallContract\n            name: {{UNDEFINED}}\n";
+        let diagnostics = PlaybookValidator::validate_str(yaml);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].location.map(|location| location.line),
+            Some(9)
+        );
+
+        let diagnostics = PlaybookValidator::validate_str("version: \"1\"\njobs: [\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_some!(diagnostics[0].location);
+    }
+
+    #[test]
+    fn can_check_playbook_file() {
+        let dir = std::env::temp_dir().join(format!("check_playbook_file_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let file = dir.join("not_yaml.yaml");
+        std::fs::write(&file, "version: \"1\"\njobs: [\n").unwrap();
+        assert_matches!(
+            PlaybookValidator::check_file(&file),
+            Err(PlaybookValidationError::PlaybookError(error))
+                if matches!(*error, PlaybookError::SerdeYamlError(_))
+        );
+
+        let file = dir.join("cyclic.yaml");
+        let yaml = "version: \"1\"\njobs:\n    job_a:\n        steps: []\nworkflows:\n    workflow_1:\n        jobs:\n        - name: job_a\n          depends_on: [job_a]\n        - job_b\n";
+        std::fs::write(&file, yaml).unwrap();
+        let diagnostics = match PlaybookValidator::check_file(&file) {
+            Err(PlaybookValidationError::InvalidPlaybook(diagnostics)) => diagnostics,
+            result => panic!("expected invalid playbook, got {:?}", result),
+        };
+        assert!(diagnostics.len() >= 2, "{:#?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.file == Some(file.clone())));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn can_report_gas_price_with_eip1559_fees() {
+        let yaml = r#"
+version: "1"
+jobs:
+    transfer:
+        steps:
+          - type: SendTransaction
+            name: transfer
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                signer:
+                    private_key: "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                to: "0x95Ba4cF87D6723ad9C0Db21737D862bE80e93911"
+                gas_price: 1000000000
+                max_fee_per_gas: 2000000000
+workflows:
+    workflow_1:
+        jobs:
+        - transfer
+"#;
+
+        let diagnostics = PlaybookValidator::validate_str(yaml);
+        assert_eq!(diagnostics.len(), 1, "{:#?}", diagnostics);
+        let gas_price = find(&diagnostics, "jobs.transfer.steps[0].arguments.gas_price");
+        assert!(gas_price.message.contains("max_fee_per_gas"));
+        assert_eq!(gas_price.location.map(|location| location.line), Some(13));
+    }
+
+    #[test]
+    fn can_report_contract_function_calls() {
+        let yaml = r#"
+version: "1"
+setup:
+    contracts:
+        UNISWAP_V3_FACTORY:
+            address: "0x1F98431c8aD98523631AE4a59f267346ea31F984"
+            abi:
+                - function getPool(address,address,uint24) view returns (address)
+jobs:
+    get_pool:
+        steps:
+          - type: CallContract
+            name: get_pool
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract: UNISWAP_V3_FACTORY
+                function: getPool
+                args:
+                    - "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                    - "${{ outputs.WETH }}"
+                    - 3000
+            output:
+                save_as: POOL_ADDRESS
+          - type: CallContract
+            name: get_owner
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract: UNISWAP_V3_FACTORY
+                function: owner
+            output:
+                save_as: OWNER
+          - type: CallContract
+            name: get_fee
+            arguments:
+                chain_rpc_url: "https://eth.llamarpc.com"
+                contract: UNKNOWN_CONTRACT
+                function: fee
+            output:
+                save_as: FEE
+workflows:
+    workflow_1:
+        jobs:
+        - get_pool
+"#;
+
+        let diagnostics = PlaybookValidator::validate_str(yaml);
+        assert_eq!(diagnostics.len(), 3, "{:#?}", diagnostics);
+
+        let reference = find(&diagnostics, "jobs.get_pool.steps[0].arguments.args[1]");
+        assert!(reference.message.contains("not saved by an earlier step"));
+
+        let function = find(&diagnostics, "jobs.get_pool.steps[1].arguments.function");
+        assert_eq!(
+            function.message,
+            "function `owner` is not found in the contract ABI"
+        );
+
+        let contract = find(&diagnostics, "jobs.get_pool.steps[2].arguments.contract");
+        assert_eq!(
+            contract.location,
+            Some(Location {
+                line: 36,
+                column: 17
+            })
+        );
+    }
+}
@@ -0,0 +1,316 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::Serialize;
+
+/// Position in the source file, both line and column are 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Path of a value in a YAML document, displayed as `jobs.get_pool.steps[0].name`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct YamlPath(Vec<PathSegment>);
+
+impl YamlPath {
+    /// Path of the document root
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Path of the value of a mapping key under this path
+    pub fn key<K: Into<String>>(&self, key: K) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(PathSegment::Key(key.into()));
+        Self(segments)
+    }
+
+    /// Path of a sequence item under this path
+    pub fn index(&self, index: usize) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(PathSegment::Index(index));
+        Self(segments)
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Path of the parent value, `None` for the root
+    pub fn parent(&self) -> Option<Self> {
+        let (_, segments) = self.0.split_last()?;
+        Some(Self(segments.to_vec()))
+    }
+}
+
+impl Display for YamlPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            return write!(f, "<root>");
+        }
+
+        for (i, segment) in self.0.iter().enumerate() {
+            match segment {
+                PathSegment::Key(key) if i == 0 => write!(f, "{}", key)?,
+                PathSegment::Key(key) => write!(f, ".{}", key)?,
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// YamlLocator finds the source location of values in a YAML document by their path. It only understands the block
+/// style used by playbooks, values in flow style (`[a, b]` or `{a: b}`) are located at their parent key.
+#[derive(Debug, Default)]
+pub struct YamlLocator {
+    locations: HashMap<YamlPath, Location>,
+}
+
+/// Mapping key or sequence item which is still open while scanning the document
+struct OpenNode {
+    indent: isize,
+    path: YamlPath,
+    is_item: bool,
+    next_index: usize,
+}
+
+impl YamlLocator {
+    pub fn new(content: &str) -> Self {
+        let mut locations = HashMap::new();
+        let mut stack = vec![OpenNode {
+            indent: -1,
+            path: YamlPath::root(),
+            is_item: false,
+            next_index: 0,
+        }];
+        // lines of a block scalar (`|` or `>`) are indented deeper than its key
+        let mut block_scalar_indent: Option<usize> = None;
+
+        for (line_index, line) in content.lines().enumerate() {
+            let trimmed = line.trim_start();
+            let indent = line.len() - trimmed.len();
+
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            match block_scalar_indent {
+                Some(scalar_indent) if indent > scalar_indent => continue,
+                _ => block_scalar_indent = None,
+            }
+            if indent == 0 && (trimmed.starts_with("---") || trimmed.starts_with("...")) {
+                continue;
+            }
+
+            let mut column = indent;
+            let mut rest = trimmed;
+
+            // sequence items, which could be nested in the same line like `- - a`
+            while rest == "-" || rest.starts_with("- ") {
+                while let Some(top) = stack.last() {
+                    if top.indent > column as isize
+                        || (top.indent == column as isize && top.is_item)
+                    {
+                        stack.pop();
+                    } else {
+                        break;
+                    }
+                }
+
+                let parent = stack.last_mut().expect("root node is never popped");
+                let path = parent.path.index(parent.next_index);
+                parent.next_index += 1;
+
+                locations.entry(path.clone()).or_insert(Location {
+                    line: line_index + 1,
+                    column: column + 1,
+                });
+                stack.push(OpenNode {
+                    indent: column as isize,
+                    path,
+                    is_item: true,
+                    next_index: 0,
+                });
+
+                let after_dash = &rest[1..];
+                let item = after_dash.trim_start();
+                column += 1 + after_dash.len() - item.len();
+                rest = item;
+            }
+
+            let Some((key, value)) = Self::split_key(rest) else {
+                continue;
+            };
+
+            while let Some(top) = stack.last() {
+                if top.indent >= column as isize {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+
+            let parent = stack.last().expect("root node is never popped");
+            let path = parent.path.key(key);
+
+            locations.entry(path.clone()).or_insert(Location {
+                line: line_index + 1,
+                column: column + 1,
+            });
+            if value.starts_with('|') || value.starts_with('>') {
+                block_scalar_indent = Some(column);
+            }
+            stack.push(OpenNode {
+                indent: column as isize,
+                path,
+                is_item: false,
+                next_index: 0,
+            });
+        }
+
+        Self { locations }
+    }
+
+    /// Location of the value at the path, or of its closest ancestor found in the source
+    pub fn locate(&self, path: &YamlPath) -> Option<Location> {
+        let mut path = Some(path.clone());
+
+        while let Some(current) = path {
+            if let Some(location) = self.locations.get(&current) {
+                return Some(*location);
+            }
+            path = current.parent();
+        }
+        None
+    }
+
+    /// Split `key: value` into the unquoted key and the trimmed value
+    fn split_key(content: &str) -> Option<(String, &str)> {
+        // flow collections and aliases are values, not keys
+        if content.starts_with(['[', '{', '&', '*', '!']) {
+            return None;
+        }
+
+        let (key, after_key) = match content.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let end = content[1..].find(quote)? + 1;
+                (content[1..end].to_string(), &content[end + 1..])
+            }
+            _ => {
+                let end = content
+                    .match_indices(':')
+                    .map(|(index, _)| index)
+                    .find(|index| {
+                        content[index + 1..].is_empty() || content[index + 1..].starts_with(' ')
+                    })?;
+                (content[..end].trim_end().to_string(), &content[end..])
+            }
+        };
+
+        let value = after_key.trim_start().strip_prefix(':')?;
+        if !value.is_empty() && !value.starts_with(' ') {
+            return None;
+        }
+        Some((key, value.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_locate_yaml_values() {
+        let content = r#"
+version: "1"
+setup:
+    variables:
+        "QUOTED_KEY": value
+jobs:
+    get_pool:
+        steps:
+          - type: CallContract
+            name: get_pool
+            arguments:
+                function_arguments:
+                    - type: address
+                      value: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48"
+                    - type: uint24
+                      value: 3000
+                function_return_types: [address]
+            description: |
+                name: not a key
+          - type: BlankStep
+workflows:
+    workflow_1:
+        jobs:
+        - get_pool
+        - name: get_slot0
+          depends_on: [get_pool]
+"#;
+        let locator = YamlLocator::new(content);
+        let location = |path: &YamlPath| {
+            let location = locator.locate(path).unwrap();
+            (location.line, location.column)
+        };
+
+        assert_eq!(location(&YamlPath::root().key("version")), (2, 1));
+        assert_eq!(
+            location(
+                &YamlPath::root()
+                    .key("setup")
+                    .key("variables")
+                    .key("QUOTED_KEY")
+            ),
+            (5, 9)
+        );
+
+        let steps = YamlPath::root().key("jobs").key("get_pool").key("steps");
+        assert_eq!(location(&steps.index(0)), (9, 11));
+        assert_eq!(location(&steps.index(0).key("name")), (10, 13));
+        assert_eq!(
+            location(
+                &steps
+                    .index(0)
+                    .key("arguments")
+                    .key("function_arguments")
+                    .index(1)
+                    .key("value")
+            ),
+            (16, 23)
+        );
+        assert_eq!(location(&steps.index(1)), (20, 11));
+        // flow sequence items are located at their key
+        assert_eq!(
+            location(
+                &steps
+                    .index(0)
+                    .key("arguments")
+                    .key("function_return_types")
+                    .index(0)
+            ),
+            (17, 17)
+        );
+
+        let workflow_jobs = YamlPath::root()
+            .key("workflows")
+            .key("workflow_1")
+            .key("jobs");
+        assert_eq!(location(&workflow_jobs.index(0)), (24, 9));
+        assert_eq!(
+            location(&workflow_jobs.index(1).key("depends_on")),
+            (26, 11)
+        );
+        assert_eq!(
+            workflow_jobs.index(1).key("depends_on").to_string(),
+            "workflows.workflow_1.jobs[1].depends_on"
+        );
+    }
+}
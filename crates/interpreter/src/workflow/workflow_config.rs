@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, num::NonZeroUsize, time::Duration};
 
 use crate::job::{JobConfig, JobName};
 use derive_builder::Builder;
@@ -8,18 +8,42 @@ use serde::Deserialize;
 #[derive(Debug, Builder, Clone)]
 pub struct WorkflowConfig {
     jobs: HashMap<JobName, JobConfig>,
+    /// Skip jobs which are not started yet once a job fails
+    #[builder(default)]
+    fail_fast: bool,
+    /// Maximum number of jobs of the workflow running at the same time, unlimited if not set
+    #[builder(default)]
+    max_parallel: Option<NonZeroUsize>,
+    /// Maximum execution time of the workflow in milliseconds, unlimited if not set
+    #[builder(default)]
+    timeout_ms: Option<u64>,
 }
 
 impl WorkflowConfig {
     pub fn get_jobs(&self) -> &HashMap<JobName, JobConfig> {
         &self.jobs
     }
+
+    pub fn fail_fast(&self) -> bool {
+        self.fail_fast
+    }
+
+    pub fn max_parallel(&self) -> Option<NonZeroUsize> {
+        self.max_parallel
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
 }
 
 /// Helper struct to help deserialize [`WorkflowConfig`]
 #[derive(Debug)]
 pub struct WorkflowConfigHelper {
     pub jobs: Vec<JobConfigInWorkflowEnum>,
+    pub fail_fast: bool,
+    pub max_parallel: Option<NonZeroUsize>,
+    pub timeout_ms: Option<u64>,
 }
 
 impl<'de> Deserialize<'de> for WorkflowConfigHelper {
@@ -30,6 +54,12 @@ impl<'de> Deserialize<'de> for WorkflowConfigHelper {
         #[derive(Debug, Deserialize)]
         struct Helper {
             pub jobs: Vec<serde_value::Value>,
+            #[serde(default)]
+            pub fail_fast: bool,
+            #[serde(default)]
+            pub max_parallel: Option<NonZeroUsize>,
+            #[serde(default)]
+            pub timeout_ms: Option<u64>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -53,7 +83,12 @@ impl<'de> Deserialize<'de> for WorkflowConfigHelper {
             })
             .collect::<Result<Vec<JobConfigInWorkflowEnum>, D::Error>>()?;
 
-        Ok(Self { jobs })
+        Ok(Self {
+            jobs,
+            fail_fast: helper.fail_fast,
+            max_parallel: helper.max_parallel,
+            timeout_ms: helper.timeout_ms,
+        })
     }
 }
 
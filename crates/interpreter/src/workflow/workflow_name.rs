@@ -1,7 +1,39 @@
-use derive_more::{Deref, FromStr};
-use serde::Deserialize;
+use derive_more::Deref;
 use serde_valid::Validate;
 
+use crate::types::impl_name;
+
 /// WorkflowName can only contains alphanumeric, `_` or `-` characters, up to a maximum of 200 characters.
-#[derive(Debug, Deref, Deserialize, Validate, Eq, PartialEq, Hash, Clone, FromStr)]
+#[derive(Debug, Deref, Validate, Eq, PartialEq, Hash, Clone)]
 pub struct WorkflowName(#[validate(pattern = r#"^[a-zA-Z0-9][a-zA-Z0-9_-]{1,199}$"#)] String);
+
+impl_name!(WorkflowName, "workflow name");
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::types::InvalidNameError;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn cannot_parse_invalid_workflow_name() {
+        assert_ok!(WorkflowName::from_str("workflow_1"));
+        assert_ok!(serde_yaml::from_str::<WorkflowName>("deploy-pools"));
+
+        for workflow_name in ["w", "deploy pools", "-deploy", "deploy.pools"] {
+            assert_err!(WorkflowName::from_str(workflow_name));
+            assert_err!(serde_yaml::from_str::<WorkflowName>(&format!(
+                "\"{}\"",
+                workflow_name
+            )));
+        }
+
+        let error = WorkflowName::from_str("deploy pools").unwrap_err();
+        assert_eq!(
+            error,
+            InvalidNameError::new("workflow name", "deploy pools")
+        );
+    }
+}
@@ -2,6 +2,12 @@ mod call_contract;
 pub use call_contract::*;
 mod send_transaction;
 pub use send_transaction::*;
+mod deploy_contract;
+pub use deploy_contract::*;
+mod nonce_manager;
+pub use nonce_manager::*;
+mod transaction_sent_hook;
+pub use transaction_sent_hook::*;
 mod blank_step;
 pub use blank_step::*;
 mod step_trait;
@@ -1,4 +1,4 @@
-use crate::{StepError, StepOutput};
+use crate::{StepError, StepErrorKind, StepOutput};
 
 use super::Step;
 use derive_builder::Builder;
@@ -35,11 +35,17 @@ where
             .middleware
             .call(&tx_request.into(), self.block)
             .await
-            .map_err(|e| StepError::CallContractError(e.to_string()))?;
+            .map_err(|e| StepError::CallContractError {
+                kind: StepErrorKind::from_middleware_error(&e),
+                message: e.to_string(),
+            })?;
 
         if let Some(return_data_types) = &self.return_data_types {
             let decoded_return_data = ethers::abi::decode(return_data_types, &bytes_result)
-                .map_err(|e| StepError::CallContractError(e.to_string()))?;
+                .map_err(|e| StepError::CallContractError {
+                    kind: StepErrorKind::Decode,
+                    message: e.to_string(),
+                })?;
             Ok(CallContractOutput::Tokens(decoded_return_data).into())
         } else {
             Ok(CallContractOutput::Bytes(bytes_result).into())
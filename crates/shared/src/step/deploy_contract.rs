@@ -0,0 +1,173 @@
+use super::send_transaction::send_and_confirm;
+use crate::{NonceManager, Step, StepError, StepErrorKind, StepOutput, TransactionSentHook};
+use derive_builder::Builder;
+use ethers::prelude::{Middleware, Signer, SignerMiddleware};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, BlockId, Bytes, TransactionReceipt, H160, H256};
+use std::sync::Arc;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Deterministic deployment proxy which is deployed at the same address on most chains. It deploys the creation code
+/// given after a 32 bytes salt in the calldata with CREATE2.
+/// `0x4e59b44847b379578588920cA78FbF26c0B4956C`
+pub const DEFAULT_CREATE2_FACTORY: Address = H160([
+    0x4e, 0x59, 0xb4, 0x48, 0x47, 0xb3, 0x79, 0x57, 0x85, 0x88, 0x92, 0x0c, 0xa7, 0x8f, 0xbf, 0x26,
+    0xc0, 0xb4, 0x95, 0x6c,
+]);
+
+/// Allow to deploy a contract using a [`SignerMiddleware`], either by a CREATE transaction without recipient, or by a
+/// call to a CREATE2 factory which makes the contract address deterministic
+#[derive(Debug, Builder)]
+pub struct DeployContract<M: Middleware, S: Signer> {
+    pub signer_middleware: SignerMiddleware<M, S>,
+    /// Transaction which deploys the contract, its data is either the creation code or the calldata of the factory
+    pub tx_request: TypedTransaction,
+    /// Address of the contract deployed by a CREATE2 factory, which is known before sending the transaction. The
+    /// address of a contract deployed by CREATE is read from the receipt.
+    #[builder(default)]
+    pub create2_address: Option<Address>,
+    #[builder(default)]
+    pub block: Option<BlockId>,
+    /// Stop waiting for the transaction to be mined at the deadline
+    #[builder(default)]
+    pub deadline: Option<Instant>,
+    /// Stop waiting for the transaction to be mined once the run is cancelled
+    #[builder(default)]
+    pub cancellation: CancellationToken,
+    /// Nonces of the signer shared with the other steps signing with it, the nonce is fetched from the chain if not
+    /// set
+    #[builder(default)]
+    pub nonce_manager: Option<Arc<NonceManager>>,
+    /// Called with the transaction hash as soon as the transaction is sent
+    #[builder(default)]
+    pub on_sent: Option<TransactionSentHook>,
+}
+
+#[async_trait::async_trait]
+impl<M, S> Step for DeployContract<M, S>
+where
+    M: Middleware,
+    S: Signer,
+{
+    async fn execute(&self) -> Result<StepOutput, StepError> {
+        let tx_receipt = send_and_confirm(
+            &self.signer_middleware,
+            self.tx_request.clone(),
+            self.block,
+            self.deadline,
+            &self.cancellation,
+            self.nonce_manager.as_deref(),
+            self.on_sent.as_ref(),
+        )
+        .await?
+        .ok_or_else(|| StepError::DeployContractError {
+            kind: StepErrorKind::Rejected,
+            message: "the transaction is dropped from the mempool".to_string(),
+        })?;
+
+        self.output_of_receipt(tx_receipt)
+    }
+
+    fn output_of_receipt(&self, tx_receipt: TransactionReceipt) -> Result<StepOutput, StepError> {
+        if tx_receipt.status == Some(0.into()) {
+            return Err(StepError::TransactionReverted {
+                tx_hash: tx_receipt.transaction_hash,
+            });
+        }
+
+        let address = self
+            .create2_address
+            .or(tx_receipt.contract_address)
+            .ok_or_else(|| StepError::DeployContractError {
+                kind: StepErrorKind::Decode,
+                message: format!(
+                    "receipt of transaction {:?} has no contract address",
+                    tx_receipt.transaction_hash
+                ),
+            })?;
+        Ok(DeployContractOutput {
+            address,
+            tx_receipt,
+        }
+        .into())
+    }
+}
+
+/// Return the calldata of the CREATE2 factory, which is the salt followed by the creation code
+pub fn create2_calldata(salt: H256, init_code: &Bytes) -> Bytes {
+    [salt.as_bytes(), init_code.as_ref()].concat().into()
+}
+
+/// Return the address of the contract deployed by the CREATE2 factory with the salt and the creation code
+pub fn create2_address(factory: Address, salt: H256, init_code: &Bytes) -> Address {
+    ethers::utils::get_create2_address(factory, salt, init_code.clone())
+}
+
+#[derive(Debug, Clone)]
+pub struct DeployContractOutput {
+    /// Address of the deployed contract
+    pub address: Address,
+    pub tx_receipt: TransactionReceipt,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_matches;
+    use ethers::prelude::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn can_compute_create2_address() {
+        // example 0 of EIP-1014
+        let address = create2_address(Address::zero(), H256::zero(), &Bytes::from(vec![0x00]));
+        assert_eq!(
+            address,
+            Address::from_str("0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38").unwrap()
+        );
+
+        assert_eq!(
+            DEFAULT_CREATE2_FACTORY,
+            Address::from_str("0x4e59b44847b379578588920cA78FbF26c0B4956C").unwrap()
+        );
+
+        let calldata = create2_calldata(H256::repeat_byte(1), &Bytes::from(vec![0x60, 0x80]));
+        assert_eq!(calldata.len(), 34);
+        assert_eq!(&calldata[32..], &[0x60, 0x80]);
+    }
+
+    #[test]
+    fn can_read_deployed_address_from_receipt() {
+        let provider = Provider::<Http>::try_from("http://localhost:8545").unwrap();
+        let wallet = LocalWallet::from_str(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap();
+        let step = DeployContractBuilder::default()
+            .signer_middleware(SignerMiddleware::new(provider, wallet))
+            .tx_request(TransactionRequest::new().data(vec![0x60, 0x80]).into())
+            .build()
+            .unwrap();
+
+        let tx_receipt = TransactionReceipt {
+            status: Some(1.into()),
+            contract_address: Some(Address::repeat_byte(1)),
+            ..Default::default()
+        };
+        let output = step.output_of_receipt(tx_receipt.clone()).unwrap();
+        assert_eq!(
+            output.unwrap_deploy_contract_output().address,
+            Address::repeat_byte(1)
+        );
+
+        let tx_receipt = TransactionReceipt {
+            status: Some(0.into()),
+            ..tx_receipt
+        };
+        assert_matches!(
+            step.output_of_receipt(tx_receipt),
+            Err(StepError::TransactionReverted { .. })
+        );
+    }
+}
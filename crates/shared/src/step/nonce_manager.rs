@@ -0,0 +1,30 @@
+use ethers::types::{Address, U256};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Hand out the nonces of an account on a chain, so transactions sent at the same time by steps signing with the
+/// account never take the same nonce. The first nonce is fetched from the pending state of the chain.
+#[derive(Debug)]
+pub struct NonceManager {
+    address: Address,
+    next_nonce: Mutex<Option<U256>>,
+}
+
+impl NonceManager {
+    pub fn new(address: Address) -> Self {
+        Self {
+            address,
+            next_nonce: Mutex::new(None),
+        }
+    }
+
+    /// Address of the account
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Lock the next nonce of the account until the transaction is sent. The nonce is none if it must be fetched
+    /// from the chain.
+    pub async fn lock(&self) -> MutexGuard<'_, Option<U256>> {
+        self.next_nonce.lock().await
+    }
+}
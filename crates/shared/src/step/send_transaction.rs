@@ -1,10 +1,19 @@
-use crate::{Step, StepError, StepOutput};
+use crate::{
+    is_already_known, NonceManager, Step, StepError, StepErrorKind, StepOutput, TransactionSentHook,
+};
 use derive_builder::Builder;
 use ethers::middleware::signer::SignerMiddlewareError;
-use ethers::prelude::{Middleware, ProviderError, Signer, SignerMiddleware, TransactionReceipt};
+use ethers::prelude::{
+    Middleware, MiddlewareError, PendingTransaction, ProviderError, Signer, SignerMiddleware,
+    TransactionReceipt,
+};
 use ethers::types::transaction::eip2718::TypedTransaction;
-use ethers::types::BlockId;
+use ethers::types::{BlockId, BlockNumber, TxHash};
+use ethers::utils::keccak256;
+use std::sync::Arc;
 use thiserror::Error;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 
 /// Allow to send transaction using a [`SignerMiddleware`]
 #[derive(Debug, Builder)]
@@ -16,6 +25,20 @@ where
     pub tx_request: Tx,
     #[builder(default)]
     pub block: Option<BlockId>,
+    /// Stop waiting for the transaction to be mined at the deadline
+    #[builder(default)]
+    pub deadline: Option<Instant>,
+    /// Stop waiting for the transaction to be mined once the run is cancelled. Sending the transaction is never
+    /// interrupted, so its hash is always known.
+    #[builder(default)]
+    pub cancellation: CancellationToken,
+    /// Nonces of the signer shared with the other steps signing with it, the nonce is fetched from the chain if not
+    /// set
+    #[builder(default)]
+    pub nonce_manager: Option<Arc<NonceManager>>,
+    /// Called with the transaction hash as soon as the transaction is sent
+    #[builder(default)]
+    pub on_sent: Option<TransactionSentHook>,
 }
 
 #[async_trait::async_trait]
@@ -26,16 +49,207 @@ where
     Tx: Into<TypedTransaction> + Send + Sync + Clone,
 {
     async fn execute(&self) -> Result<StepOutput, StepError> {
-        let pending_tx = self
-            .signer_middleware
-            .send_transaction(self.tx_request.clone(), self.block)
-            .await
-            .map_err(|e| StepError::SendTransactionError(e.to_string()))?;
+        let tx_receipt = send_and_confirm(
+            &self.signer_middleware,
+            self.tx_request.clone(),
+            self.block,
+            self.deadline,
+            &self.cancellation,
+            self.nonce_manager.as_deref(),
+            self.on_sent.as_ref(),
+        )
+        .await?
+        .ok_or_else(|| StepError::SendTransactionError {
+            kind: StepErrorKind::Rejected,
+            message: "the transaction is dropped from the mempool".to_string(),
+        })?;
+
+        self.output_of_receipt(tx_receipt)
+    }
+}
+
+/// Send the transaction and wait for its receipt, until the deadline or the cancellation of the run. The nonce of
+/// the transaction is taken from the nonce manager if any, unless the transaction sets it. The hook is called with the
+/// transaction hash once it is sent, or once broadcasting it fails without telling whether it is sent, in which case
+/// the signed transaction is returned in the error to be broadcast again.
+pub(crate) async fn send_and_confirm<M, S, Tx>(
+    signer_middleware: &SignerMiddleware<M, S>,
+    tx_request: Tx,
+    block: Option<BlockId>,
+    deadline: Option<Instant>,
+    cancellation: &CancellationToken,
+    nonce_manager: Option<&NonceManager>,
+    on_sent: Option<&TransactionSentHook>,
+) -> Result<Option<TransactionReceipt>, StepError>
+where
+    M: Middleware,
+    S: Signer,
+    Tx: Into<TypedTransaction> + Send + Sync,
+{
+    let mut tx: TypedTransaction = tx_request.into();
+    let send_error = |e: SignerMiddlewareError<M, S>| StepError::SendTransactionError {
+        kind: match &e {
+            SignerMiddlewareError::MiddlewareError(e) => StepErrorKind::from_middleware_error(e),
+            // errors of the signer itself, e.g. a wrong chain id, fail again
+            _ => StepErrorKind::Rejected,
+        },
+        message: e.to_string(),
+    };
+
+    // the next nonce stays locked until the transaction is sent, so other steps signing with the account wait for it
+    let mut next_nonce = match nonce_manager {
+        Some(nonce_manager) => Some(nonce_manager.lock().await),
+        None => None,
+    };
+    let managed_nonce = match next_nonce.as_deref() {
+        Some(next_nonce) if tx.nonce().is_none() => {
+            let nonce = match next_nonce {
+                Some(nonce) => *nonce,
+                None => before_sending(
+                    deadline,
+                    cancellation,
+                    signer_middleware.inner().get_transaction_count(
+                        signer_middleware.address(),
+                        Some(BlockNumber::Pending.into()),
+                    ),
+                )
+                .await?
+                .map_err(|e| StepError::SendTransactionError {
+                    kind: StepErrorKind::from_middleware_error(&e),
+                    message: e.to_string(),
+                })?,
+            };
+            tx.set_nonce(nonce);
+            Some(nonce)
+        }
+        _ => None,
+    };
 
-        let tx_receipt = pending_tx
+    // the transaction is filled before the deadline or the cancellation of the run, then it is signed and broadcasting
+    // it is never interrupted so its hash is known
+    before_sending(
+        deadline,
+        cancellation,
+        signer_middleware.fill_transaction(&mut tx, block),
+    )
+    .await?
+    .map_err(send_error)?;
+    if tx.chain_id().map(|chain_id| chain_id.as_u64())
+        != Some(signer_middleware.signer().chain_id())
+    {
+        return Err(send_error(SignerMiddlewareError::DifferentChainID));
+    }
+    let signature = signer_middleware
+        .signer()
+        .sign_transaction(&tx)
+        .await
+        .map_err(|e| send_error(SignerMiddlewareError::SignerError(e)))?;
+    let raw_tx = tx.rlp_signed(&signature);
+    let tx_hash = TxHash::from(keccak256(&raw_tx));
+
+    let broadcast = match signer_middleware
+        .inner()
+        .send_raw_transaction(raw_tx.clone())
+        .await
+    {
+        Err(e) if e.as_error_response().is_some_and(is_already_known) => Ok(()),
+        broadcast => broadcast.map(drop),
+    };
+    if let Some(next_nonce) = next_nonce.as_deref_mut() {
+        // a failed broadcast could still reach the mempool and a nonce set by the step could be ahead of the managed
+        // one, so the nonce is fetched again for the next transaction in both cases
+        *next_nonce = match (&broadcast, managed_nonce) {
+            (Ok(()), Some(nonce)) => Some(nonce + 1),
+            _ => None,
+        };
+    }
+    drop(next_nonce);
+
+    if let Err(e) = broadcast {
+        let kind = StepErrorKind::from_middleware_error(&e);
+        // the RPC provider rejects the transaction, so it is not sent
+        if e.as_error_response().is_some() && !kind.is_transient() {
+            return Err(StepError::SendTransactionError {
+                kind,
+                message: e.to_string(),
+            });
+        }
+        // otherwise it may be sent, so only the same signed transaction can be broadcast again
+        notify_sent(on_sent, tx_hash).await?;
+        return Err(StepError::TransactionNotBroadcast {
+            tx_hash,
+            raw_tx,
+            kind,
+            message: e.to_string(),
+        });
+    }
+
+    // the transaction is sent, so a timeout from now on leaves a pending transaction
+    notify_sent(on_sent, tx_hash).await?;
+    let pending_tx = PendingTransaction::new(tx_hash, signer_middleware.provider());
+    let mined = async {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, pending_tx)
+                .await
+                .map_err(|_| StepError::TransactionPending { tx_hash }),
+            None => Ok(pending_tx.await),
+        }
+    };
+    let tx_receipt = tokio::select! {
+        tx_receipt = mined => tx_receipt?,
+        _ = cancellation.cancelled() => {
+            return Err(StepError::TransactionCancelled { tx_hash })
+        }
+    }
+    .map_err(|e| StepError::TransactionUnconfirmed {
+        tx_hash,
+        kind: StepErrorKind::from_middleware_error(&e),
+        message: e.to_string(),
+    })?;
+    Ok(tx_receipt)
+}
+
+/// Call the hook with the hash of the transaction which may be sent, the step fails if the hook fails
+async fn notify_sent(
+    on_sent: Option<&TransactionSentHook>,
+    tx_hash: TxHash,
+) -> Result<(), StepError> {
+    if let Some(on_sent) = on_sent {
+        on_sent
+            .call(tx_hash)
             .await
-            .map_err(|e| StepError::SendTransactionError(e.to_string()))?;
-        Ok(SendTransactionOutput::TransactionReceipt(tx_receipt).into())
+            .map_err(|e| StepError::TransactionNotRecorded {
+                tx_hash,
+                message: e.to_string(),
+            })?;
+    }
+    Ok(())
+}
+
+/// Run a request made before the transaction is sent until the deadline or the cancellation of the run, since it is
+/// safe to interrupt
+async fn before_sending<F: std::future::Future>(
+    deadline: Option<Instant>,
+    cancellation: &CancellationToken,
+    future: F,
+) -> Result<F::Output, StepError> {
+    let until_deadline = async {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, future)
+                .await
+                .map_err(|_| StepError::SendTransactionError {
+                    kind: StepErrorKind::Timeout,
+                    message: "the transaction is not sent before the timeout".to_string(),
+                }),
+            None => Ok(future.await),
+        }
+    };
+    tokio::select! {
+        output = until_deadline => output,
+        _ = cancellation.cancelled() => Err(StepError::SendTransactionError {
+            kind: StepErrorKind::Cancelled,
+            message: "the run is cancelled before the transaction is sent".to_string(),
+        }),
     }
 }
 
@@ -62,6 +276,7 @@ mod tests {
     use claims::*;
     use ethers::prelude::*;
     use ethers::utils::Anvil;
+    use std::str::FromStr;
 
     #[tokio::test]
     #[ignore]
@@ -91,4 +306,216 @@ mod tests {
             output.unwrap_send_transaction_output();
         let _tx_receipt = assert_some!(tx_receipt);
     }
+
+    #[test]
+    fn should_fail_when_transaction_reverted() {
+        let provider = Provider::<Http>::try_from("http://localhost:8545").unwrap();
+        let wallet = LocalWallet::from_str(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap();
+        let step = SendTransactionBuilder::default()
+            .signer_middleware(SignerMiddleware::new(provider, wallet))
+            .tx_request(TransactionRequest::new())
+            .build()
+            .unwrap();
+
+        let tx_receipt = TransactionReceipt {
+            status: Some(1.into()),
+            ..Default::default()
+        };
+        assert_ok!(step.output_of_receipt(tx_receipt.clone()));
+
+        let tx_receipt = TransactionReceipt {
+            status: Some(0.into()),
+            ..tx_receipt
+        };
+        let err = step.output_of_receipt(tx_receipt).unwrap_err();
+        assert_matches!(err, StepError::TransactionReverted { .. });
+        assert_eq!(err.kind(), StepErrorKind::Revert);
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_transaction_dropped() {
+        let (provider, mock) = Provider::mocked();
+        let wallet = LocalWallet::from_str(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap()
+        .with_chain_id(31337u64);
+        let step = SendTransactionBuilder::default()
+            .signer_middleware(SignerMiddleware::new(
+                provider.interval(std::time::Duration::from_millis(1)),
+                wallet,
+            ))
+            .tx_request(
+                TransactionRequest::new()
+                    .to(Address::repeat_byte(1))
+                    .gas(21000)
+                    .gas_price(1),
+            )
+            .build()
+            .unwrap();
+
+        // responses are popped from the back, the transaction is never found after it is sent
+        for _ in 0..5 {
+            mock.push(Option::<Transaction>::None).unwrap();
+        }
+        mock.push(TxHash::zero()).unwrap();
+        mock.push(U256::from(5)).unwrap();
+
+        assert_matches!(
+            step.execute().await,
+            Err(StepError::SendTransactionError {
+                kind: StepErrorKind::Rejected,
+                ..
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn can_take_nonces_from_nonce_manager() {
+        let (provider, mock) = Provider::mocked();
+        let wallet = LocalWallet::from_str(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap()
+        .with_chain_id(31337u64);
+        let signer_middleware = SignerMiddleware::new(provider, wallet.clone());
+        let nonce_manager = NonceManager::new(wallet.address());
+        let tx = TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .gas(21000)
+            .gas_price(1);
+
+        // responses are popped from the back, the nonce is fetched once for both transactions, whose hashes are
+        // computed from the signed transactions rather than taken from the responses
+        mock.push(TxHash::repeat_byte(2)).unwrap();
+        mock.push(TxHash::repeat_byte(1)).unwrap();
+        mock.push(U256::from(5)).unwrap();
+
+        let sent_tx_hashes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let on_sent = {
+            let sent_tx_hashes = sent_tx_hashes.clone();
+            TransactionSentHook::new(move |tx_hash| {
+                sent_tx_hashes.lock().unwrap().push(tx_hash);
+                async { Ok::<(), String>(()) }
+            })
+        };
+        let mut tx_hashes = Vec::new();
+        for _ in 0..2 {
+            // the deadline passes at once, so the receipt is not polled
+            let result = send_and_confirm(
+                &signer_middleware,
+                tx.clone(),
+                None,
+                Some(Instant::now()),
+                &CancellationToken::new(),
+                Some(&nonce_manager),
+                Some(&on_sent),
+            )
+            .await;
+            let Err(StepError::TransactionPending { tx_hash }) = result else {
+                panic!("unexpected result {:?}", result);
+            };
+            tx_hashes.push(tx_hash);
+        }
+        assert_ne!(tx_hashes[0], tx_hashes[1]);
+        assert_eq!(*nonce_manager.lock().await, Some(U256::from(7)));
+        // the hashes are known even though the transactions are never mined
+        assert_eq!(*sent_tx_hashes.lock().unwrap(), tx_hashes);
+
+        mock.assert_request("eth_getTransactionCount", (wallet.address(), "pending"))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn can_keep_signed_transaction_when_broadcast_fails() {
+        let (provider, mock) = Provider::mocked();
+        let wallet = LocalWallet::from_str(
+            "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+        )
+        .unwrap()
+        .with_chain_id(31337u64);
+        let signer_middleware = SignerMiddleware::new(provider, wallet.clone());
+        let nonce_manager = NonceManager::new(wallet.address());
+        let tx = TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .gas(21000)
+            .gas_price(1);
+        let sent_tx_hashes = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let on_sent = {
+            let sent_tx_hashes = sent_tx_hashes.clone();
+            TransactionSentHook::new(move |tx_hash| {
+                let mut sent_tx_hashes = sent_tx_hashes.lock().unwrap();
+                sent_tx_hashes.push(tx_hash);
+                // recording the fourth transaction fails
+                let result = match sent_tx_hashes.len() {
+                    4 => Err("disk full".to_string()),
+                    _ => Ok(()),
+                };
+                async move { result }
+            })
+        };
+        let cancellation = CancellationToken::new();
+        let send = || {
+            send_and_confirm(
+                &signer_middleware,
+                tx.clone(),
+                None,
+                Some(Instant::now()),
+                &cancellation,
+                Some(&nonce_manager),
+                Some(&on_sent),
+            )
+        };
+        let error_response = |message: &str| {
+            MockResponse::Error(JsonRpcError {
+                code: -32000,
+                message: message.to_string(),
+                data: None,
+            })
+        };
+
+        // the broadcast request fails without a response, so the transaction may still reach the mempool
+        mock.push(U256::from(5)).unwrap();
+        let result = send().await;
+        let Err(StepError::TransactionNotBroadcast {
+            tx_hash, raw_tx, ..
+        }) = result
+        else {
+            panic!("unexpected result {:?}", result);
+        };
+        assert_eq!(tx_hash, TxHash::from(keccak256(&raw_tx)));
+        assert_eq!(*sent_tx_hashes.lock().unwrap(), vec![tx_hash]);
+        assert_none!(*nonce_manager.lock().await);
+
+        // a transaction already in the mempool is sent
+        mock.push_response(error_response("already known"));
+        mock.push(U256::from(5)).unwrap();
+        assert_matches!(send().await, Err(StepError::TransactionPending { tx_hash: hash }) if hash == tx_hash);
+        assert_eq!(*nonce_manager.lock().await, Some(U256::from(6)));
+
+        // a transaction rejected by the RPC provider is not sent
+        mock.push_response(error_response("nonce too low"));
+        assert_matches!(
+            send().await,
+            Err(StepError::SendTransactionError {
+                kind: StepErrorKind::Rejected,
+                ..
+            })
+        );
+        assert_eq!(sent_tx_hashes.lock().unwrap().len(), 2);
+        assert_none!(*nonce_manager.lock().await);
+
+        // the step fails once the hook fails, rather than waiting for a transaction a resumed run doesn't know
+        mock.push(TxHash::zero()).unwrap();
+        mock.push(TxHash::zero()).unwrap();
+        mock.push(U256::from(5)).unwrap();
+        assert_matches!(send().await, Err(StepError::TransactionPending { .. }));
+        assert_matches!(
+            send().await,
+            Err(StepError::TransactionNotRecorded { tx_hash: hash, .. }) if hash != tx_hash
+        );
+    }
 }
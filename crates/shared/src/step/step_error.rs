@@ -1,11 +1,266 @@
-use derive_more::Unwrap;
+use std::fmt;
+
+use ethers::{
+    providers::{JsonRpcError, MiddlewareError},
+    types::{Bytes, TxHash},
+};
 use thiserror::Error;
 
+/// JSON-RPC error codes used by RPC providers when a request exceeds their rate limit
+const RATE_LIMIT_ERROR_CODES: [i64; 2] = [429, -32005];
+/// Error messages of RPC providers receiving a transaction which is already in their mempool
+const ALREADY_KNOWN_MESSAGES: [&str; 3] = ["already known", "known transaction", "alreadyknown"];
+
 #[non_exhaustive]
-#[derive(Debug, Error, Unwrap)]
+#[derive(Debug, Error)]
 pub enum StepError {
-    #[error("call contract error {:#?}", .0)]
-    CallContractError(String),
-    #[error("send transction error {:#?}", .0)]
-    SendTransactionError(String),
+    #[error("call contract error ({kind}): {message}")]
+    CallContractError {
+        kind: StepErrorKind,
+        message: String,
+    },
+    #[error("send transaction error ({kind}): {message}")]
+    SendTransactionError {
+        kind: StepErrorKind,
+        message: String,
+    },
+    #[error("deploy contract error ({kind}): {message}")]
+    DeployContractError {
+        kind: StepErrorKind,
+        message: String,
+    },
+    /// The transaction is sent, but not mined before the deadline of the step
+    #[error("transaction {tx_hash:?} is sent but not mined before the timeout")]
+    TransactionPending { tx_hash: TxHash },
+    /// The transaction is sent, but the run is cancelled before it is mined
+    #[error("transaction {tx_hash:?} is sent but the run is cancelled before it is mined")]
+    TransactionCancelled { tx_hash: TxHash },
+    /// The transaction is sent, but its receipt cannot be fetched
+    #[error(
+        "transaction {tx_hash:?} is sent but its receipt cannot be fetched ({kind}): {message}"
+    )]
+    TransactionUnconfirmed {
+        tx_hash: TxHash,
+        kind: StepErrorKind,
+        message: String,
+    },
+    /// The transaction is mined, but it reverted
+    #[error("transaction {tx_hash:?} reverted")]
+    TransactionReverted { tx_hash: TxHash },
+    /// The transaction may be sent, but recording its hash fails, so the step stops rather than leave a transaction
+    /// which a resumed run would not know
+    #[error("transaction {tx_hash:?} may be sent but recording it fails: {message}")]
+    TransactionNotRecorded { tx_hash: TxHash, message: String },
+    /// The transaction is signed, but broadcasting it fails without telling whether it reached the mempool, so the
+    /// same signed transaction must be broadcast again rather than a new one
+    #[error("transaction {tx_hash:?} is signed but broadcasting it fails ({kind}): {message}")]
+    TransactionNotBroadcast {
+        tx_hash: TxHash,
+        raw_tx: Bytes,
+        kind: StepErrorKind,
+        message: String,
+    },
+}
+
+impl StepError {
+    pub fn kind(&self) -> StepErrorKind {
+        match self {
+            StepError::CallContractError { kind, .. }
+            | StepError::SendTransactionError { kind, .. }
+            | StepError::DeployContractError { kind, .. }
+            | StepError::TransactionUnconfirmed { kind, .. }
+            | StepError::TransactionNotBroadcast { kind, .. } => *kind,
+            StepError::TransactionReverted { .. } => StepErrorKind::Revert,
+            StepError::TransactionNotRecorded { .. } => StepErrorKind::Rejected,
+            StepError::TransactionPending { .. } => StepErrorKind::Timeout,
+            StepError::TransactionCancelled { .. } => StepErrorKind::Cancelled,
+        }
+    }
+
+    /// Whether executing the step again could succeed. A step whose transaction is sent is never executed again,
+    /// which would send another transaction.
+    pub fn is_transient(&self) -> bool {
+        self.tx_hash().is_none() && self.kind().is_transient()
+    }
+
+    /// Hash of the transaction which is sent before the step fails, if any
+    pub fn tx_hash(&self) -> Option<TxHash> {
+        match self {
+            StepError::TransactionPending { tx_hash }
+            | StepError::TransactionCancelled { tx_hash }
+            | StepError::TransactionUnconfirmed { tx_hash, .. }
+            | StepError::TransactionReverted { tx_hash }
+            | StepError::TransactionNotRecorded { tx_hash, .. }
+            | StepError::TransactionNotBroadcast { tx_hash, .. } => Some(*tx_hash),
+            _ => None,
+        }
+    }
+
+    /// Signed transaction which must be broadcast again since broadcasting it failed, if any
+    pub fn raw_tx(&self) -> Option<&Bytes> {
+        match self {
+            StepError::TransactionNotBroadcast { raw_tx, .. } => Some(raw_tx),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of a [`StepError`], it tells whether the failure is transient
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepErrorKind {
+    /// The RPC provider is unreachable or the connection is broken
+    Transport,
+    /// The RPC provider rejects the request since it exceeds the rate limit
+    RateLimit,
+    /// The call or the transaction reverts
+    Revert,
+    /// The response or the return data cannot be decoded
+    Decode,
+    /// The RPC provider or the signer rejects the request for another reason
+    Rejected,
+    /// The step doesn't finish before its deadline
+    Timeout,
+    /// The run is cancelled before the step finishes
+    Cancelled,
+}
+
+impl StepErrorKind {
+    /// Classify an error returned by a [`Middleware`](ethers::providers::Middleware)
+    pub fn from_middleware_error<E: MiddlewareError>(error: &E) -> Self {
+        if let Some(error_response) = error.as_error_response() {
+            return Self::from_error_response(error_response);
+        }
+        if error.is_serde_error() {
+            return StepErrorKind::Decode;
+        }
+        if is_rate_limit_message(&error.to_string()) {
+            return StepErrorKind::RateLimit;
+        }
+        StepErrorKind::Transport
+    }
+
+    /// Classify a JSON-RPC error response
+    pub fn from_error_response(error_response: &JsonRpcError) -> Self {
+        if RATE_LIMIT_ERROR_CODES.contains(&error_response.code)
+            || is_rate_limit_message(&error_response.message)
+        {
+            StepErrorKind::RateLimit
+        } else if error_response.is_revert() {
+            StepErrorKind::Revert
+        } else {
+            StepErrorKind::Rejected
+        }
+    }
+
+    /// Transport and rate limit failures are transient, the others fail again with the same request
+    pub fn is_transient(&self) -> bool {
+        matches!(self, StepErrorKind::Transport | StepErrorKind::RateLimit)
+    }
+}
+
+impl fmt::Display for StepErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            StepErrorKind::Transport => "transport",
+            StepErrorKind::RateLimit => "rate limit",
+            StepErrorKind::Revert => "revert",
+            StepErrorKind::Decode => "decode",
+            StepErrorKind::Rejected => "rejected",
+            StepErrorKind::Timeout => "timeout",
+            StepErrorKind::Cancelled => "cancelled",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// Whether the RPC provider rejects a transaction since it is already in its mempool, which means it is sent
+pub fn is_already_known(error_response: &JsonRpcError) -> bool {
+    let message = error_response.message.to_lowercase();
+    ALREADY_KNOWN_MESSAGES
+        .iter()
+        .any(|known_message| message.contains(known_message))
+}
+
+fn is_rate_limit_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("rate limit") || message.contains("too many requests")
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::providers::ProviderError;
+
+    use super::*;
+
+    fn error_response(code: i64, message: &str) -> JsonRpcError {
+        JsonRpcError {
+            code,
+            message: message.to_string(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn can_classify_step_errors() {
+        assert_eq!(
+            StepErrorKind::from_error_response(&error_response(3, "execution reverted: STF")),
+            StepErrorKind::Revert
+        );
+        assert_eq!(
+            StepErrorKind::from_error_response(&error_response(-32005, "limit exceeded")),
+            StepErrorKind::RateLimit
+        );
+        assert_eq!(
+            StepErrorKind::from_error_response(&error_response(-32000, "insufficient funds")),
+            StepErrorKind::Rejected
+        );
+
+        assert_eq!(
+            StepErrorKind::from_middleware_error(&ProviderError::CustomError(
+                "HTTP status 429 Too Many Requests".to_string()
+            )),
+            StepErrorKind::RateLimit
+        );
+        assert_eq!(
+            StepErrorKind::from_middleware_error(&ProviderError::CustomError(
+                "connection refused".to_string()
+            )),
+            StepErrorKind::Transport
+        );
+
+        let step_error = StepError::CallContractError {
+            kind: StepErrorKind::Revert,
+            message: "execution reverted".to_string(),
+        };
+        assert!(!step_error.is_transient());
+        assert_eq!(
+            step_error.to_string(),
+            "call contract error (revert): execution reverted"
+        );
+
+        let step_error = StepError::TransactionUnconfirmed {
+            tx_hash: TxHash::zero(),
+            kind: StepErrorKind::Transport,
+            message: "connection refused".to_string(),
+        };
+        assert_eq!(step_error.kind(), StepErrorKind::Transport);
+        assert!(!step_error.is_transient());
+        assert_eq!(step_error.tx_hash(), Some(TxHash::zero()));
+
+        let step_error = StepError::TransactionNotBroadcast {
+            tx_hash: TxHash::zero(),
+            raw_tx: Bytes::from_static(&[1]),
+            kind: StepErrorKind::Transport,
+            message: "connection reset".to_string(),
+        };
+        assert!(!step_error.is_transient());
+        assert_eq!(step_error.raw_tx(), Some(&Bytes::from_static(&[1])));
+
+        assert!(is_already_known(&error_response(-32000, "already known")));
+        assert!(is_already_known(&error_response(
+            -32010,
+            "Transaction with the same hash was already imported. (Known transaction)"
+        )));
+        assert!(!is_already_known(&error_response(-32000, "nonce too low")));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{CallContractOutput, SendTransactionOutput};
+use crate::{CallContractOutput, DeployContractOutput, SendTransactionOutput};
 use derive_more::{From, Unwrap};
 
 #[non_exhaustive]
@@ -6,5 +6,6 @@ use derive_more::{From, Unwrap};
 pub enum StepOutput {
     CallContractOutput(CallContractOutput),
     SendTransactionOutput(SendTransactionOutput),
+    DeployContractOutput(DeployContractOutput),
     None,
 }
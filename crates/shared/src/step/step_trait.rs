@@ -1,9 +1,21 @@
-use crate::{StepError, StepOutput};
+use crate::{SendTransactionOutput, StepError, StepOutput};
 use async_trait::async_trait;
+use ethers::types::TransactionReceipt;
 
 /// A step can be executed individually and can be chained to make a pipeline in a job.
 #[async_trait]
 pub trait Step: Send + Sync {
     /// Execute the step and return result
     async fn execute(&self) -> Result<StepOutput, StepError>;
+
+    /// Return the output of the step from the receipt of its transaction, which is sent by this or an earlier
+    /// execution. Fail if the transaction reverted.
+    fn output_of_receipt(&self, tx_receipt: TransactionReceipt) -> Result<StepOutput, StepError> {
+        if tx_receipt.status == Some(0.into()) {
+            return Err(StepError::TransactionReverted {
+                tx_hash: tx_receipt.transaction_hash,
+            });
+        }
+        Ok(SendTransactionOutput::TransactionReceipt(Some(tx_receipt)).into())
+    }
 }
@@ -0,0 +1,41 @@
+use std::{error::Error, fmt, future::Future, sync::Arc};
+
+use ethers::types::TxHash;
+use futures::future::{BoxFuture, FutureExt};
+
+/// Error returned by a [`TransactionSentHook`]
+pub type TransactionSentHookError = Box<dyn Error + Send + Sync>;
+
+/// Hook called with the hash of a transaction as soon as it may be sent, before waiting for its receipt, so the caller
+/// can record the pending transaction even if the step fails or the process stops while waiting. The step fails if
+/// the hook fails, since the transaction could be sent again by a run which doesn't know it.
+#[derive(Clone)]
+pub struct TransactionSentHook(
+    Arc<dyn Fn(TxHash) -> BoxFuture<'static, Result<(), TransactionSentHookError>> + Send + Sync>,
+);
+
+impl TransactionSentHook {
+    pub fn new<F, Fut, E>(hook: F) -> Self
+    where
+        F: Fn(TxHash) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<TransactionSentHookError>,
+    {
+        Self(Arc::new(move |tx_hash| {
+            hook(tx_hash)
+                .map(|result| result.map_err(Into::into))
+                .boxed()
+        }))
+    }
+
+    /// Call the hook with the hash of the sent transaction
+    pub async fn call(&self, tx_hash: TxHash) -> Result<(), TransactionSentHookError> {
+        (self.0)(tx_hash).await
+    }
+}
+
+impl fmt::Debug for TransactionSentHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TransactionSentHook").finish()
+    }
+}